@@ -5,6 +5,7 @@ use cursor_hero_agent_types::prelude::*;
 use cursor_hero_character_types::prelude::*;
 use cursor_hero_movement_tool_types::prelude::*;
 use cursor_hero_toolbelt_types::prelude::*;
+use cursor_hero_tools::prelude::*;
 use leafwing_input_manager::axislike::DualAxisData;
 use leafwing_input_manager::prelude::*;
 
@@ -21,7 +22,11 @@ impl Plugin for AgentMovementPlugin {
 fn agent_tool_movement(
     character_query: Query<(&Children, &Transform), (With<Character>, With<Agent>)>,
     toolbelt_query: Query<&Children, With<Toolbelt>>,
-    mut tool_query: Query<&mut ActionState<MovementToolAction>>,
+    // tool calls take over until they finish
+    mut tool_query: Query<
+        &mut ActionState<MovementToolAction>,
+        Without<HeldToolActions<MovementToolAction>>,
+    >,
     time: Res<Time>,
 ) {
     for character in character_query.iter() {
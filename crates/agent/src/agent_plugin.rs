@@ -1,5 +1,6 @@
 use crate::agent_movement_plugin::AgentMovementPlugin;
 use crate::agent_spawning_plugin::AgentSpawningPlugin;
+use crate::agent_tool_call_plugin::AgentToolCallPlugin;
 use crate::insert_agent_toolbelt::InsertAgentToolbeltPlugin;
 use bevy::prelude::*;
 
@@ -10,5 +11,6 @@ impl Plugin for AgentPlugin {
         app.add_plugins(InsertAgentToolbeltPlugin);
         app.add_plugins(AgentSpawningPlugin);
         app.add_plugins(AgentMovementPlugin);
+        app.add_plugins(AgentToolCallPlugin);
     }
 }
@@ -18,51 +18,64 @@ impl Plugin for AgentSpawningPlugin {
     }
 }
 
+/// Horizontal distance between agents spawned into the same environment
+const AGENT_SPACING: f32 = 150.0;
+
 fn spawn_agent(
     mut commands: Commands,
     mut environment_events: EventReader<PopulateEnvironmentEvent>,
     environment_query: Query<&AgentEnvironment>,
+    roster: Res<AgentRoster>,
     asset_server: Res<AssetServer>,
 ) {
     for event in environment_events.read() {
         if !environment_query.contains(event.environment_id) {
             continue;
         }
-        info!("Spawning agent for environment {:?}", event.environment_id);
+        info!(
+            "Spawning {} agents for environment {:?}",
+            roster.personas.len(),
+            event.environment_id
+        );
         commands
             .entity(event.environment_id)
             .with_children(|parent| {
-                let spawn_position = Vec2::new(1920.0, 1080.0).neg_y() / 2.0;
-                parent.spawn((
-                    SpriteBundle {
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(64.0, 64.0)),
+                for (i, persona) in roster.personas.iter().enumerate() {
+                    let spawn_position = Vec2::new(1920.0, 1080.0).neg_y() / 2.0
+                        + Vec2::X * AGENT_SPACING * i as f32;
+                    parent.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(Vec2::new(64.0, 64.0)),
+                                ..default()
+                            },
+                            texture: asset_server.load(AgentAppearance::Default.get_texture_path()),
+                            transform: Transform::from_translation(spawn_position.extend(80.0)),
+                            ..default()
+                        },
+                        Character,
+                        AgentCharacter,
+                        TrackedEnvironment {
+                            environment_id: event.environment_id,
+                        },
+                        Name::new(format!("Character - (Agent) {}", persona.name)),
+                        FloatyName {
+                            text: persona.name.clone(),
+                            vertical_offset: 40.0,
+                            appearance: NametagAppearance::Character,
+                        },
+                        Agent,
+                        persona.clone(),
+                        RigidBody::Dynamic,
+                        ObservationBuffer {
+                            log_level: ObservationLogLevel::All,
                             ..default()
                         },
-                        texture: asset_server.load(AgentAppearance::Default.get_texture_path()),
-                        transform: Transform::from_translation(spawn_position.extend(80.0)),
-                        ..default()
-                    },
-                    Character,
-                    AgentCharacter,
-                    TrackedEnvironment {
-                        environment_id: event.environment_id,
-                    },
-                    Name::new("Character - (Agent) Ithia Tig"),
-                    FloatyName {
-                        text: "Ithia Tig".to_string(),
-                        vertical_offset: 40.0,
-                        appearance: NametagAppearance::Character,
-                    },
-                    Agent,
-                    RigidBody::Dynamic,
-                    ObservationBuffer {
-                        log_level: ObservationLogLevel::All,
-                        ..default()
-                    },
-                    Collider::capsule(25.0, 12.5),
-                    MovementDamping { factor: 0.90 },
-                ));
+                        ObservationRetention::default(),
+                        Collider::capsule(25.0, 12.5),
+                        MovementDamping { factor: 0.90 },
+                    ));
+                }
             });
     }
 }
@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use cursor_hero_agent_types::prelude::*;
+use cursor_hero_character_types::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_toolbelt_types::prelude::*;
+
+/// Lets agents act through their toolbelt by turning the tool calls of their inference sessions into [`ToolCallEvent`]s.
+pub struct AgentToolCallPlugin;
+
+impl Plugin for AgentToolCallPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, handle_tool_calls);
+    }
+}
+
+/// How long directional actions like moving are held for
+const DIRECTIONAL_HOLD: Duration = Duration::from_secs(1);
+
+#[allow(clippy::type_complexity)]
+fn handle_tool_calls(
+    mut commands: Commands,
+    mut inference_events: EventReader<TextInferenceEvent>,
+    mut tool_call_events: EventWriter<ToolCallEvent>,
+    mut activation_events: EventWriter<ToolActivationEvent>,
+    character_query: Query<&Children, (With<Character>, With<Agent>)>,
+    toolbelt_query: Query<&Children, With<Toolbelt>>,
+    tool_query: Query<(Entity, &Tool, Option<&ToolCallActions>, Option<&ActiveTool>)>,
+) {
+    for event in inference_events.read() {
+        let TextInferenceEvent::ToolCalls { session_id, calls } = event else {
+            continue;
+        };
+        // sessions of agents are keyed by their character
+        let Ok(character_children) = character_query.get(*session_id) else {
+            continue;
+        };
+        let tools = character_children
+            .iter()
+            .filter_map(|child| toolbelt_query.get(*child).ok())
+            .flat_map(|toolbelt_children| tool_query.iter_many(toolbelt_children))
+            .collect::<Vec<_>>();
+        for call in calls {
+            let Some((tool_id, tool, actions, active)) = tools
+                .iter()
+                .find(|(_, tool, _, _)| function_name(&tool.name) == call.function_name)
+            else {
+                warn!(
+                    "Agent {:?} called unknown tool {:?}",
+                    session_id, call.function_name
+                );
+                continue;
+            };
+            let Some(actions) = actions else {
+                warn!(
+                    "Agent {:?} called {}, which does not take tool calls",
+                    session_id, tool.name
+                );
+                continue;
+            };
+            if !actions
+                .0
+                .iter()
+                .any(|action| action.eq_ignore_ascii_case(&call.action))
+            {
+                warn!(
+                    "Agent {:?} called {} with unknown action {:?}, it has {:?}",
+                    session_id, tool.name, call.action, actions.0
+                );
+                continue;
+            }
+            if active.is_none() {
+                // inactive tools ignore their actions
+                info!("Activating {} for tool call", tool.name);
+                commands.entity(*tool_id).insert(ActiveTool);
+                activation_events.send(ToolActivationEvent::Activate(*tool_id));
+            }
+            info!(
+                "Agent {:?} calling {} {} {:?}",
+                session_id, tool.name, call.action, call.direction
+            );
+            tool_call_events.send(ToolCallEvent {
+                tool_id: *tool_id,
+                action: call.action.clone(),
+                direction: call.direction,
+                hold: match call.direction {
+                    Some(_) => DIRECTIONAL_HOLD,
+                    None => Duration::ZERO,
+                },
+            });
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod agent_movement_plugin;
 pub mod agent_plugin;
 pub mod agent_spawning_plugin;
+pub mod agent_tool_call_plugin;
 pub mod insert_agent_toolbelt;
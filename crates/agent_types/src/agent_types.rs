@@ -14,3 +14,73 @@ impl AgentAppearance {
         }
     }
 }
+
+/// Who an agent is, used to build its prompts and to tell it apart from the other agents in its environment
+#[derive(Component, Reflect, Debug, Clone, PartialEq, Eq)]
+pub struct AgentPersona {
+    /// How the agent is addressed by others, must be unique among the agents of an environment
+    pub name: String,
+    /// Personality of the agent, rendered by the `persona` prompt template
+    pub system_prompt: String,
+    /// File stem of the prompt template rendered as the system message, `system` for `prompt_templates/system.txt`
+    pub template: String,
+    /// Text to speech voice, unset for the default voice
+    pub voice: Option<String>,
+    /// Overrides the configured inference model for this agent
+    pub model: Option<String>,
+}
+
+impl AgentPersona {
+    pub fn new(name: impl Into<String>, system_prompt: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            system_prompt: system_prompt.into(),
+            template: "system".to_string(),
+            voice: None,
+            model: None,
+        }
+    }
+
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = template.into();
+        self
+    }
+
+    pub fn with_voice(mut self, voice: impl Into<String>) -> Self {
+        self.voice = Some(voice.into());
+        self
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn ithia_tig() -> Self {
+        Self::new(
+            "Ithia Tig",
+            "Ithia Tig is interested in assisting in adding new functionality to the environment in which they are constrained.
+
+Ithia Tig's replies are short and curious.
+
+Ithia Tig should refrain from platitudes like \"have a fantastic day\", since that implies an end to the conversation which is a bad thing.",
+        )
+    }
+}
+
+/// The agents spawned into every agent environment, and the person they are talking with
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct AgentRoster {
+    pub user_name: String,
+    pub personas: Vec<AgentPersona>,
+}
+
+impl Default for AgentRoster {
+    fn default() -> Self {
+        Self {
+            user_name: "Tume Eena".to_string(),
+            personas: vec![AgentPersona::ithia_tig()],
+        }
+    }
+}
@@ -7,5 +7,8 @@ pub struct AgentTypesPlugin;
 impl Plugin for AgentTypesPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Agent>();
+        app.register_type::<AgentPersona>();
+        app.register_type::<AgentRoster>();
+        app.init_resource::<AgentRoster>();
     }
 }
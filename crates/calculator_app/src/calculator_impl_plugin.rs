@@ -6,6 +6,7 @@ use cursor_hero_calculator_app_types::calculator_app_types::CalculatorDisplay;
 use cursor_hero_calculator_app_types::calculator_app_types::CalculatorElementKind;
 use cursor_hero_calculator_app_types::calculator_app_types::CalculatorExpression;
 use cursor_hero_calculator_app_types::calculator_app_types::CalculatorHiddenState;
+use cursor_hero_calculator_app_types::calculator_app_types::CalculatorResultEvent;
 use cursor_hero_cursor_types::cursor_click_types::ClickEvent;
 use cursor_hero_cursor_types::cursor_click_types::Way;
 use cursor_hero_cursor_types::cursor_types::Cursor;
@@ -82,6 +83,7 @@ fn handle_clicks(
         (With<CalculatorDisplay>, Without<CalculatorExpression>),
     >,
     mut text_query: Query<&mut Text>,
+    mut result_events: EventWriter<CalculatorResultEvent>,
 ) {
     for event in click_events.read() {
         // Only handle left click events
@@ -185,6 +187,17 @@ fn handle_clicks(
             &mut expression.value,
             &mut value.value,
         );
+
+        if *button_kind == CalculatorElementKind::EqualsButton {
+            let event = CalculatorResultEvent {
+                calculator_id,
+                environment_id: calculator_environment.environment_id,
+                expression: expression.value.clone(),
+                value: value.value.clone(),
+            };
+            debug!("Sending event {:?}", event);
+            result_events.send(event);
+        }
     }
 }
 
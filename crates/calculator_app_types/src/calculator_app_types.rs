@@ -394,6 +394,15 @@ pub struct SpawnCalculatorRequestEvent {
     pub position: Vec2,
 }
 
+/// Sent when the equals button of a calculator is clicked
+#[derive(Event, Debug, Reflect, Clone)]
+pub struct CalculatorResultEvent {
+    pub calculator_id: Entity,
+    pub environment_id: Entity,
+    pub expression: String,
+    pub value: String,
+}
+
 #[derive(Component, Debug, Reflect, Default, Clone, PartialEq)]
 pub struct Calculator {
     pub hidden_state: CalculatorHiddenState,
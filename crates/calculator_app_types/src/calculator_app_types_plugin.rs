@@ -7,6 +7,8 @@ impl Plugin for CalculatorAppTypesPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnCalculatorRequestEvent>();
         app.register_type::<SpawnCalculatorRequestEvent>();
+        app.add_event::<CalculatorResultEvent>();
+        app.register_type::<CalculatorResultEvent>();
         app.register_type::<Calculator>();
         app.register_type::<CalculatorElementKind>();
         app.register_type::<CalculatorStartMenuPanelButton>();
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 use bevy::text::Text2dBounds;
+use bevy::utils::HashMap;
+use bevy::utils::HashSet;
 use bevy_xpbd_2d::prelude::*;
 use cursor_hero_character_types::prelude::*;
 use cursor_hero_chat_types::prelude::*;
@@ -146,73 +148,148 @@ fn handle_chat_events(
     mut events: EventReader<ChatEvent>,
     mut commands: Commands,
     character_query: Query<&GlobalTransform, With<Character>>,
+    mut streaming_query: Query<(Entity, &StreamingChatBubble, &Children, &mut ChatBubble)>,
+    mut text_query: Query<&mut Text>,
 ) {
+    // bubbles spawned or finished by commands aren't visible to the queries until the next frame
+    let mut new_streams = HashMap::<Entity, String>::new();
+    let mut finished = HashSet::<Entity>::new();
     for event in events.read() {
-        match event {
-            ChatEvent::Chat {
-                character_id,
-                message,
-            } => {
-                if let Ok(character) = character_query.get(*character_id) {
-                    let character_transform = character;
-                    info!(
-                        "Creating chat bubble for character {:?} at position {:?}",
-                        character_id,
-                        character_transform.translation()
-                    );
-                    let size = Vec2::new(300.0, 100.0);
-                    let resolution = 3.0;
-                    let padding = Vec2::new(10.0, 10.0);
-                    let mut transform = character_transform.compute_transform();
-                    transform.translation -= Vec3::new(0.0, 100.0, 10.0);
-                    commands
-                        .spawn((
-                            SpriteBundle {
-                                sprite: Sprite {
-                                    color: Color::BLACK,
-                                    custom_size: Some(size),
-                                    ..default()
-                                },
-                                transform,
-                                ..default()
-                            },
-                            ChatBubble {
-                                lifetime: Timer::from_seconds(25.0, TimerMode::Once),
-                            },
-                            RigidBody::Dynamic,
-                            LinearVelocity(Vec2::new(0.0, -30.0)),
-                            Collider::cuboid(size.x, size.y),
-                            Name::new("Chat Bubble"),
-                        ))
-                        .with_children(|parent| {
-                            parent.spawn((Text2dBundle {
-                                text: Text::from_section(
-                                    message.clone(),
-                                    TextStyle {
-                                        font_size: 20.0 * resolution,
-                                        color: Color::WHITE,
-                                        ..default()
-                                    },
-                                ),
-                                text_2d_bounds: Text2dBounds {
-                                    size: size * resolution - padding,
-                                },
-                                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0))
-                                    .with_scale(Vec3::new(1.0 / resolution, 1.0 / resolution, 1.0)),
-                                ..default()
-                            },));
-                        });
-                } else {
-                    warn!(
-                        "Character {:?} not found? Skipping chat bubble creation.",
-                        character_id
-                    );
+        let character_id = match event {
+            ChatEvent::Chat { character_id, .. }
+            | ChatEvent::Partial { character_id, .. }
+            | ChatEvent::Abandoned { character_id } => *character_id,
+        };
+        let streaming = streaming_query
+            .iter_mut()
+            .find(|(bubble_id, streaming, _, _)| {
+                streaming.character_id == character_id && !finished.contains(bubble_id)
+            });
+        match (event, streaming) {
+            (ChatEvent::Chat { message, .. }, _) if new_streams.contains_key(&character_id) => {
+                new_streams.remove(&character_id);
+                spawn_chat_bubble(&mut commands, &character_query, character_id, message);
+            }
+            (ChatEvent::Chat { message, .. }, Some((bubble_id, _, children, mut bubble))) => {
+                // the pieces of the message are already on display, the whole message replaces them
+                debug!(
+                    "Finishing streamed chat bubble for character {:?}",
+                    character_id
+                );
+                let mut texts = text_query.iter_many_mut(children);
+                while let Some(mut text) = texts.fetch_next() {
+                    text.sections[0].value.clone_from(message);
                 }
+                bubble.lifetime.reset();
+                commands.entity(bubble_id).remove::<StreamingChatBubble>();
+                finished.insert(bubble_id);
             }
+            (ChatEvent::Chat { message, .. }, None) => {
+                spawn_chat_bubble(&mut commands, &character_query, character_id, message);
+            }
+            (ChatEvent::Partial { delta, .. }, _) if new_streams.contains_key(&character_id) => {
+                if let Some(text) = new_streams.get_mut(&character_id) {
+                    text.push_str(delta);
+                }
+            }
+            (ChatEvent::Partial { delta, .. }, Some((_, _, children, _))) => {
+                let mut texts = text_query.iter_many_mut(children);
+                while let Some(mut text) = texts.fetch_next() {
+                    text.sections[0].value.push_str(delta);
+                }
+            }
+            (ChatEvent::Partial { delta, .. }, None) => {
+                new_streams.insert(character_id, delta.clone());
+            }
+            // what was written so far stays up until the bubble expires
+            (ChatEvent::Abandoned { .. }, _) if new_streams.contains_key(&character_id) => {
+                if let Some(text) = new_streams.remove(&character_id) {
+                    spawn_chat_bubble(&mut commands, &character_query, character_id, &text);
+                }
+            }
+            (ChatEvent::Abandoned { .. }, Some((bubble_id, _, _, _))) => {
+                commands.entity(bubble_id).remove::<StreamingChatBubble>();
+                finished.insert(bubble_id);
+            }
+            (ChatEvent::Abandoned { .. }, None) => {}
+        }
+    }
+    for (character_id, text) in new_streams {
+        if let Some(bubble_id) =
+            spawn_chat_bubble(&mut commands, &character_query, character_id, &text)
+        {
+            commands
+                .entity(bubble_id)
+                .insert(StreamingChatBubble { character_id });
         }
     }
 }
 
+fn spawn_chat_bubble(
+    commands: &mut Commands,
+    character_query: &Query<&GlobalTransform, With<Character>>,
+    character_id: Entity,
+    message: &str,
+) -> Option<Entity> {
+    let Ok(character) = character_query.get(character_id) else {
+        warn!(
+            "Character {:?} not found? Skipping chat bubble creation.",
+            character_id
+        );
+        return None;
+    };
+    let character_transform = character;
+    info!(
+        "Creating chat bubble for character {:?} at position {:?}",
+        character_id,
+        character_transform.translation()
+    );
+    let size = Vec2::new(300.0, 100.0);
+    let resolution = 3.0;
+    let padding = Vec2::new(10.0, 10.0);
+    let mut transform = character_transform.compute_transform();
+    transform.translation -= Vec3::new(0.0, 100.0, 10.0);
+    let bubble_id = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::BLACK,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform,
+                ..default()
+            },
+            ChatBubble {
+                lifetime: Timer::from_seconds(25.0, TimerMode::Once),
+            },
+            RigidBody::Dynamic,
+            LinearVelocity(Vec2::new(0.0, -30.0)),
+            Collider::cuboid(size.x, size.y),
+            Name::new("Chat Bubble"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text2dBundle {
+                text: Text::from_section(
+                    message,
+                    TextStyle {
+                        font_size: 20.0 * resolution,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                text_2d_bounds: Text2dBounds {
+                    size: size * resolution - padding,
+                },
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 1.0))
+                    .with_scale(Vec3::new(1.0 / resolution, 1.0 / resolution, 1.0)),
+                ..default()
+            },));
+        })
+        .id();
+    Some(bubble_id)
+}
+
 fn chat_bubble_lifetime(
     mut commands: Commands,
     time: Res<Time>,
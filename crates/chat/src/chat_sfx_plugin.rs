@@ -17,7 +17,9 @@ fn play_sound_for_new_chat_messages(
     asset_server: Res<AssetServer>,
 ) {
     for event in events.read() {
-        let ChatEvent::Chat { character_id, .. } = event;
+        let ChatEvent::Chat { character_id, .. } = event else {
+            continue;
+        };
         let Ok(character) = character_query.get(*character_id) else {
             warn!("Character not found for event {:?}", event);
             continue;
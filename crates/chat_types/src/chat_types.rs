@@ -70,6 +70,10 @@ pub enum ChatEvent {
         character_id: Entity,
         message: String,
     },
+    /// A piece of a message that is still being written, the whole message follows as a [`ChatEvent::Chat`]
+    Partial { character_id: Entity, delta: String },
+    /// The message being written was given up on, no [`ChatEvent::Chat`] follows for its pieces
+    Abandoned { character_id: Entity },
 }
 
 #[derive(Event, PartialEq, Eq, Clone, Hash, Debug, Reflect)]
@@ -98,3 +102,9 @@ pub struct ChatInput;
 pub struct ChatBubble {
     pub lifetime: Timer,
 }
+
+/// A chat bubble that is still receiving [`ChatEvent::Partial`]s of the character's message
+#[derive(Component, Reflect, Debug)]
+pub struct StreamingChatBubble {
+    pub character_id: Entity,
+}
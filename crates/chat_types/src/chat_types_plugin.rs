@@ -7,6 +7,7 @@ impl Plugin for ChatTypesPlugin {
         app.register_type::<ChatWheelTool>();
         app.register_type::<ChatTool>();
         app.register_type::<ChatBubble>();
+        app.register_type::<StreamingChatBubble>();
         app.register_type::<ChatInput>();
         app.add_event::<ChatEvent>();
         app.add_event::<ChatInputEvent>();
@@ -49,7 +49,10 @@ fn track(
         }
         if let Some(environment_id) = environment_ids.iter().next() {
             if let Some(mut tag) = thing_environment_tag {
-                tag.environment_id = *environment_id;
+                // only real moves count as changes
+                if tag.environment_id != *environment_id {
+                    tag.environment_id = *environment_id;
+                }
             } else {
                 commands.entity(thing_id).insert(TrackedEnvironment {
                     environment_id: *environment_id,
@@ -1,21 +1,31 @@
 use bevy::prelude::*;
-use crossbeam_channel::bounded;
-use crossbeam_channel::Receiver;
-use crossbeam_channel::Sender;
 use cursor_hero_inference_types::prelude::*;
-use std::thread;
+use cursor_hero_worker::prelude::anyhow::Error;
+use cursor_hero_worker::prelude::send_gamebound;
+use cursor_hero_worker::prelude::Sender;
+use cursor_hero_worker::prelude::WorkerConfig;
+use cursor_hero_worker::prelude::WorkerFuture;
+use cursor_hero_worker::prelude::WorkerPlugin;
 
 pub struct GladosTtsInferencePlugin;
 
 impl Plugin for GladosTtsInferencePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, create_worker_thread);
+        app.add_plugins(WorkerPlugin {
+            config: WorkerConfig::<ThreadboundMessage, GameboundMessage, (), _, _, _> {
+                name: "glados_tts_inference".to_string(),
+                handle_threadbound_message_async: Some(handle_threadbound_message),
+                // lines are spoken in the order they were requested
+                max_in_flight: 1,
+                ..default()
+            },
+        });
         app.add_systems(Update, bridge_requests);
         app.add_systems(Update, bridge_responses);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Event)]
 enum GameboundMessage {
     Response {
         session_id: Entity,
@@ -24,7 +34,7 @@ enum GameboundMessage {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Event)]
 enum ThreadboundMessage {
     Generate {
         session_id: Entity,
@@ -32,84 +42,68 @@ enum ThreadboundMessage {
     },
 }
 
-#[derive(Resource)]
-struct Bridge {
-    pub sender: Sender<ThreadboundMessage>,
-    pub receiver: Receiver<GameboundMessage>,
-}
-
-fn create_worker_thread(mut commands: Commands) {
-    let (game_tx, game_rx) = bounded::<_>(10);
-    let (thread_tx, thread_rx) = bounded::<_>(10);
-    commands.insert_resource(Bridge {
-        sender: thread_tx,
-        receiver: game_rx,
-    });
-
-    let game_tx_clone = game_tx.clone();
-    thread::Builder::new()
-        .name("GLaDOSTTS thread".to_string())
-        .spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let game_tx = game_tx_clone;
-                loop {
-                    let msg = match thread_rx.recv() {
-                        Ok(msg) => msg,
-                        Err(_) => {
-                            error!("Threadbound channel failure, exiting");
-                            break;
-                        }
-                    };
-                    match msg {
-                        ThreadboundMessage::Generate { session_id, prompt } => {
-                            debug!("Worker received generate request for session {:?}, generating response", session_id);
-                            let prompt_str = match &prompt {
-                                SpeechPrompt::Raw { content } => content,
-                            };
-                            let data = match crate::glados_tts::generate(prompt_str).await {
-                                Ok(data) => data,
-                                Err(e) => {
-                                    error!("Failed to generate TTS: {:?}", e);
-                                    continue;
-                                }
-                            };
-                            if let Err(e) = game_tx.send(GameboundMessage::Response {
-                                session_id,
-                                prompt: prompt.clone(),
-                                response: data,
-                            }) {
-                                error!("Gamebound channel failure, exiting: {:?}", e);
-                                break;
-                            }
+fn handle_threadbound_message<'a>(
+    msg: &'a ThreadboundMessage,
+    reply_tx: &'a Sender<GameboundMessage>,
+    _state: &'a mut (),
+) -> WorkerFuture<'a, Error> {
+    Box::pin(async move {
+        match msg {
+            ThreadboundMessage::Generate { session_id, prompt } => {
+                debug!(
+                    "Worker received generate request for session {:?}, generating response",
+                    session_id
+                );
+                let prompt_str = match prompt {
+                    SpeechPrompt::Raw { content, voice } => {
+                        if let Some(voice) = voice {
+                            debug!("GLaDOS TTS only has one voice, ignoring {:?}", voice);
                         }
+                        content
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(50));
-                }
-            });
-        })
-        .expect("Failed to spawn thread");
+                };
+                let data = match crate::glados_tts::generate(prompt_str).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        error!("Failed to generate TTS: {:?}", e);
+                        return Ok(());
+                    }
+                };
+                let reply = GameboundMessage::Response {
+                    session_id: *session_id,
+                    prompt: prompt.clone(),
+                    response: data,
+                };
+                send_gamebound(reply_tx, reply).await?;
+            }
+        }
+        Ok(())
+    })
 }
 
-fn bridge_requests(bridge: ResMut<Bridge>, mut events: EventReader<SpeechInferenceEvent>) {
+fn bridge_requests(
+    mut bridge: EventWriter<ThreadboundMessage>,
+    mut events: EventReader<SpeechInferenceEvent>,
+) {
     for event in events.read() {
         if let SpeechInferenceEvent::Request { session_id, prompt } = event {
             debug!(
                 "Received generate request for session {:?}, sending over bridge to worker thread",
                 session_id
             );
-            if let Err(e) = bridge.sender.send(ThreadboundMessage::Generate {
+            bridge.send(ThreadboundMessage::Generate {
                 session_id: *session_id,
                 prompt: prompt.clone(),
-            }) {
-                error!("Threadbound channel failure: {:?}", e);
-            }
+            });
         }
     }
 }
 
-fn bridge_responses(bridge: ResMut<Bridge>, mut events: EventWriter<SpeechInferenceEvent>) {
-    for msg in bridge.receiver.try_iter() {
+fn bridge_responses(
+    mut bridge: EventReader<GameboundMessage>,
+    mut events: EventWriter<SpeechInferenceEvent>,
+) {
+    for msg in bridge.read() {
         match msg {
             GameboundMessage::Response {
                 session_id,
@@ -121,9 +115,9 @@ fn bridge_responses(bridge: ResMut<Bridge>, mut events: EventWriter<SpeechInfere
                     session_id
                 );
                 events.send(SpeechInferenceEvent::Response {
-                    session_id,
-                    prompt,
-                    wav: response,
+                    session_id: *session_id,
+                    prompt: prompt.clone(),
+                    wav: response.clone(),
                 });
             }
         }
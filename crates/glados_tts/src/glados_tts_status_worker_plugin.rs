@@ -1,91 +1,70 @@
 use bevy::prelude::*;
-use crossbeam_channel::bounded;
-use crossbeam_channel::Receiver;
-use crossbeam_channel::Sender;
 use cursor_hero_glados_tts_types::prelude::*;
-use std::thread;
+use cursor_hero_worker::prelude::anyhow::Error;
+use cursor_hero_worker::prelude::send_gamebound;
+use cursor_hero_worker::prelude::Sender;
+use cursor_hero_worker::prelude::WorkerConfig;
+use cursor_hero_worker::prelude::WorkerFuture;
+use cursor_hero_worker::prelude::WorkerPlugin;
 
 pub struct GladosTtsStatusWorkerPlugin;
 
 impl Plugin for GladosTtsStatusWorkerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, create_worker_thread);
+        app.add_plugins(WorkerPlugin {
+            config: WorkerConfig::<ThreadboundMessage, GameboundMessage, (), _, _, _> {
+                name: "glados_tts_status".to_string(),
+                handle_threadbound_message_async: Some(handle_threadbound_message),
+                ..default()
+            },
+        });
         app.add_systems(Update, events_to_bridge);
         app.add_systems(Update, bridge_to_events);
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Event)]
 enum GameboundMessage {
     Pong { status: GladosTtsStatus },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Event)]
 enum ThreadboundMessage {
     Ping,
     Startup,
 }
 
-#[derive(Resource)]
-struct Bridge {
-    pub sender: Sender<ThreadboundMessage>,
-    pub receiver: Receiver<GameboundMessage>,
-}
-
-fn create_worker_thread(mut commands: Commands) {
-    let (game_tx, game_rx) = bounded::<_>(10);
-    let (thread_tx, thread_rx) = bounded::<_>(10);
-    commands.insert_resource(Bridge {
-        sender: thread_tx,
-        receiver: game_rx,
-    });
-
-    let game_tx_clone = game_tx.clone();
-    thread::Builder::new()
-        .name("GLaDOS TTS status thread".to_string())
-        .spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let game_tx = game_tx_clone;
-                loop {
-                    let msg = match thread_rx.recv() {
-                        Ok(msg) => msg,
-                        Err(_) => {
-                            error!("Threadbound channel failure, exiting");
-                            break;
-                        }
-                    };
-                    match msg {
-                        ThreadboundMessage::Ping => {
-                            debug!("Worker received ping request, pinging GladosTts API");
-                            let status = match crate::glados_tts::get_status().await {
-                                Ok(status) => status,
-                                Err(e) => {
-                                    error!("Failed to get status: {:?}", e);
-                                    GladosTtsStatus::Unknown
-                                }
-                            };
-                            if let Err(e) = game_tx.send(GameboundMessage::Pong { status }) {
-                                error!("Gamebound channel failure, exiting: {}", e);
-                                break;
-                            }
-                        }
-                        ThreadboundMessage::Startup => {
-                            debug!("Worker received startup request, starting GladosTts API");
-                            if let Err(e) = crate::glados_tts::start() {
-                                error!("Failed to start: {:?}", e);
-                            };
-                        }
+fn handle_threadbound_message<'a>(
+    msg: &'a ThreadboundMessage,
+    reply_tx: &'a Sender<GameboundMessage>,
+    _state: &'a mut (),
+) -> WorkerFuture<'a, Error> {
+    Box::pin(async move {
+        match msg {
+            ThreadboundMessage::Ping => {
+                debug!("Worker received ping request, pinging GladosTts API");
+                let status = match crate::glados_tts::get_status().await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        error!("Failed to get status: {:?}", e);
+                        GladosTtsStatus::Unknown
                     }
-                    std::thread::sleep(std::time::Duration::from_millis(50));
-                }
-            });
-        })
-        .expect("Failed to spawn thread");
+                };
+                send_gamebound(reply_tx, GameboundMessage::Pong { status }).await?;
+            }
+            ThreadboundMessage::Startup => {
+                debug!("Worker received startup request, starting GladosTts API");
+                if let Err(e) = crate::glados_tts::start() {
+                    error!("Failed to start: {:?}", e);
+                };
+            }
+        }
+        Ok(())
+    })
 }
 
 fn events_to_bridge(
-    bridge: ResMut<Bridge>,
+    mut bridge: EventWriter<ThreadboundMessage>,
     mut ping_events: EventReader<GladosTtsPingEvent>,
     mut status_events: EventReader<GladosTtsStatusEvent>,
 ) {
@@ -96,9 +75,7 @@ fn events_to_bridge(
         };
         let msg = ThreadboundMessage::Ping;
         debug!("Sending bridge message: {:?}", msg);
-        if let Err(e) = bridge.sender.send(msg) {
-            error!("Threadbound channel failure: {}", e);
-        }
+        bridge.send(msg);
     }
 
     // Detect startup requests
@@ -109,17 +86,18 @@ fn events_to_bridge(
         status_events.clear();
         let msg = ThreadboundMessage::Startup;
         debug!("Sending bridge message: {:?}", msg);
-        if let Err(e) = bridge.sender.send(msg) {
-            error!("Threadbound channel failure: {}", e);
-        }
+        bridge.send(msg);
     }
 }
 
-fn bridge_to_events(bridge: ResMut<Bridge>, mut events: EventWriter<GladosTtsPingEvent>) {
-    for msg in bridge.receiver.try_iter() {
+fn bridge_to_events(
+    mut bridge: EventReader<GameboundMessage>,
+    mut events: EventWriter<GladosTtsPingEvent>,
+) {
+    for msg in bridge.read() {
         match msg {
             GameboundMessage::Pong { status } => {
-                let event = GladosTtsPingEvent::Pong { status };
+                let event = GladosTtsPingEvent::Pong { status: *status };
                 debug!("Received bridge response, sending game event {:?}", event);
                 events.send(event);
             }
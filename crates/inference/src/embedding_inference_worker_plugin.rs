@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_worker::prelude::anyhow::Error;
+use cursor_hero_worker::prelude::send_gamebound;
+use cursor_hero_worker::prelude::Sender;
+use cursor_hero_worker::prelude::WorkerConfig;
+use cursor_hero_worker::prelude::WorkerFuture;
+use cursor_hero_worker::prelude::WorkerPlugin;
+
+/// Consumes [`EmbeddingInferenceEvent::Request`]s and answers them using the active backend in [`TextInferenceBackends`].
+pub struct EmbeddingInferenceWorkerPlugin;
+
+impl Plugin for EmbeddingInferenceWorkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextInferenceBackends>();
+        app.add_plugins(WorkerPlugin {
+            config: WorkerConfig::<ThreadboundMessage, GameboundMessage, (), _, _, _> {
+                name: "embedding_inference".to_string(),
+                handle_threadbound_message_async: Some(handle_threadbound_message),
+                // observations arrive in bursts, so the queue is deeper than the text worker's
+                gamebound_channel_capacity: 100,
+                threadbound_channel_capacity: 100,
+                ..default()
+            },
+        });
+        app.add_systems(Update, bridge_requests);
+        app.add_systems(Update, bridge_responses);
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+enum GameboundMessage {
+    Response {
+        session_id: Entity,
+        text: String,
+        embedding: Vec<f32>,
+    },
+    Failed {
+        session_id: Entity,
+        text: String,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Event)]
+enum ThreadboundMessage {
+    Embed {
+        session_id: Entity,
+        text: String,
+        config: InferenceConfig,
+        backend: Arc<dyn TextInferenceBackend>,
+    },
+}
+
+fn handle_threadbound_message<'a>(
+    msg: &'a ThreadboundMessage,
+    reply_tx: &'a Sender<GameboundMessage>,
+    _state: &'a mut (),
+) -> WorkerFuture<'a, Error> {
+    Box::pin(async move {
+        let ThreadboundMessage::Embed {
+            session_id,
+            text,
+            config,
+            backend,
+        } = msg;
+        let msg = match backend.embed(text, config).await {
+            Ok(embedding) => GameboundMessage::Response {
+                session_id: *session_id,
+                text: text.clone(),
+                embedding,
+            },
+            Err(e) => {
+                error!("Failed to embed using {:?}: {:?}", backend, e);
+                GameboundMessage::Failed {
+                    session_id: *session_id,
+                    text: text.clone(),
+                    reason: e.to_string(),
+                }
+            }
+        };
+        send_gamebound(reply_tx, msg).await?;
+        Ok(())
+    })
+}
+
+fn bridge_requests(
+    mut bridge: EventWriter<ThreadboundMessage>,
+    mut events: ParamSet<(
+        EventReader<EmbeddingInferenceEvent>,
+        EventWriter<EmbeddingInferenceEvent>,
+    )>,
+    backends: Res<TextInferenceBackends>,
+    config: Res<InferenceConfig>,
+) {
+    let mut failures = Vec::new();
+    for event in events.p0().read() {
+        let EmbeddingInferenceEvent::Request { session_id, text } = event else {
+            continue;
+        };
+        let Some(backend) = backends.for_session(*session_id) else {
+            failures.push(EmbeddingInferenceEvent::Failed {
+                session_id: *session_id,
+                text: text.clone(),
+                reason: "No text inference backend available".to_string(),
+            });
+            continue;
+        };
+        debug!(
+            "Sending embedding request for session {:?} to worker using {:?}",
+            session_id, backend
+        );
+        bridge.send(ThreadboundMessage::Embed {
+            session_id: *session_id,
+            text: text.clone(),
+            config: config.clone(),
+            backend,
+        });
+    }
+    for event in failures {
+        debug!("Sending event {:?}", event);
+        events.p1().send(event);
+    }
+}
+
+fn bridge_responses(
+    mut bridge: EventReader<GameboundMessage>,
+    mut events: EventWriter<EmbeddingInferenceEvent>,
+) {
+    for msg in bridge.read() {
+        let event = match msg.clone() {
+            GameboundMessage::Response {
+                session_id,
+                text,
+                embedding,
+            } => EmbeddingInferenceEvent::Response {
+                session_id,
+                text,
+                embedding,
+            },
+            GameboundMessage::Failed {
+                session_id,
+                text,
+                reason,
+            } => EmbeddingInferenceEvent::Failed {
+                session_id,
+                text,
+                reason,
+            },
+        };
+        debug!("Received bridge message, sending game event");
+        events.send(event);
+    }
+}
@@ -0,0 +1,565 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use bevy::utils::HashMap;
+use cursor_hero_inference_types::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Records responses of the active backend to a fixture file, or serves them back from it.
+///
+/// Must be added after the plugins registering the backend to record.
+pub struct InferenceFixturePlugin {
+    pub mode: InferenceFixtureMode,
+    pub file: PathBuf,
+}
+
+impl Plugin for InferenceFixturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextInferenceBackends>();
+        let mut backends = app.world.resource_mut::<TextInferenceBackends>();
+        let backend = match self.mode {
+            InferenceFixtureMode::Record => {
+                let Some(inner) = backends
+                    .active_name()
+                    .map(|name| name.to_string())
+                    .and_then(|name| backends.get(&name))
+                else {
+                    error!("No active text inference backend to record fixtures from");
+                    return;
+                };
+                FixtureTextInferenceBackend::record(inner, self.file.clone())
+            }
+            InferenceFixtureMode::Replay => FixtureTextInferenceBackend::replay(self.file.clone()),
+        };
+        let backend = match backend {
+            Ok(backend) => backend,
+            Err(e) => {
+                error!(
+                    "Failed to load inference fixtures from {:?}: {}",
+                    self.file, e
+                );
+                return;
+            }
+        };
+        info!(
+            "Using inference fixtures in {:?} mode from {:?}",
+            self.mode, self.file
+        );
+        let name = backend.name().to_string();
+        backends.register(backend);
+        backends.set_active(&name);
+    }
+}
+
+/// A recorded response along with what it was a response to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InferenceFixture {
+    /// Hash of the materialized prompt, see [`prompt_hash`]
+    pub prompt_hash: String,
+    pub model: String,
+    pub num_predict: Option<usize>,
+    pub stop: Option<Vec<String>>,
+    /// Function names of the tools offered to the model
+    #[serde(default)]
+    pub tools: Vec<String>,
+    pub response: String,
+    #[serde(default)]
+    pub tool_calls: Vec<RecordedToolCall>,
+}
+
+impl InferenceFixture {
+    fn matches(&self, other: &InferenceFixture) -> bool {
+        self.prompt_hash == other.prompt_hash
+            && self.model == other.model
+            && self.num_predict == other.num_predict
+            && self.stop == other.stop
+            && self.tools == other.tools
+    }
+}
+
+/// A [`ToolCall`] as it was made by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedToolCall {
+    pub function_name: String,
+    pub arguments: serde_json::Value,
+}
+
+impl From<&ToolCall> for RecordedToolCall {
+    fn from(call: &ToolCall) -> Self {
+        let mut arguments = serde_json::json!({ "action": call.action });
+        if let Some(direction) = call.direction {
+            arguments["x"] = serde_json::json!(direction.x);
+            arguments["y"] = serde_json::json!(direction.y);
+        }
+        Self {
+            function_name: call.function_name.clone(),
+            arguments,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InferenceFixtures {
+    /// Whether the recorded backend took tool calls, replaying offers tools the same way
+    #[serde(default)]
+    pub supports_tools: bool,
+    pub fixtures: Vec<InferenceFixture>,
+}
+
+/// Stable across runs and platforms so fixtures can be committed.
+///
+/// Times are masked, prompts mention the current time and when each observation was made.
+pub fn prompt_hash(materialized: &str) -> String {
+    format!("{:016x}", fxhash::hash64(&mask_times(materialized)))
+}
+
+/// Replaces dates like `2024-03-01`, optionally followed by a time like ` 13:37` or `T13:37:00`
+fn mask_times(text: &str) -> String {
+    let bytes = text.as_bytes();
+    // `0` stands for any digit
+    let shaped = |at: usize, shape: &[u8]| {
+        bytes.get(at..at + shape.len()).is_some_and(|window| {
+            window
+                .iter()
+                .zip(shape)
+                .all(|(byte, expected)| match expected {
+                    b'0' => byte.is_ascii_digit(),
+                    expected => byte == expected,
+                })
+        })
+    };
+    let mut masked = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if !shaped(i, b"0000-00-00") {
+            i += 1;
+            continue;
+        }
+        let mut end = i + 10;
+        if shaped(end, b" 00:00") || shaped(end, b"T00:00") {
+            end += 6;
+            if shaped(end, b":00") {
+                end += 3;
+            }
+        }
+        // only ASCII was matched, so these are char boundaries
+        masked.push_str(&text[copied..i]);
+        masked.push_str("<time>");
+        copied = end;
+        i = end;
+    }
+    masked.push_str(&text[copied..]);
+    masked
+}
+
+enum FixtureMode {
+    Record {
+        inner: Arc<dyn TextInferenceBackend>,
+    },
+    Replay,
+}
+
+struct FixtureState {
+    fixtures: InferenceFixtures,
+    /// How many times each fixture has been served, so repeated prompts replay their responses in order
+    served: HashMap<usize, usize>,
+}
+
+pub struct FixtureTextInferenceBackend {
+    mode: FixtureMode,
+    file: PathBuf,
+    state: Mutex<FixtureState>,
+}
+
+impl FixtureTextInferenceBackend {
+    /// Appends to the fixtures already in `file`, if any
+    pub fn record(
+        inner: Arc<dyn TextInferenceBackend>,
+        file: PathBuf,
+    ) -> Result<Self, TextInferenceBackendError> {
+        let mut fixtures = match file.exists() {
+            true => read_fixtures(&file)?,
+            false => InferenceFixtures::default(),
+        };
+        fixtures.supports_tools = inner.supports_tools();
+        Ok(Self::new(FixtureMode::Record { inner }, file, fixtures))
+    }
+
+    pub fn replay(file: PathBuf) -> Result<Self, TextInferenceBackendError> {
+        let fixtures = read_fixtures(&file)?;
+        Ok(Self::new(FixtureMode::Replay, file, fixtures))
+    }
+
+    fn new(mode: FixtureMode, file: PathBuf, fixtures: InferenceFixtures) -> Self {
+        Self {
+            mode,
+            file,
+            state: Mutex::new(FixtureState {
+                fixtures,
+                served: HashMap::default(),
+            }),
+        }
+    }
+
+    fn key(
+        prompt: &MaterializedTextPrompt,
+        config: &InferenceConfig,
+        tools: &[ToolDefinition],
+    ) -> InferenceFixture {
+        let options = prompt.prompt.options().unwrap_or_default();
+        InferenceFixture {
+            prompt_hash: prompt_hash(&prompt.materialized),
+            model: config.model.clone(),
+            num_predict: options.num_predict,
+            stop: options.stop,
+            tools: tools.iter().map(|tool| tool.function_name()).collect(),
+            response: String::new(),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    fn replay_output(
+        &self,
+        prompt: &MaterializedTextPrompt,
+        config: &InferenceConfig,
+        tools: &[ToolDefinition],
+    ) -> Result<TextInferenceOutput, TextInferenceBackendError> {
+        let key = Self::key(prompt, config, tools);
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        let matching = state
+            .fixtures
+            .fixtures
+            .iter()
+            .enumerate()
+            .filter(|(_, fixture)| fixture.matches(&key))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        // the least served match, the earliest recorded one on ties
+        let Some(index) = matching
+            .iter()
+            .copied()
+            .min_by_key(|i| state.served.get(i).copied().unwrap_or_default())
+        else {
+            return Err(format!(
+                "No recorded response for prompt {} using model {}",
+                key.prompt_hash, key.model
+            )
+            .into());
+        };
+        *state.served.entry(index).or_default() += 1;
+        let fixture = &state.fixtures.fixtures[index];
+        Ok(TextInferenceOutput {
+            text: fixture.response.clone(),
+            tool_calls: fixture
+                .tool_calls
+                .iter()
+                .map(|call| ToolCall::parse(&call.function_name, &call.arguments))
+                .collect::<Result<_, _>>()?,
+            usage: None,
+        })
+    }
+
+    fn record_output(
+        &self,
+        prompt: &MaterializedTextPrompt,
+        config: &InferenceConfig,
+        tools: &[ToolDefinition],
+        output: &TextInferenceOutput,
+    ) -> Result<(), TextInferenceBackendError> {
+        let fixture = InferenceFixture {
+            response: output.text.clone(),
+            tool_calls: output.tool_calls.iter().map(Into::into).collect(),
+            ..Self::key(prompt, config, tools)
+        };
+        let mut state = self.state.lock().map_err(|e| e.to_string())?;
+        state.fixtures.fixtures.push(fixture);
+        if let Some(parent) = self.file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // a crash mid-write must not take the earlier recordings with it
+        let temp_file = self.file.with_extension("json.tmp");
+        std::fs::write(&temp_file, serde_json::to_string_pretty(&state.fixtures)?)?;
+        std::fs::rename(&temp_file, &self.file)?;
+        debug!(
+            "Recorded inference fixture {} to {:?}",
+            state.fixtures.fixtures.len(),
+            self.file
+        );
+        Ok(())
+    }
+}
+
+fn ignore_delta(_delta: &str) {}
+
+fn read_fixtures(file: &Path) -> Result<InferenceFixtures, TextInferenceBackendError> {
+    let contents = std::fs::read_to_string(file)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+impl TextInferenceBackend for FixtureTextInferenceBackend {
+    fn name(&self) -> &str {
+        "fixture"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        self.generate_streaming(prompt, config, &ignore_delta)
+    }
+
+    fn generate_streaming<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        on_delta: &'a (dyn Fn(&str) + Send + Sync),
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(async move {
+            match &self.mode {
+                FixtureMode::Record { inner } => {
+                    let response = inner.generate_streaming(prompt, config, on_delta).await?;
+                    let output = TextInferenceOutput {
+                        text: response,
+                        ..default()
+                    };
+                    if let Err(e) = self.record_output(prompt, config, &[], &output) {
+                        error!("Failed to record inference fixture: {}", e);
+                    }
+                    Ok(output.text)
+                }
+                FixtureMode::Replay => {
+                    let output = self.replay_output(prompt, config, &[])?;
+                    // replayed responses arrive in one piece
+                    on_delta(&output.text);
+                    Ok(output.text)
+                }
+            }
+        })
+    }
+
+    fn supports_tools(&self) -> bool {
+        match &self.mode {
+            FixtureMode::Record { inner } => inner.supports_tools(),
+            FixtureMode::Replay => self
+                .state
+                .lock()
+                .map(|state| state.fixtures.supports_tools)
+                .unwrap_or_default(),
+        }
+    }
+
+    fn generate_with_tools<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        tools: &'a [ToolDefinition],
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(async move {
+            match &self.mode {
+                FixtureMode::Record { inner } => {
+                    let output = inner.generate_with_tools(prompt, config, tools).await?;
+                    if let Err(e) = self.record_output(prompt, config, tools, &output) {
+                        error!("Failed to record inference fixture: {}", e);
+                    }
+                    Ok(output)
+                }
+                FixtureMode::Replay => self.replay_output(prompt, config, tools),
+            }
+        })
+    }
+
+    /// Embeddings are not recorded, they pass through while recording
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<Vec<f32>, TextInferenceBackendError>> {
+        match &self.mode {
+            FixtureMode::Record { inner } => inner.embed(text, config),
+            FixtureMode::Replay => {
+                Box::pin(async { Err("Embeddings are not available when replaying".into()) })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use bevy::ecs::event::ManualEventReader;
+    use cursor_hero_text_asset_types::prelude::*;
+
+    use crate::mock_inference_backend::MockTextInferenceBackend;
+    use crate::text_inference_worker_plugin::TextInferenceWorkerPlugin;
+
+    #[test]
+    fn record_then_replay() {
+        let file = std::env::temp_dir().join(format!(
+            "cursor_hero_inference_fixtures_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&file);
+        let prompt = |content: &str| MaterializedTextPrompt {
+            prompt: TextPrompt::Raw {
+                content: content.to_string(),
+                options: None,
+            },
+            materialized: content.to_string(),
+        };
+        let config = InferenceConfig::default();
+
+        let inner = Arc::new(MockTextInferenceBackend::new(vec![
+            "first".to_string(),
+            "second".to_string(),
+            "third".to_string(),
+        ]));
+        let recorder = FixtureTextInferenceBackend::record(inner, file.clone()).unwrap();
+        for content in ["hello", "hello", "bye"] {
+            bevy::tasks::block_on(recorder.generate(&prompt(content), &config)).unwrap();
+        }
+
+        let replayer = FixtureTextInferenceBackend::replay(file.clone()).unwrap();
+        let replay =
+            |content: &str| bevy::tasks::block_on(replayer.generate(&prompt(content), &config));
+        assert_eq!(replay("bye").unwrap(), "third");
+        assert_eq!(replay("hello").unwrap(), "first");
+        assert_eq!(replay("hello").unwrap(), "second");
+        assert!(replay("unrecorded").is_err());
+
+        let tools = vec![ToolDefinition {
+            name: "Cube Tool".to_string(),
+            description: "Spawns cubes".to_string(),
+            actions: vec!["Spawn".to_string()],
+        }];
+        let recorder = FixtureTextInferenceBackend::record(
+            Arc::new(MockTextInferenceBackend::new(
+                vec!["with tools".to_string()],
+            )),
+            file.clone(),
+        )
+        .unwrap();
+        bevy::tasks::block_on(recorder.generate_with_tools(&prompt("hello"), &config, &tools))
+            .unwrap();
+        let replayer = FixtureTextInferenceBackend::replay(file.clone()).unwrap();
+        let output =
+            bevy::tasks::block_on(replayer.generate_with_tools(&prompt("hello"), &config, &tools))
+                .unwrap();
+        assert_eq!(output.text, "with tools");
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn recorded_tool_calls_round_trip() {
+        let call = ToolCall {
+            function_name: "movement_tool".to_string(),
+            action: "Move".to_string(),
+            direction: Some(Vec2::new(1.0, -0.5)),
+        };
+        let recorded = RecordedToolCall::from(&call);
+        assert_eq!(
+            ToolCall::parse(&recorded.function_name, &recorded.arguments).unwrap(),
+            call
+        );
+    }
+
+    #[test]
+    fn times_are_masked() {
+        assert_eq!(
+            mask_times("It is 2024-03-01 13:37, you last spoke at 2024-02-29T08:00:59."),
+            "It is <time>, you last spoke at <time>."
+        );
+        assert_eq!(prompt_hash("at 2024-03-01"), prompt_hash("at 1999-12-31"));
+        assert_ne!(prompt_hash("at 2024-03-01"), prompt_hash("on 2024-03-01"));
+    }
+
+    /// Talks to an agent through the text inference worker, timestamping each line with `date`
+    fn converse(backend: FixtureTextInferenceBackend, date: &str) -> Vec<String> {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_plugins(InferenceTypesPlugin);
+        app.init_resource::<Assets<TextAsset>>();
+        app.add_plugins(TextInferenceWorkerPlugin);
+        app.world
+            .resource_mut::<TextInferenceBackends>()
+            .register(backend);
+        let session_id = app.world.spawn_empty().id();
+
+        let mut reader = ManualEventReader::<TextInferenceEvent>::default();
+        let mut messages = Vec::new();
+        let mut replies = Vec::new();
+        for (i, line) in ["Hello there", "What are you up to?", "Goodbye"]
+            .iter()
+            .enumerate()
+        {
+            messages.push(ChatMessage::new(
+                ChatRole::User,
+                format!("[{} 12:0{}] {}", date, i, line),
+            ));
+            app.world.send_event(TextInferenceEvent::Request {
+                session_id,
+                prompt: TextPrompt::Messages {
+                    system: None,
+                    messages: messages.clone(),
+                    options: None,
+                },
+            });
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let reply = loop {
+                assert!(Instant::now() < deadline, "No reply to {:?}", line);
+                app.update();
+                let events = app.world.resource::<Events<TextInferenceEvent>>();
+                let reply = reader.read(events).find_map(|event| match event {
+                    TextInferenceEvent::Response { response, .. } => Some(Ok(response.clone())),
+                    TextInferenceEvent::Failed { reason, .. } => Some(Err(reason.clone())),
+                    _ => None,
+                });
+                match reply {
+                    Some(reply) => break reply.unwrap(),
+                    None => std::thread::sleep(Duration::from_millis(10)),
+                }
+            };
+            messages.push(ChatMessage::new(ChatRole::Assistant, reply.clone()));
+            replies.push(reply);
+        }
+        replies
+    }
+
+    #[test]
+    fn replay_agent_conversation() {
+        let file = std::env::temp_dir().join(format!(
+            "cursor_hero_inference_conversation_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&file);
+        let inner = Arc::new(MockTextInferenceBackend::new(vec![
+            "Hi!".to_string(),
+            "Pushing cubes around.".to_string(),
+            "See you.".to_string(),
+        ]));
+
+        let recorded = converse(
+            FixtureTextInferenceBackend::record(inner, file.clone()).unwrap(),
+            "2024-03-01",
+        );
+        // the next day, so every timestamp in the prompts differs from the recording
+        let replayed = converse(
+            FixtureTextInferenceBackend::replay(file.clone()).unwrap(),
+            "2024-03-02",
+        );
+        assert_eq!(recorded, vec!["Hi!", "Pushing cubes around.", "See you."]);
+        assert_eq!(replayed, recorded);
+
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -1,11 +1,22 @@
 use bevy::prelude::*;
+use cursor_hero_inference_types::prelude::*;
 
+use crate::embedding_inference_worker_plugin::EmbeddingInferenceWorkerPlugin;
+use crate::llama_cpp_inference_backend::LlamaCppTextInferenceBackend;
+use crate::openai_inference_backend::OpenAiTextInferenceBackend;
 use crate::prompt_asset_plugin::PromptAssetPlugin;
+use crate::text_inference_worker_plugin::TextInferenceWorkerPlugin;
 
 pub struct InferencePlugin;
 
 impl Plugin for InferencePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(PromptAssetPlugin);
+        app.add_plugins(TextInferenceWorkerPlugin);
+        app.add_plugins(EmbeddingInferenceWorkerPlugin);
+
+        let mut backends = app.world.resource_mut::<TextInferenceBackends>();
+        backends.register(OpenAiTextInferenceBackend::default());
+        backends.register(LlamaCppTextInferenceBackend);
     }
 }
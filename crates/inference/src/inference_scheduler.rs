@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use cursor_hero_inference_types::prelude::*;
+
+/// A request waiting in an [`InferenceScheduler`]
+#[derive(Debug)]
+pub struct Scheduled<T> {
+    pub session_id: Entity,
+    pub priority: InferencePriority,
+    /// Identical prompts share a key, see [`crate::fixture_inference_backend::prompt_hash`]
+    pub key: String,
+    /// Tie breaker, earlier requests of the same priority go first
+    sequence: u64,
+    pub item: T,
+}
+
+#[derive(Debug)]
+pub enum Enqueued<T> {
+    Queued,
+    Deduplicated,
+    Replaced,
+    /// Room was made by dropping a request of lower priority
+    Evicted(Scheduled<T>),
+    Rejected(T),
+}
+
+/// Priority queue that runs at most one request per session at a time.
+///
+/// Only the newest queued request of a session is kept, sessions only care about the latest state of the world.
+pub struct InferenceScheduler<T> {
+    pub capacity: usize,
+    pending: Vec<Scheduled<T>>,
+    /// Keys of the running request by session
+    running: HashMap<Entity, String>,
+    next_sequence: u64,
+}
+
+impl<T> InferenceScheduler<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pending: Vec::new(),
+            running: HashMap::default(),
+            next_sequence: 0,
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    pub fn is_running(&self, session_id: Entity) -> bool {
+        self.running.contains_key(&session_id)
+    }
+
+    pub fn push(
+        &mut self,
+        session_id: Entity,
+        priority: InferencePriority,
+        key: String,
+        item: T,
+    ) -> Enqueued<T> {
+        if self.running.get(&session_id) == Some(&key) {
+            return Enqueued::Deduplicated;
+        }
+        if let Some(queued) = self
+            .pending
+            .iter_mut()
+            .find(|queued| queued.session_id == session_id)
+        {
+            if queued.key == key {
+                return Enqueued::Deduplicated;
+            }
+            // keeps its place in line
+            queued.priority = queued.priority.max(priority);
+            queued.key = key;
+            queued.item = item;
+            return Enqueued::Replaced;
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let scheduled = Scheduled {
+            session_id,
+            priority,
+            key,
+            sequence,
+            item,
+        };
+        if self.pending.len() < self.capacity {
+            self.pending.push(scheduled);
+            return Enqueued::Queued;
+        }
+
+        // the newest of the least important requests has waited the least
+        let Some(victim) = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, queued)| queued.priority < priority)
+            .min_by_key(|(_, queued)| (queued.priority, std::cmp::Reverse(queued.sequence)))
+            .map(|(i, _)| i)
+        else {
+            return Enqueued::Rejected(scheduled.item);
+        };
+        let evicted = self.pending.swap_remove(victim);
+        self.pending.push(scheduled);
+        Enqueued::Evicted(evicted)
+    }
+
+    /// Takes the most important request whose session is idle and marks that session as running
+    pub fn pop(&mut self) -> Option<Scheduled<T>> {
+        let index = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, queued)| !self.running.contains_key(&queued.session_id))
+            .max_by_key(|(_, queued)| (queued.priority, std::cmp::Reverse(queued.sequence)))
+            .map(|(i, _)| i)?;
+        let scheduled = self.pending.remove(index);
+        self.running
+            .insert(scheduled.session_id, scheduled.key.clone());
+        Some(scheduled)
+    }
+
+    /// Lets the next request of the session run
+    pub fn finish(&mut self, session_id: Entity) {
+        self.running.remove(&session_id);
+    }
+
+    /// Drops the queued request of the session, the running one is left to the caller
+    pub fn remove(&mut self, session_id: Entity) -> Option<T> {
+        let index = self
+            .pending
+            .iter()
+            .position(|queued| queued.session_id == session_id)?;
+        Some(self.pending.remove(index).item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(index: u32) -> Entity {
+        Entity::from_raw(index)
+    }
+
+    #[test]
+    fn serves_by_priority_then_age() {
+        let mut scheduler = InferenceScheduler::new(10);
+        scheduler.push(session(1), InferencePriority::Background, "a".into(), 1);
+        scheduler.push(session(2), InferencePriority::Observation, "b".into(), 2);
+        scheduler.push(session(3), InferencePriority::UserChat, "c".into(), 3);
+        scheduler.push(session(4), InferencePriority::Observation, "d".into(), 4);
+        let order = std::iter::from_fn(|| scheduler.pop().map(|scheduled| scheduled.item))
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![3, 2, 4, 1]);
+    }
+
+    #[test]
+    fn serializes_and_deduplicates_sessions() {
+        let mut scheduler = InferenceScheduler::new(10);
+        let priority = InferencePriority::Observation;
+        scheduler.push(session(1), priority, "a".into(), 1);
+        assert_eq!(scheduler.pop().unwrap().item, 1);
+        assert!(matches!(
+            scheduler.push(session(1), priority, "a".into(), 2),
+            Enqueued::Deduplicated
+        ));
+        assert!(matches!(
+            scheduler.push(session(1), priority, "b".into(), 3),
+            Enqueued::Queued
+        ));
+        assert!(matches!(
+            scheduler.push(session(1), priority, "c".into(), 4),
+            Enqueued::Replaced
+        ));
+        // the session is still running its first request
+        assert!(scheduler.pop().is_none());
+        scheduler.finish(session(1));
+        assert_eq!(scheduler.pop().unwrap().item, 4);
+    }
+
+    #[test]
+    fn full_queue_evicts_lower_priority() {
+        let mut scheduler = InferenceScheduler::new(2);
+        scheduler.push(session(1), InferencePriority::Background, "a".into(), 1);
+        scheduler.push(session(2), InferencePriority::Background, "b".into(), 2);
+        assert!(matches!(
+            scheduler.push(session(3), InferencePriority::Background, "c".into(), 3),
+            Enqueued::Rejected(3)
+        ));
+        let Enqueued::Evicted(evicted) =
+            scheduler.push(session(4), InferencePriority::UserChat, "d".into(), 4)
+        else {
+            panic!("expected an eviction");
+        };
+        assert_eq!(evicted.item, 2);
+        assert_eq!(scheduler.pending(), 2);
+    }
+}
@@ -1,2 +1,10 @@
+pub mod embedding_inference_worker_plugin;
+pub mod fixture_inference_backend;
 pub mod inference_plugin;
+pub mod inference_scheduler;
+pub mod llama_cpp_inference_backend;
+pub mod mock_inference_backend;
+pub mod openai_inference_backend;
 pub mod prompt_asset_plugin;
+pub mod prompt_template;
+pub mod text_inference_worker_plugin;
@@ -0,0 +1,66 @@
+use bevy::utils::BoxedFuture;
+use cursor_hero_inference_types::prelude::*;
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Talks to the `/completion` endpoint of a llama.cpp `server`.
+///
+/// The server is started with its model and context size, so those parts of [`InferenceConfig`] are ignored.
+pub struct LlamaCppTextInferenceBackend;
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    content: String,
+}
+
+impl TextInferenceBackend for LlamaCppTextInferenceBackend {
+    fn name(&self) -> &str {
+        "llama.cpp"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(async move {
+            let mut payload = serde_json::json!({
+                "prompt": prompt.materialized,
+                "stream": false
+            });
+            if let Some(temperature) = config.temperature {
+                payload["temperature"] = serde_json::json!(temperature);
+            }
+            if let Some(top_p) = config.top_p {
+                payload["top_p"] = serde_json::json!(top_p);
+            }
+            if let Some(seed) = config.seed {
+                payload["seed"] = serde_json::json!(seed);
+            }
+            if let Some(options) = prompt.prompt.options() {
+                if let Some(num_predict) = options.num_predict {
+                    payload["n_predict"] = serde_json::json!(num_predict);
+                }
+                if let Some(stop) = options.stop {
+                    payload["stop"] = serde_json::json!(stop);
+                }
+            }
+
+            let client = Client::new();
+            let res = client
+                .post(format!("{}/completion", config.base_url(self.name())))
+                .json(&payload)
+                .send()
+                .await?;
+
+            if res.status().is_success() {
+                let api_response = res.json::<ApiResponse>().await?;
+                Ok(api_response.content.trim().to_string())
+            } else {
+                let status = res.status();
+                let body = res.text().await?;
+                Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+            }
+        })
+    }
+}
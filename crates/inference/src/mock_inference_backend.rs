@@ -0,0 +1,96 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use bevy::utils::BoxedFuture;
+use cursor_hero_inference_types::prelude::*;
+
+/// Deterministic backend that replies with canned responses in order, wrapping around when exhausted.
+///
+/// Not registered by default, intended for tests.
+pub struct MockTextInferenceBackend {
+    pub responses: Vec<String>,
+    next: AtomicUsize,
+}
+
+impl MockTextInferenceBackend {
+    pub fn new(responses: Vec<String>) -> Self {
+        Self {
+            responses,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl TextInferenceBackend for MockTextInferenceBackend {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        _prompt: &'a MaterializedTextPrompt,
+        _config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(async move {
+            if self.responses.is_empty() {
+                return Err("Mock backend has no responses".into());
+            }
+            let index = self.next.fetch_add(1, Ordering::Relaxed) % self.responses.len();
+            Ok(self.responses[index].clone())
+        })
+    }
+
+    /// Hashed bag of words, so texts sharing words end up close together
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+        _config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<Vec<f32>, TextInferenceBackendError>> {
+        Box::pin(async move {
+            let mut embedding = vec![0.0; MOCK_EMBEDDING_DIMENSIONS];
+            for word in text.split_whitespace() {
+                let word = word
+                    .trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_lowercase();
+                if word.is_empty() {
+                    continue;
+                }
+                embedding[fxhash::hash64(&word) as usize % MOCK_EMBEDDING_DIMENSIONS] += 1.0;
+            }
+            Ok(embedding)
+        })
+    }
+}
+
+const MOCK_EMBEDDING_DIMENSIONS: usize = 64;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_cycle() {
+        let backend = MockTextInferenceBackend::new(vec!["a".to_string(), "b".to_string()]);
+        let prompt = MaterializedTextPrompt {
+            prompt: TextPrompt::Raw {
+                content: "hi".to_string(),
+                options: None,
+            },
+            materialized: "hi".to_string(),
+        };
+        let config = InferenceConfig::default();
+        let responses = (0..3)
+            .map(|_| bevy::tasks::block_on(backend.generate(&prompt, &config)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(responses, vec!["a", "b", "a"]);
+    }
+
+    #[test]
+    fn embeddings_are_deterministic() {
+        let backend = MockTextInferenceBackend::new(Vec::new());
+        let config = InferenceConfig::default();
+        let embed = |text: &str| bevy::tasks::block_on(backend.embed(text, &config)).unwrap();
+        assert_eq!(embed("Hello there!"), embed("hello THERE"));
+        assert_ne!(embed("hello"), embed("goodbye"));
+    }
+}
@@ -0,0 +1,219 @@
+use bevy::log::debug;
+use bevy::utils::BoxedFuture;
+use cursor_hero_inference_types::prelude::*;
+use reqwest::Client;
+use serde::Deserialize;
+
+/// Talks to any server exposing an OpenAI-compatible `/v1/chat/completions` endpoint.
+#[derive(Default)]
+pub struct OpenAiTextInferenceBackend {
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse {
+    choices: Vec<ApiChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiChoice {
+    message: ApiMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ApiToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiToolCall {
+    function: ApiFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiFunctionCall {
+    name: String,
+    /// A string containing JSON
+    arguments: serde_json::Value,
+}
+
+/// The API rejects requests with more stop sequences than this
+const MAX_STOP_SEQUENCES: usize = 4;
+
+impl OpenAiTextInferenceBackend {
+    fn build_payload(
+        prompt: &MaterializedTextPrompt,
+        config: &InferenceConfig,
+    ) -> serde_json::Value {
+        let messages = match &prompt.prompt {
+            TextPrompt::Messages { messages, .. } => messages
+                .iter()
+                .map(|message| {
+                    let mut json = serde_json::json!({
+                        "role": message.role.as_str(),
+                        "content": message.content,
+                    });
+                    if let Some(name) = &message.name {
+                        // the API only accepts names matching ^[a-zA-Z0-9_-]+$
+                        let name = name
+                            .chars()
+                            .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+                                true => c,
+                                false => '_',
+                            })
+                            .collect::<String>();
+                        json["name"] = serde_json::json!(name);
+                    }
+                    json
+                })
+                .collect::<Vec<_>>(),
+            _ => vec![serde_json::json!({
+                "role": "user",
+                "content": prompt.materialized,
+            })],
+        };
+        let mut payload = serde_json::json!({
+            "model": config.model,
+            "messages": messages,
+            "stream": false
+        });
+        if let Some(temperature) = config.temperature {
+            payload["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_p) = config.top_p {
+            payload["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(seed) = config.seed {
+            payload["seed"] = serde_json::json!(seed);
+        }
+        if let Some(options) = prompt.prompt.options() {
+            if let Some(num_predict) = options.num_predict {
+                payload["max_tokens"] = serde_json::json!(num_predict);
+            }
+            if let Some(mut stop) = options.stop {
+                if stop.len() > MAX_STOP_SEQUENCES {
+                    debug!(
+                        "Dropping stop sequences {:?}, only {} are accepted",
+                        &stop[MAX_STOP_SEQUENCES..],
+                        MAX_STOP_SEQUENCES
+                    );
+                    stop.truncate(MAX_STOP_SEQUENCES);
+                }
+                payload["stop"] = serde_json::json!(stop);
+            }
+        }
+        payload
+    }
+
+    async fn send(
+        &self,
+        payload: &serde_json::Value,
+        config: &InferenceConfig,
+    ) -> Result<ApiMessage, TextInferenceBackendError> {
+        let client = Client::new();
+        let mut request = client
+            .post(format!(
+                "{}/v1/chat/completions",
+                config.base_url(self.name())
+            ))
+            .json(payload);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let res = request.send().await?;
+
+        if res.status().is_success() {
+            let api_response = res.json::<ApiResponse>().await?;
+            api_response
+                .choices
+                .into_iter()
+                .next()
+                .map(|choice| choice.message)
+                .ok_or_else(|| "API response contained no choices".into())
+        } else {
+            let status = res.status();
+            let body = res.text().await?;
+            Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+        }
+    }
+}
+
+impl TextInferenceBackend for OpenAiTextInferenceBackend {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn generate<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(async move {
+            let payload = Self::build_payload(prompt, config);
+            let message = self.send(&payload, config).await?;
+            Ok(message.content.unwrap_or_default().trim().to_string())
+        })
+    }
+
+    fn supports_tools(&self) -> bool {
+        true
+    }
+
+    fn generate_with_tools<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        tools: &'a [ToolDefinition],
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(async move {
+            let mut payload = Self::build_payload(prompt, config);
+            payload["tools"] = serde_json::json!(tools
+                .iter()
+                .map(|tool| tool.function_json())
+                .collect::<Vec<_>>());
+            let message = self.send(&payload, config).await?;
+            let tool_calls = message
+                .tool_calls
+                .iter()
+                .map(|call| ToolCall::parse(&call.function.name, &call.function.arguments))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(TextInferenceOutput {
+                text: message.content.unwrap_or_default().trim().to_string(),
+                tool_calls,
+                usage: None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_sequences_are_capped() {
+        let prompt = MaterializedTextPrompt {
+            prompt: TextPrompt::Raw {
+                content: "hi".to_string(),
+                options: Some(TextInferenceOptions {
+                    stop: Some(
+                        ["\n", "(Human)", "(User)", "(Alice)", "(Bob)"]
+                            .map(String::from)
+                            .to_vec(),
+                    ),
+                    ..Default::default()
+                }),
+            },
+            materialized: "hi".to_string(),
+        };
+        let payload =
+            OpenAiTextInferenceBackend::build_payload(&prompt, &InferenceConfig::default());
+        assert_eq!(
+            payload["stop"],
+            serde_json::json!(["\n", "(Human)", "(User)", "(Alice)"])
+        );
+    }
+}
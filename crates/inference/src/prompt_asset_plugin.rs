@@ -1,18 +1,85 @@
+use bevy::asset::LoadedFolder;
 use bevy::prelude::*;
 use cursor_hero_inference_types::prelude::*;
+use cursor_hero_text_asset_types::prelude::*;
+
+use crate::prompt_template::PromptTemplateEngine;
 
 pub struct PromptAssetPlugin;
 
 impl Plugin for PromptAssetPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<TextPromptHandles>();
+        app.init_resource::<PromptTemplates>();
         app.add_systems(Startup, load_prompt_assets);
+        app.add_systems(
+            Update,
+            (index_prompt_templates, validate_prompt_templates).chain(),
+        );
+    }
+}
+
+fn load_prompt_assets(mut templates: ResMut<PromptTemplates>, asset_server: Res<AssetServer>) {
+    templates.folder = asset_server.load_folder("prompt_templates");
+}
+
+fn index_prompt_templates(
+    mut folder_events: EventReader<AssetEvent<LoadedFolder>>,
+    folders: Res<Assets<LoadedFolder>>,
+    mut templates: ResMut<PromptTemplates>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in folder_events.read() {
+        if !event.is_loaded_with_dependencies(&templates.folder) {
+            continue;
+        }
+        let Some(folder) = folders.get(&templates.folder) else {
+            continue;
+        };
+        let mut indexed = Vec::new();
+        for handle in folder.handles.iter() {
+            let Ok(handle) = handle.clone().try_typed::<TextAsset>() else {
+                continue;
+            };
+            let Some(name) = asset_server.get_path(handle.id()).and_then(|path| {
+                path.path()
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            }) else {
+                continue;
+            };
+            indexed.push((name, handle));
+        }
+        templates.templates = indexed.into_iter().collect();
+        info!(
+            "Indexed prompt templates: {:?}",
+            templates.templates.keys().collect::<Vec<_>>()
+        );
     }
 }
 
-fn load_prompt_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(TextPromptHandles {
-        raw: asset_server.load("prompt_templates/raw.txt"),
-        chat: asset_server.load("prompt_templates/chat.txt"),
-    });
+/// Revalidates every template whenever one of them changes, since a broken partial breaks everything including it
+fn validate_prompt_templates(
+    mut text_asset_events: EventReader<AssetEvent<TextAsset>>,
+    templates: Res<PromptTemplates>,
+    text_assets: Res<Assets<TextAsset>>,
+) {
+    let mut changed = templates.is_changed();
+    for event in text_asset_events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event {
+            if let Some(name) = templates.name_of(*id) {
+                debug!("Prompt template {} was (re)loaded", name);
+                changed = true;
+            }
+        }
+    }
+    if !changed {
+        return;
+    }
+
+    let engine = PromptTemplateEngine::new(&templates, &text_assets);
+    for name in templates.templates.keys() {
+        if let Err(e) = engine.validate(name) {
+            error!("Invalid prompt template: {}", e);
+        }
+    }
 }
@@ -0,0 +1,454 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_text_asset_types::prelude::*;
+
+/// Partials can include other partials, this stops a template that includes itself
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptTemplateError {
+    NotLoaded {
+        template: String,
+    },
+    Syntax {
+        template: String,
+        message: String,
+    },
+    MissingVariable {
+        template: String,
+        name: String,
+    },
+    WrongType {
+        template: String,
+        name: String,
+        expected: &'static str,
+    },
+    IncludeDepthExceeded {
+        template: String,
+    },
+}
+
+impl fmt::Display for PromptTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromptTemplateError::NotLoaded { template } => {
+                write!(f, "Prompt template {} is not loaded", template)
+            }
+            PromptTemplateError::Syntax { template, message } => {
+                write!(
+                    f,
+                    "Syntax error in prompt template {}: {}",
+                    template, message
+                )
+            }
+            PromptTemplateError::MissingVariable { template, name } => {
+                write!(
+                    f,
+                    "Prompt template {} uses variable {} which was not provided",
+                    template, name
+                )
+            }
+            PromptTemplateError::WrongType {
+                template,
+                name,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "Prompt template {} expected variable {} to be {}",
+                    template, name, expected
+                )
+            }
+            PromptTemplateError::IncludeDepthExceeded { template } => {
+                write!(
+                    f,
+                    "Prompt template {} nests partials deeper than {}, does it include itself?",
+                    template, MAX_INCLUDE_DEPTH
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PromptTemplateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    /// `{{name}}` or `{{name.field}}`
+    Variable(String),
+    /// `{{#if name}}...{{else}}...{{/if}}`
+    If {
+        condition: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    /// `{{#each name}}...{{/each}}`, the current item is `{{this}}` and its fields are in scope
+    Each {
+        list: String,
+        body: Vec<Node>,
+    },
+    /// `{{> name}}` renders another template with the same variables
+    Partial(String),
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            return Err("Tag opened with {{ is never closed".to_string());
+        };
+        tokens.push(Token::Tag(after_open[..end].trim()));
+        rest = &after_open[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, String> {
+    let mut tokens = tokenize(source)?.into_iter();
+    match parse_block(&mut tokens)? {
+        (nodes, None) => Ok(nodes),
+        (_, Some(tag)) => Err(format!("Unexpected {} without a matching block", tag)),
+    }
+}
+
+/// Parses until the input runs out or a tag that ends a block (`else`, `/if`, `/each`), which is returned for the caller to check
+fn parse_block<'a>(
+    tokens: &mut impl Iterator<Item = Token<'a>>,
+) -> Result<(Vec<Node>, Option<&'a str>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text.to_string()));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag)));
+        } else if tag.starts_with('!') {
+            // comment
+        } else if let Some(condition) = tag.strip_prefix("#if ") {
+            let (then, end) = parse_block(tokens)?;
+            let otherwise = match end {
+                Some("/if") => Vec::new(),
+                Some("else") => match parse_block(tokens)? {
+                    (otherwise, Some("/if")) => otherwise,
+                    (_, end) => return Err(unclosed("#if", end)),
+                },
+                end => return Err(unclosed("#if", end)),
+            };
+            nodes.push(Node::If {
+                condition: condition.trim().to_string(),
+                then,
+                otherwise,
+            });
+        } else if let Some(list) = tag.strip_prefix("#each ") {
+            match parse_block(tokens)? {
+                (body, Some("/each")) => nodes.push(Node::Each {
+                    list: list.trim().to_string(),
+                    body,
+                }),
+                (_, end) => return Err(unclosed("#each", end)),
+            }
+        } else if let Some(name) = tag.strip_prefix('>') {
+            nodes.push(Node::Partial(name.trim().to_string()));
+        } else if tag.is_empty() || tag.starts_with('#') {
+            return Err(format!("Unknown tag {:?}", tag));
+        } else {
+            nodes.push(Node::Variable(tag.to_string()));
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn unclosed(block: &str, found: Option<&str>) -> String {
+    match found {
+        Some(tag) => format!("Expected {} block to be closed but found {}", block, tag),
+        None => format!("{} block is never closed", block),
+    }
+}
+
+/// Names visible while rendering, innermost last
+#[derive(Clone, Copy)]
+enum Scope<'v> {
+    Variables(&'v PromptVariables),
+    Item(&'v PromptValue),
+}
+
+fn lookup<'v>(scopes: &[Scope<'v>], path: &str) -> Option<&'v PromptValue> {
+    let mut segments = path.split('.');
+    let first = segments.next()?;
+    let mut value = scopes.iter().rev().find_map(|scope| match *scope {
+        Scope::Item(item) if first == "this" => Some(item),
+        Scope::Item(PromptValue::Object(fields)) | Scope::Variables(fields) => fields.get(first),
+        Scope::Item(_) => None,
+    })?;
+    for segment in segments {
+        match value {
+            PromptValue::Object(fields) => value = fields.get(segment)?,
+            _ => return None,
+        }
+    }
+    Some(value)
+}
+
+/// Returns false for values that have no sensible text form
+fn write_value(value: &PromptValue, out: &mut String) -> bool {
+    match value {
+        PromptValue::Text(text) => out.push_str(text),
+        PromptValue::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        PromptValue::List(items) => {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push('\n');
+                }
+                if !write_value(item, out) {
+                    return false;
+                }
+            }
+        }
+        PromptValue::Object(_) => return false,
+    }
+    true
+}
+
+/// Renders the templates in [`PromptTemplates`].
+///
+/// Templates are parsed from the current asset contents on every render, so reloaded files take effect immediately.
+pub struct PromptTemplateEngine<'a> {
+    templates: &'a PromptTemplates,
+    text_assets: &'a Assets<TextAsset>,
+}
+
+impl<'a> PromptTemplateEngine<'a> {
+    pub fn new(templates: &'a PromptTemplates, text_assets: &'a Assets<TextAsset>) -> Self {
+        Self {
+            templates,
+            text_assets,
+        }
+    }
+
+    pub fn render(&self, template: &PromptTemplateRef) -> Result<String, PromptTemplateError> {
+        let mut out = String::new();
+        let mut scopes = vec![Scope::Variables(&template.variables)];
+        self.render_template(&template.name, &mut scopes, 0, &mut out)?;
+        Ok(out)
+    }
+
+    /// Checks the syntax of a template and the partials it includes.
+    ///
+    /// Variables are only known when rendering, so missing ones are reported by [`PromptTemplateEngine::render`].
+    pub fn validate(&self, name: &str) -> Result<(), PromptTemplateError> {
+        self.validate_template(name, 0)
+    }
+
+    fn parse(&self, name: &str) -> Result<Vec<Node>, PromptTemplateError> {
+        let source = self
+            .templates
+            .get(name)
+            .and_then(|handle| self.text_assets.get(handle))
+            .ok_or_else(|| PromptTemplateError::NotLoaded {
+                template: name.to_string(),
+            })?;
+        parse(&source.value).map_err(|message| PromptTemplateError::Syntax {
+            template: name.to_string(),
+            message,
+        })
+    }
+
+    fn validate_template(&self, name: &str, depth: usize) -> Result<(), PromptTemplateError> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(PromptTemplateError::IncludeDepthExceeded {
+                template: name.to_string(),
+            });
+        }
+        let nodes = self.parse(name)?;
+        self.validate_nodes(&nodes, depth)
+    }
+
+    fn validate_nodes(&self, nodes: &[Node], depth: usize) -> Result<(), PromptTemplateError> {
+        for node in nodes {
+            match node {
+                Node::If {
+                    then, otherwise, ..
+                } => {
+                    self.validate_nodes(then, depth)?;
+                    self.validate_nodes(otherwise, depth)?;
+                }
+                Node::Each { body, .. } => self.validate_nodes(body, depth)?,
+                Node::Partial(partial) => self.validate_template(partial, depth + 1)?,
+                Node::Text(_) | Node::Variable(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn render_template<'v>(
+        &self,
+        name: &str,
+        scopes: &mut Vec<Scope<'v>>,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<(), PromptTemplateError> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(PromptTemplateError::IncludeDepthExceeded {
+                template: name.to_string(),
+            });
+        }
+        let nodes = self.parse(name)?;
+        self.render_nodes(name, &nodes, scopes, depth, out)
+    }
+
+    fn render_nodes<'v>(
+        &self,
+        template: &str,
+        nodes: &[Node],
+        scopes: &mut Vec<Scope<'v>>,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<(), PromptTemplateError> {
+        let missing = |name: &str| PromptTemplateError::MissingVariable {
+            template: template.to_string(),
+            name: name.to_string(),
+        };
+        let wrong_type = |name: &str, expected: &'static str| PromptTemplateError::WrongType {
+            template: template.to_string(),
+            name: name.to_string(),
+            expected,
+        };
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Variable(name) => {
+                    let value = lookup(scopes, name).ok_or_else(|| missing(name))?;
+                    if !write_value(value, out) {
+                        return Err(wrong_type(name, "text"));
+                    }
+                }
+                Node::If {
+                    condition,
+                    then,
+                    otherwise,
+                } => {
+                    // unset is falsy so templates can have optional sections
+                    let branch = match lookup(scopes, condition).is_some_and(PromptValue::is_truthy)
+                    {
+                        true => then,
+                        false => otherwise,
+                    };
+                    self.render_nodes(template, branch, scopes, depth, out)?;
+                }
+                Node::Each { list, body } => {
+                    let items = match lookup(scopes, list) {
+                        Some(PromptValue::List(items)) => items,
+                        Some(_) => return Err(wrong_type(list, "a list")),
+                        None => return Err(missing(list)),
+                    };
+                    for item in items {
+                        scopes.push(Scope::Item(item));
+                        let result = self.render_nodes(template, body, scopes, depth, out);
+                        scopes.pop();
+                        result?;
+                    }
+                }
+                Node::Partial(partial) => {
+                    self.render_template(partial, scopes, depth + 1, out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(files: &[(&str, &str)]) -> (PromptTemplates, Assets<TextAsset>) {
+        let mut templates = PromptTemplates::default();
+        let mut text_assets = Assets::<TextAsset>::default();
+        for (name, value) in files {
+            let handle = text_assets.add(TextAsset {
+                value: value.to_string(),
+            });
+            templates.templates.insert(name.to_string(), handle);
+        }
+        (templates, text_assets)
+    }
+
+    #[test]
+    fn renders_blocks_and_partials() {
+        let (templates, text_assets) = setup(&[
+            (
+                "main",
+                "{{> header}}{{#if snapshot}}Screen: {{snapshot}}{{else}}No screen{{/if}}\n{{#each events}}- {{this}}\n{{/each}}",
+            ),
+            ("header", "{{! greeting }}Hi {{agent.name}}.\n"),
+        ]);
+        let engine = PromptTemplateEngine::new(&templates, &text_assets);
+        let variables = PromptVariables::default()
+            .with(
+                "agent",
+                PromptVariables::default().with("name", "Ithia Tig"),
+            )
+            .with("events", vec!["one", "two"]);
+        let rendered = engine
+            .render(&PromptTemplateRef::new("main", variables))
+            .unwrap();
+        assert_eq!(rendered, "Hi Ithia Tig.\nNo screen\n- one\n- two\n");
+        assert!(engine.validate("main").is_ok());
+    }
+
+    #[test]
+    fn missing_variable_fails() {
+        let (templates, text_assets) = setup(&[("main", "Hello {{name}}")]);
+        let engine = PromptTemplateEngine::new(&templates, &text_assets);
+        assert_eq!(
+            engine.render(&PromptTemplateRef::new("main", PromptVariables::default())),
+            Err(PromptTemplateError::MissingVariable {
+                template: "main".to_string(),
+                name: "name".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn validation_catches_bad_templates() {
+        let (templates, text_assets) = setup(&[
+            ("unclosed", "{{#if x}}never closed"),
+            ("missing_partial", "{{> nowhere}}"),
+            ("recursive", "{{> recursive}}"),
+        ]);
+        let engine = PromptTemplateEngine::new(&templates, &text_assets);
+        assert!(matches!(
+            engine.validate("unclosed"),
+            Err(PromptTemplateError::Syntax { .. })
+        ));
+        assert!(matches!(
+            engine.validate("missing_partial"),
+            Err(PromptTemplateError::NotLoaded { .. })
+        ));
+        assert!(matches!(
+            engine.validate("recursive"),
+            Err(PromptTemplateError::IncludeDepthExceeded { .. })
+        ));
+    }
+}
@@ -0,0 +1,706 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use crossbeam_channel::TrySendError;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_metrics::Metrics;
+use cursor_hero_text_asset_types::prelude::*;
+use cursor_hero_worker::prelude::anyhow::Error;
+use cursor_hero_worker::prelude::send_gamebound;
+use cursor_hero_worker::prelude::Sender;
+use cursor_hero_worker::prelude::WorkerConfig;
+use cursor_hero_worker::prelude::WorkerFuture;
+use cursor_hero_worker::prelude::WorkerPlugin;
+use tokio::sync::watch;
+
+use crate::fixture_inference_backend::prompt_hash;
+use crate::inference_scheduler::Enqueued;
+use crate::inference_scheduler::InferenceScheduler;
+use crate::prompt_template::PromptTemplateEngine;
+use crate::prompt_template::PromptTemplateError;
+
+/// Consumes [`TextInferenceEvent::Request`]s and answers them using the backend selected in [`TextInferenceBackends`].
+pub struct TextInferenceWorkerPlugin;
+
+impl Plugin for TextInferenceWorkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TextInferenceBackends>();
+        app.init_resource::<InferenceQueueStatus>();
+        app.init_resource::<InferenceMetrics>();
+        app.init_resource::<InFlightRequests>();
+        app.insert_resource(TextInferenceQueue(InferenceScheduler::new(QUEUE_CAPACITY)));
+        app.add_plugins(WorkerPlugin {
+            config: WorkerConfig::<ThreadboundMessage, GameboundMessage, (), _, _, _> {
+                name: "text_inference".to_string(),
+                handle_threadbound_message_async: Some(handle_threadbound_message),
+                // partials are sent without waiting, this leaves them room between frames
+                gamebound_channel_capacity: 256,
+                // generations are limited by the queue, the worker keeps its default slots
+                // so that a cancelled generation winding down doesn't hold up the next one
+                ..default()
+            },
+        });
+        app.add_systems(
+            Update,
+            (
+                bridge_generate_requests,
+                dispatch_queued_requests,
+                update_queue_status,
+            )
+                .chain(),
+        );
+        // after the worker has moved this frame's replies into events
+        app.add_systems(PostUpdate, bridge_generate_responses);
+    }
+}
+
+/// Requests beyond this many wait in the queue are rejected unless they outrank one that is waiting
+const QUEUE_CAPACITY: usize = 32;
+
+/// Generations dispatched to the worker at once, the rest wait in [`TextInferenceQueue`].
+///
+/// A local model answers one prompt at a time anyway, so a second generation would only compete with the first.
+/// Cancellation doesn't go through the worker, dropping the sender in [`InFlightRequests`] stops a generation right away
+const MAX_RUNNING: usize = 1;
+
+struct QueuedGenerate {
+    prompt: MaterializedTextPrompt,
+    backend: Arc<dyn TextInferenceBackend>,
+    timeout: Duration,
+    queued_at: Instant,
+}
+
+#[derive(Resource)]
+struct TextInferenceQueue(InferenceScheduler<QueuedGenerate>);
+
+struct InFlightRequest {
+    /// Dropping this cancels the request
+    cancel_tx: watch::Sender<()>,
+    /// The worker let go of the request without answering when this is still set a frame later
+    closed: bool,
+}
+
+#[derive(Resource, Default)]
+struct InFlightRequests(HashMap<Entity, InFlightRequest>);
+
+#[derive(Debug, Clone, Event)]
+enum GameboundMessage {
+    Partial {
+        session_id: Entity,
+        delta: String,
+    },
+    Response {
+        session_id: Entity,
+        prompt: MaterializedTextPrompt,
+        response: String,
+        tool_calls: Vec<ToolCall>,
+        sample: TextInferenceSample,
+    },
+    Failed {
+        session_id: Entity,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Event)]
+enum ThreadboundMessage {
+    Generate {
+        session_id: Entity,
+        prompt: MaterializedTextPrompt,
+        config: InferenceConfig,
+        backend: Arc<dyn TextInferenceBackend>,
+        timeout: Duration,
+        priority: InferencePriority,
+        queue_wait: Duration,
+        cancel_rx: watch::Receiver<()>,
+    },
+}
+
+fn handle_threadbound_message<'a>(
+    msg: &'a ThreadboundMessage,
+    reply_tx: &'a Sender<GameboundMessage>,
+    _state: &'a mut (),
+) -> WorkerFuture<'a, Error> {
+    Box::pin(async move {
+        let ThreadboundMessage::Generate {
+            session_id,
+            prompt,
+            config,
+            backend,
+            timeout,
+            priority,
+            queue_wait,
+            cancel_rx,
+        } = msg;
+        let session_id = *session_id;
+        let mut cancel_rx = cancel_rx.clone();
+        if cancel_rx.has_changed().is_err() {
+            debug!(
+                "Skipping generate request for session {:?}, it was cancelled while queued",
+                session_id
+            );
+            return Ok(());
+        }
+        debug!(
+            "Worker received generate request for session {:?}, generating response using {:?}",
+            session_id, backend
+        );
+        let metrics = Mutex::new(Metrics::default());
+        {
+            let mut metrics = metrics.lock().unwrap();
+            metrics.begin("total");
+            metrics.begin("first token");
+        }
+        let on_delta = |delta: &str| {
+            // only the first delta ends it
+            metrics.lock().unwrap().end("first token");
+            let partial = GameboundMessage::Partial {
+                session_id,
+                delta: delta.to_string(),
+            };
+            // the response carries the whole text, so a partial is dropped rather than holding up the stream
+            if let Err(TrySendError::Full(partial)) = reply_tx.try_send(partial) {
+                warn!("Gamebound channel is full, dropping {:?}", partial);
+            }
+        };
+        let tools = prompt
+            .prompt
+            .options()
+            .and_then(|options| options.tools)
+            .filter(|tools| {
+                !tools.is_empty()
+                    && backend.supports_tools()
+                    && config.supports_tools(&config.model)
+            });
+        let generation = async {
+            // tool calls arrive whole, so those requests are not streamed
+            if let Some(tools) = &tools {
+                match backend.generate_with_tools(prompt, config, tools).await {
+                    Ok(output) => return Ok(output),
+                    Err(e) => warn!(
+                        "Failed to generate with tools using {:?} and model {}, falling back to plain text: {:?}",
+                        backend, config.model, e
+                    ),
+                }
+            }
+            backend
+                .generate_streaming_output(prompt, config, &on_delta)
+                .await
+        };
+        let generation = tokio::time::timeout(*timeout, generation);
+        let msg = tokio::select! {
+            result = generation => match result {
+                Ok(Ok(output)) => {
+                    let mut metrics = metrics.lock().unwrap();
+                    metrics.end("total");
+                    debug!(
+                        "Generate request for session {:?} took {}",
+                        session_id,
+                        metrics.report()
+                    );
+                    let sample = TextInferenceSample {
+                        session_id,
+                        backend: backend.name().to_string(),
+                        model: config.model.clone(),
+                        priority: *priority,
+                        timing: TextInferenceTiming {
+                            queue_wait: *queue_wait,
+                            first_token: metrics.get("first token"),
+                            total: metrics.get("total").unwrap_or_default(),
+                        },
+                        usage: output.usage,
+                    };
+                    GameboundMessage::Response {
+                        session_id,
+                        prompt: prompt.clone(),
+                        response: output.text,
+                        tool_calls: output.tool_calls,
+                        sample,
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Failed to generate using {:?}: {:?}", backend, e);
+                    GameboundMessage::Failed {
+                        session_id,
+                        reason: e.to_string(),
+                    }
+                }
+                Err(_) => {
+                    warn!(
+                        "Generate request for session {:?} timed out after {:?}",
+                        session_id, timeout
+                    );
+                    GameboundMessage::Failed {
+                        session_id,
+                        reason: format!("Timed out after {:?}", timeout),
+                    }
+                }
+            },
+            // nothing is ever sent, this completes once the sender is dropped
+            _ = cancel_rx.changed() => {
+                debug!("Generate request for session {:?} was cancelled", session_id);
+                return Ok(());
+            }
+        };
+        send_gamebound(reply_tx, msg).await?;
+        Ok(())
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn bridge_generate_requests(
+    mut in_flight: ResMut<InFlightRequests>,
+    mut queue: ResMut<TextInferenceQueue>,
+    mut events: ParamSet<(
+        EventReader<TextInferenceEvent>,
+        EventWriter<TextInferenceEvent>,
+    )>,
+    mut queue_events: EventWriter<InferenceQueueEvent>,
+    templates: Res<PromptTemplates>,
+    text_assets: Res<Assets<TextAsset>>,
+    backends: Res<TextInferenceBackends>,
+    config: Res<InferenceConfig>,
+) {
+    let engine = PromptTemplateEngine::new(&templates, &text_assets);
+    let mut failures = Vec::new();
+    for event in events.p0().read() {
+        match event {
+            TextInferenceEvent::Cancel { session_id } => {
+                if queue.0.remove(*session_id).is_some() {
+                    debug!("Dropping queued request for session {:?}", session_id);
+                }
+                if in_flight.0.remove(session_id).is_some() {
+                    debug!("Cancelling running request for session {:?}", session_id);
+                    queue.0.finish(*session_id);
+                }
+            }
+            TextInferenceEvent::Request { session_id, prompt } => {
+                let Some(backend) = backends.for_session(*session_id) else {
+                    error!(
+                        "No text inference backend available for session {:?}, registered backends: {:?}",
+                        session_id,
+                        backends.names().collect::<Vec<_>>()
+                    );
+                    failures.push(TextInferenceEvent::Failed {
+                        session_id: *session_id,
+                        reason: "No text inference backend available".to_string(),
+                    });
+                    continue;
+                };
+
+                let materialized_prompt = match materialize(prompt, &engine) {
+                    Ok(materialized_prompt) => materialized_prompt,
+                    Err(e) => {
+                        error!("Failed to materialize prompt: {}", e);
+                        failures.push(TextInferenceEvent::Failed {
+                            session_id: *session_id,
+                            reason: e.to_string(),
+                        });
+                        continue;
+                    }
+                };
+
+                let options = prompt.options().unwrap_or_default();
+                let timeout = options.timeout.unwrap_or(config.request_timeout);
+                let key = prompt_hash(&materialized_prompt.materialized);
+                let queued = QueuedGenerate {
+                    prompt: materialized_prompt,
+                    backend,
+                    timeout,
+                    queued_at: Instant::now(),
+                };
+                let event = match queue.0.push(*session_id, options.priority, key, queued) {
+                    Enqueued::Queued => InferenceQueueEvent::Queued {
+                        session_id: *session_id,
+                        priority: options.priority,
+                        pending: queue.0.pending(),
+                    },
+                    Enqueued::Deduplicated => InferenceQueueEvent::Deduplicated {
+                        session_id: *session_id,
+                    },
+                    Enqueued::Replaced => InferenceQueueEvent::Replaced {
+                        session_id: *session_id,
+                    },
+                    Enqueued::Evicted(evicted) => {
+                        warn!(
+                            "Text inference queue is full, evicting request of session {:?} for {:?}",
+                            evicted.session_id, session_id
+                        );
+                        failures.push(TextInferenceEvent::Failed {
+                            session_id: evicted.session_id,
+                            reason: "Evicted from the full inference queue".to_string(),
+                        });
+                        InferenceQueueEvent::Evicted {
+                            session_id: evicted.session_id,
+                        }
+                    }
+                    Enqueued::Rejected(_) => {
+                        warn!(
+                            "Text inference queue is full, rejecting request of session {:?}",
+                            session_id
+                        );
+                        failures.push(TextInferenceEvent::Failed {
+                            session_id: *session_id,
+                            reason: "The inference queue is full".to_string(),
+                        });
+                        InferenceQueueEvent::Rejected {
+                            session_id: *session_id,
+                        }
+                    }
+                };
+                debug!("Sending event {:?}", event);
+                queue_events.send(event);
+            }
+            _ => {}
+        }
+    }
+    for event in failures {
+        debug!("Sending event {:?}", event);
+        events.p1().send(event);
+    }
+}
+
+fn dispatch_queued_requests(
+    mut bridge: EventWriter<ThreadboundMessage>,
+    mut in_flight: ResMut<InFlightRequests>,
+    mut queue: ResMut<TextInferenceQueue>,
+    config: Res<InferenceConfig>,
+) {
+    while queue.0.running() < MAX_RUNNING {
+        let Some(scheduled) = queue.0.pop() else {
+            break;
+        };
+        let session_id = scheduled.session_id;
+        let QueuedGenerate {
+            prompt,
+            backend,
+            timeout,
+            queued_at,
+        } = scheduled.item;
+        info!(
+            "Sending {:?} prompt to text generation worker using {:?}:\n{}",
+            scheduled.priority, backend, prompt.materialized
+        );
+
+        let mut config = config.clone();
+        if let Some(model) = prompt.prompt.options().and_then(|options| options.model) {
+            config.model = model;
+        }
+
+        let (cancel_tx, cancel_rx) = watch::channel(());
+        in_flight.0.insert(
+            session_id,
+            InFlightRequest {
+                cancel_tx,
+                closed: false,
+            },
+        );
+        bridge.send(ThreadboundMessage::Generate {
+            session_id,
+            prompt,
+            config,
+            backend,
+            timeout,
+            priority: scheduled.priority,
+            queue_wait: queued_at.elapsed(),
+            cancel_rx,
+        });
+    }
+}
+
+fn update_queue_status(
+    queue: Res<TextInferenceQueue>,
+    mut status: ResMut<InferenceQueueStatus>,
+    mut queue_events: EventWriter<InferenceQueueEvent>,
+) {
+    let new_status = InferenceQueueStatus {
+        pending: queue.0.pending(),
+        running: queue.0.running(),
+        capacity: queue.0.capacity,
+    };
+    if *status == new_status {
+        return;
+    }
+    match (status.is_saturated(), new_status.is_saturated()) {
+        (false, true) => queue_events.send(InferenceQueueEvent::Saturated {
+            pending: new_status.pending,
+        }),
+        (true, false) => queue_events.send(InferenceQueueEvent::Relieved {
+            pending: new_status.pending,
+        }),
+        _ => {}
+    }
+    *status = new_status;
+}
+
+fn materialize(
+    prompt: &TextPrompt,
+    engine: &PromptTemplateEngine,
+) -> Result<MaterializedTextPrompt, PromptTemplateError> {
+    let materialized_prompt = match prompt {
+        TextPrompt::Raw { content, .. } => MaterializedTextPrompt {
+            prompt: prompt.clone(),
+            materialized: engine.render(&PromptTemplateRef::new(
+                "raw",
+                PromptVariables::default().with("content", content.as_str()),
+            ))?,
+        },
+        TextPrompt::Chat {
+            chat_history,
+            persona,
+            ..
+        } => MaterializedTextPrompt {
+            prompt: prompt.clone(),
+            materialized: engine.render(&PromptTemplateRef::new(
+                "chat",
+                persona.clone().with("chat_history", chat_history.as_str()),
+            ))?,
+        },
+        TextPrompt::Template { template, .. } => MaterializedTextPrompt {
+            prompt: prompt.clone(),
+            materialized: engine.render(template)?,
+        },
+        TextPrompt::Messages {
+            system,
+            messages,
+            options,
+        } => {
+            // backends only ever see plain messages
+            let mut messages = messages.clone();
+            if let Some(system) = system {
+                let content = engine.render(system)?;
+                messages.insert(0, ChatMessage::new(ChatRole::System, content.trim()));
+            }
+            MaterializedTextPrompt {
+                materialized: render_chat_messages(&messages),
+                prompt: TextPrompt::Messages {
+                    system: None,
+                    messages,
+                    options: options.clone(),
+                },
+            }
+        }
+    };
+    Ok(materialized_prompt)
+}
+
+fn bridge_generate_responses(
+    mut bridge: EventReader<GameboundMessage>,
+    mut in_flight: ResMut<InFlightRequests>,
+    mut queue: ResMut<TextInferenceQueue>,
+    mut events: EventWriter<TextInferenceEvent>,
+    mut metrics: ResMut<InferenceMetrics>,
+) {
+    for msg in bridge.read() {
+        match msg.clone() {
+            GameboundMessage::Partial { session_id, delta } => {
+                let event = TextInferenceEvent::Partial { session_id, delta };
+                trace!("Received bridge partial, sending game event {:?}", event);
+                events.send(event);
+            }
+            GameboundMessage::Response {
+                session_id,
+                prompt,
+                response,
+                tool_calls,
+                sample,
+            } => {
+                // lets the next request of the session run
+                if in_flight.0.remove(&session_id).is_some() {
+                    queue.0.finish(session_id);
+                }
+                metrics.record(sample);
+                if !tool_calls.is_empty() {
+                    let event = TextInferenceEvent::ToolCalls {
+                        session_id,
+                        calls: tool_calls,
+                    };
+                    debug!("Received bridge tool calls, sending game event {:?}", event);
+                    events.send(event);
+                }
+                let event = TextInferenceEvent::Response {
+                    session_id,
+                    response,
+                    prompt,
+                };
+                debug!("Received bridge response, sending game event {:?}", event);
+                events.send(event);
+            }
+            GameboundMessage::Failed { session_id, reason } => {
+                if in_flight.0.remove(&session_id).is_some() {
+                    queue.0.finish(session_id);
+                }
+                metrics.record_failure();
+                let event = TextInferenceEvent::Failed { session_id, reason };
+                debug!("Received bridge failure, sending game event {:?}", event);
+                events.send(event);
+            }
+        }
+    }
+
+    // Requests lost to a full channel or a restarted worker have no receivers left.
+    // A reply sent just before letting go can still be on its way, so those get another frame
+    let mut lost = Vec::new();
+    in_flight.0.retain(|session_id, request| {
+        if !request.cancel_tx.is_closed() {
+            return true;
+        }
+        if !request.closed {
+            request.closed = true;
+            return true;
+        }
+        lost.push(*session_id);
+        false
+    });
+    for session_id in lost {
+        warn!(
+            "Text inference worker dropped the request of session {:?}",
+            session_id
+        );
+        queue.0.finish(session_id);
+        metrics.record_failure();
+        events.send(TextInferenceEvent::Failed {
+            session_id,
+            reason: "Text inference worker dropped the request".to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    /// Streams its reply word by word, but rejects tools like a server would for a model without tool support
+    #[derive(Default)]
+    struct ToollessBackend {
+        tool_requests: Arc<AtomicUsize>,
+    }
+
+    impl TextInferenceBackend for ToollessBackend {
+        fn name(&self) -> &str {
+            "toolless"
+        }
+
+        fn generate<'a>(
+            &'a self,
+            _prompt: &'a MaterializedTextPrompt,
+            _config: &'a InferenceConfig,
+        ) -> bevy::utils::BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+            Box::pin(async { Ok("Hello there".to_string()) })
+        }
+
+        fn generate_streaming<'a>(
+            &'a self,
+            prompt: &'a MaterializedTextPrompt,
+            config: &'a InferenceConfig,
+            on_delta: &'a (dyn Fn(&str) + Send + Sync),
+        ) -> bevy::utils::BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+            Box::pin(async move {
+                let response = self.generate(prompt, config).await?;
+                for word in response.split_inclusive(' ') {
+                    on_delta(word);
+                }
+                Ok(response)
+            })
+        }
+
+        fn supports_tools(&self) -> bool {
+            true
+        }
+
+        fn generate_with_tools<'a>(
+            &'a self,
+            _prompt: &'a MaterializedTextPrompt,
+            config: &'a InferenceConfig,
+            _tools: &'a [ToolDefinition],
+        ) -> bevy::utils::BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>>
+        {
+            self.tool_requests.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move { Err(format!("{} does not support tools", config.model).into()) })
+        }
+    }
+
+    /// The partials, the response and how often the backend was asked to use tools for a request offering a tool
+    fn generate_offering_tools(config: InferenceConfig) -> (Vec<String>, String, usize) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins);
+        app.add_plugins(InferenceTypesPlugin);
+        app.init_resource::<Assets<TextAsset>>();
+        app.add_plugins(TextInferenceWorkerPlugin);
+        app.insert_resource(config);
+        let backend = ToollessBackend::default();
+        let tool_requests = backend.tool_requests.clone();
+        app.world
+            .resource_mut::<TextInferenceBackends>()
+            .register(backend);
+        let session_id = app.world.spawn_empty().id();
+        app.world.send_event(TextInferenceEvent::Request {
+            session_id,
+            // plain messages need no templates, the test app has none loaded
+            prompt: TextPrompt::Messages {
+                system: None,
+                messages: vec![ChatMessage::new(ChatRole::User, "Hi")],
+                options: Some(TextInferenceOptions {
+                    tools: Some(vec![ToolDefinition {
+                        name: "Cube Tool".to_string(),
+                        description: "Spawns cubes".to_string(),
+                        actions: vec!["Spawn".to_string()],
+                    }]),
+                    ..default()
+                }),
+            },
+        });
+
+        let mut reader = bevy::ecs::event::ManualEventReader::<TextInferenceEvent>::default();
+        let mut partials = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            assert!(Instant::now() < deadline, "No response");
+            app.update();
+            let events = app.world.resource::<Events<TextInferenceEvent>>();
+            for event in reader.read(events) {
+                match event {
+                    TextInferenceEvent::Partial { delta, .. } => partials.push(delta.clone()),
+                    TextInferenceEvent::Response { response, .. } => {
+                        return (
+                            partials,
+                            response.clone(),
+                            tool_requests.load(Ordering::Relaxed),
+                        )
+                    }
+                    TextInferenceEvent::Failed { reason, .. } => panic!("Failed: {}", reason),
+                    _ => {}
+                }
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn tools_are_opt_in() {
+        let (partials, response, tool_requests) =
+            generate_offering_tools(InferenceConfig::default());
+        assert_eq!(tool_requests, 0);
+        assert_eq!(partials, vec!["Hello ", "there"]);
+        assert_eq!(response, "Hello there");
+    }
+
+    #[test]
+    fn rejected_tools_fall_back_to_streaming() {
+        let config = InferenceConfig::default();
+        let config = InferenceConfig {
+            tool_models: vec![config.model.clone()],
+            ..config
+        };
+        let (partials, response, tool_requests) = generate_offering_tools(config);
+        assert_eq!(tool_requests, 1);
+        assert_eq!(partials, vec!["Hello ", "there"]);
+        assert_eq!(response, "Hello there");
+    }
+}
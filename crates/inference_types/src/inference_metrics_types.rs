@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Tokens consumed by a single request, as reported by the backend
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextInferenceUsage {
+    /// Ollama's `prompt_eval_count`
+    pub prompt_tokens: usize,
+    /// Ollama's `eval_count`
+    pub completion_tokens: usize,
+}
+
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TextInferenceTiming {
+    /// Time spent in the inference queue before the worker picked the request up
+    pub queue_wait: Duration,
+    /// Time from the start of generation until the first streamed delta, unset when nothing was streamed
+    pub first_token: Option<Duration>,
+    /// Time from the start of generation until the response was complete
+    pub total: Duration,
+}
+
+/// Measurements of one finished text inference request
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub struct TextInferenceSample {
+    pub session_id: Entity,
+    pub backend: String,
+    pub model: String,
+    pub priority: InferencePriority,
+    pub timing: TextInferenceTiming,
+    pub usage: Option<TextInferenceUsage>,
+}
+
+impl TextInferenceSample {
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let usage = self.usage?;
+        let seconds = self.timing.total.as_secs_f64();
+        if seconds <= 0.0 {
+            return None;
+        }
+        Some(usage.completion_tokens as f64 / seconds)
+    }
+}
+
+/// Keeps the last `capacity` values of a measurement
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub struct RollingHistogram {
+    pub capacity: usize,
+    values: VecDeque<f64>,
+}
+
+impl RollingHistogram {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            values: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn last(&self) -> Option<f64> {
+        self.values.back().copied()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        Some(self.values.iter().sum::<f64>() / self.values.len() as f64)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::max)
+    }
+
+    /// Nearest-rank percentile, `p` ranges from 0 to 100
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        let mut sorted = self.values.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by(f64::total_cmp);
+        let rank = (p.clamp(0.0, 100.0) / 100.0 * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+
+    /// Counts of values in `count` equal-width buckets spanning 0 to [`RollingHistogram::max`]
+    pub fn buckets(&self, count: usize) -> Vec<usize> {
+        let mut buckets = vec![0; count];
+        let Some(max) = self.max().filter(|max| *max > 0.0) else {
+            if let Some(first) = buckets.first_mut() {
+                *first = self.values.len();
+            }
+            return buckets;
+        };
+        for value in self.values.iter() {
+            let index = ((value / max) * count as f64) as usize;
+            buckets[index.min(count - 1)] += 1;
+        }
+        buckets
+    }
+}
+
+/// Latency and token usage of recent text inference requests
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct InferenceMetrics {
+    pub queue_wait_ms: RollingHistogram,
+    pub first_token_ms: RollingHistogram,
+    pub total_ms: RollingHistogram,
+    pub prompt_tokens: RollingHistogram,
+    pub completion_tokens: RollingHistogram,
+    pub tokens_per_second: RollingHistogram,
+    /// Newest last
+    pub recent: VecDeque<TextInferenceSample>,
+    pub requests: usize,
+    pub failures: usize,
+    pub prompt_tokens_total: usize,
+    pub completion_tokens_total: usize,
+}
+
+impl InferenceMetrics {
+    /// How many requests the histograms and [`InferenceMetrics::recent`] remember
+    pub const WINDOW: usize = 100;
+
+    pub fn record(&mut self, sample: TextInferenceSample) {
+        let timing = sample.timing;
+        self.requests += 1;
+        self.queue_wait_ms
+            .push(timing.queue_wait.as_secs_f64() * 1000.0);
+        if let Some(first_token) = timing.first_token {
+            self.first_token_ms.push(first_token.as_secs_f64() * 1000.0);
+        }
+        self.total_ms.push(timing.total.as_secs_f64() * 1000.0);
+        if let Some(usage) = sample.usage {
+            self.prompt_tokens.push(usage.prompt_tokens as f64);
+            self.completion_tokens.push(usage.completion_tokens as f64);
+            self.prompt_tokens_total += usage.prompt_tokens;
+            self.completion_tokens_total += usage.completion_tokens;
+        }
+        if let Some(tokens_per_second) = sample.tokens_per_second() {
+            self.tokens_per_second.push(tokens_per_second);
+        }
+        if self.recent.len() == Self::WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sample);
+    }
+
+    pub fn record_failure(&mut self) {
+        self.requests += 1;
+        self.failures += 1;
+    }
+}
+
+impl Default for InferenceMetrics {
+    fn default() -> Self {
+        Self {
+            queue_wait_ms: RollingHistogram::new(Self::WINDOW),
+            first_token_ms: RollingHistogram::new(Self::WINDOW),
+            total_ms: RollingHistogram::new(Self::WINDOW),
+            prompt_tokens: RollingHistogram::new(Self::WINDOW),
+            completion_tokens: RollingHistogram::new(Self::WINDOW),
+            tokens_per_second: RollingHistogram::new(Self::WINDOW),
+            recent: VecDeque::with_capacity(Self::WINDOW),
+            requests: 0,
+            failures: 0,
+            prompt_tokens_total: 0,
+            completion_tokens_total: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_histogram() {
+        let mut histogram = RollingHistogram::new(4);
+        assert_eq!(histogram.percentile(50.0), None);
+        for value in [10.0, 1.0, 2.0, 3.0, 4.0] {
+            histogram.push(value);
+        }
+        // the oldest value rolled out
+        assert_eq!(histogram.len(), 4);
+        assert_eq!(histogram.max(), Some(4.0));
+        assert_eq!(histogram.mean(), Some(2.5));
+        assert_eq!(histogram.percentile(50.0), Some(2.0));
+        assert_eq!(histogram.percentile(95.0), Some(4.0));
+        assert_eq!(histogram.buckets(2), vec![1, 3]);
+    }
+}
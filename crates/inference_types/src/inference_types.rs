@@ -1,10 +1,171 @@
 use crate::prelude::*;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use serde::Serialize;
+use std::time::Duration;
 
 #[derive(Reflect, Debug, Clone, Default, Eq, PartialEq)]
 pub struct TextInferenceOptions {
     pub num_predict: Option<usize>,
+    /// Most important first, backends that accept only a few keep the leading ones
     pub stop: Option<Vec<String>>,
+    /// Overrides [`InferenceConfig::request_timeout`] for this request
+    pub timeout: Option<Duration>,
+    /// Offered to backends that support tool calls, see [`TextInferenceEvent::ToolCalls`]
+    pub tools: Option<Vec<ToolDefinition>>,
+    pub priority: InferencePriority,
+    /// Overrides [`InferenceConfig::model`] for this request
+    pub model: Option<String>,
+}
+
+/// Order in which queued requests are served, higher first
+#[derive(Reflect, Debug, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum InferencePriority {
+    /// Housekeeping such as summarizing old observations
+    Background,
+    #[default]
+    Observation,
+    /// Someone is waiting for a reply
+    UserChat,
+}
+
+/// How busy the text inference queue is
+#[derive(Resource, Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Resource)]
+pub struct InferenceQueueStatus {
+    pub pending: usize,
+    pub running: usize,
+    pub capacity: usize,
+}
+
+impl InferenceQueueStatus {
+    pub fn is_saturated(&self) -> bool {
+        self.capacity > 0 && self.pending >= self.capacity
+    }
+}
+
+#[derive(Event, Reflect, Debug, Clone)]
+pub enum InferenceQueueEvent {
+    Queued {
+        session_id: Entity,
+        priority: InferencePriority,
+        pending: usize,
+    },
+    /// The same prompt is already queued or running for the session, the request was dropped
+    Deduplicated {
+        session_id: Entity,
+    },
+    /// A newer request of the session took the place of its queued one
+    Replaced {
+        session_id: Entity,
+    },
+    /// Made room for a request of higher priority, a [`TextInferenceEvent::Failed`] is sent for it too
+    Evicted {
+        session_id: Entity,
+    },
+    /// The queue is full, a [`TextInferenceEvent::Failed`] is sent for the request too
+    Rejected {
+        session_id: Entity,
+    },
+    /// The queue reached its capacity, requests may be rejected until [`InferenceQueueEvent::Relieved`]
+    Saturated {
+        pending: usize,
+    },
+    Relieved {
+        pending: usize,
+    },
+}
+
+/// Connection and sampling settings applied to every text inference request.
+///
+/// Unset sampling values are left out of the request so the server defaults apply.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct InferenceConfig {
+    /// Where the server of each backend listens, by [`TextInferenceBackend::name`]
+    pub base_urls: HashMap<String, String>,
+    pub model: String,
+    /// Used for [`EmbeddingInferenceEvent`]s, chat models usually make poor embeddings
+    pub embedding_model: String,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub seed: Option<i64>,
+    pub context_length: Option<usize>,
+    /// How long the server should keep the model loaded after a request, e.g. "5m"
+    pub keep_alive: Option<String>,
+    /// Requests taking longer than this are abandoned and reported as failed
+    pub request_timeout: Duration,
+    /// Models that are offered tools, the others only reply in streamed plain text.
+    ///
+    /// Opt-in since servers reject requests with tools for models that weren't trained for them.
+    pub tool_models: Vec<String>,
+}
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        Self {
+            base_urls: [
+                ("ollama", "http://localhost:11434"),
+                ("openai", "https://api.openai.com"),
+                ("llama.cpp", "http://localhost:8080"),
+            ]
+            .into_iter()
+            .map(|(backend, base_url)| (backend.to_string(), base_url.to_string()))
+            .collect(),
+            model: "whatevs".to_string(),
+            embedding_model: "nomic-embed-text".to_string(),
+            temperature: None,
+            top_p: None,
+            seed: None,
+            context_length: None,
+            keep_alive: None,
+            request_timeout: Duration::from_secs(120),
+            tool_models: Vec::new(),
+        }
+    }
+}
+
+impl InferenceConfig {
+    /// The base URL of the named backend, empty for backends that don't talk to a server
+    pub fn base_url(&self, backend: &str) -> &str {
+        self.base_urls
+            .get(backend)
+            .map(|base_url| base_url.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn supports_tools(&self, model: &str) -> bool {
+        self.tool_models
+            .iter()
+            .any(|tool_model| tool_model == model)
+    }
+}
+
+/// Whether text inference goes through recorded fixtures instead of only a live backend
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InferenceFixtureMode {
+    /// Responses from the active backend are saved alongside the prompt that produced them
+    Record,
+    /// Saved responses are served back without contacting any server
+    Replay,
+}
+
+impl InferenceFixtureMode {
+    /// Reads `CURSOR_HERO_INFERENCE_FIXTURES`, which may be `record` or `replay`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var("CURSOR_HERO_INFERENCE_FIXTURES")
+            .ok()?
+            .as_str()
+        {
+            "record" => Some(InferenceFixtureMode::Record),
+            "replay" => Some(InferenceFixtureMode::Replay),
+            other => {
+                warn!("Unknown inference fixture mode {:?}, ignoring", other);
+                None
+            }
+        }
+    }
 }
 
 #[derive(Event, Reflect, Debug, Clone)]
@@ -13,11 +174,49 @@ pub enum TextInferenceEvent {
         session_id: Entity,
         prompt: TextPrompt,
     },
+    /// A piece of a response that is still being generated.
+    /// The concatenated deltas of a session precede its [`TextInferenceEvent::Response`].
+    Partial {
+        session_id: Entity,
+        delta: String,
+    },
     Response {
         session_id: Entity,
         prompt: MaterializedTextPrompt,
         response: String,
     },
+    /// Tool calls made by the model, sent right before the [`TextInferenceEvent::Response`] of the same request
+    ToolCalls {
+        session_id: Entity,
+        calls: Vec<ToolCall>,
+    },
+    /// Abandons the queued or running request of a session, no response will be sent for it.
+    /// A new request for a session replaces its queued request, a running one finishes first.
+    Cancel {
+        session_id: Entity,
+    },
+    Failed {
+        session_id: Entity,
+        reason: String,
+    },
+}
+
+#[derive(Event, Reflect, Debug, Clone)]
+pub enum EmbeddingInferenceEvent {
+    Request {
+        session_id: Entity,
+        text: String,
+    },
+    Response {
+        session_id: Entity,
+        text: String,
+        embedding: Vec<f32>,
+    },
+    Failed {
+        session_id: Entity,
+        text: String,
+        reason: String,
+    },
 }
 
 #[derive(Event, Reflect, Debug, Clone)]
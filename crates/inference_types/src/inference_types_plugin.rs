@@ -6,10 +6,33 @@ impl Plugin for InferenceTypesPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<TextPrompt>();
         app.register_type::<MaterializedTextPrompt>();
+        app.register_type::<ChatMessage>();
+        app.register_type::<ChatRole>();
+        app.register_type::<PromptValue>();
+        app.register_type::<PromptVariables>();
+        app.register_type::<PromptTemplateRef>();
+        app.init_resource::<PromptTemplates>();
         app.register_type::<TextInferenceEvent>();
         app.add_event::<TextInferenceEvent>();
+        app.init_resource::<TextInferenceBackends>();
+        app.register_type::<InferenceConfig>();
+        app.init_resource::<InferenceConfig>();
+        app.init_resource::<TextTokenizer>();
+        app.register_type::<InferenceFixtureMode>();
+        app.register_type::<ToolDefinition>();
+        app.register_type::<ToolCall>();
 
         app.register_type::<SpeechPrompt>();
+        app.register_type::<InferencePriority>();
+        app.register_type::<InferenceQueueStatus>();
+        app.init_resource::<InferenceQueueStatus>();
+        app.register_type::<InferenceQueueEvent>();
+        app.add_event::<InferenceQueueEvent>();
+        app.register_type::<InferenceMetrics>();
+        app.init_resource::<InferenceMetrics>();
+
+        app.register_type::<EmbeddingInferenceEvent>();
+        app.add_event::<EmbeddingInferenceEvent>();
         app.register_type::<SpeechInferenceEvent>();
         app.add_event::<SpeechInferenceEvent>();
 
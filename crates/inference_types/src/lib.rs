@@ -1,9 +1,19 @@
+pub mod inference_metrics_types;
 pub mod inference_types;
 pub mod inference_types_plugin;
+pub mod prompt_template_types;
 pub mod prompt_types;
+pub mod text_inference_backend_types;
+pub mod tokenizer_types;
+pub mod tool_call_types;
 
 pub mod prelude {
+    pub use crate::inference_metrics_types::*;
     pub use crate::inference_types::*;
     pub use crate::inference_types_plugin::InferenceTypesPlugin;
+    pub use crate::prompt_template_types::*;
     pub use crate::prompt_types::*;
+    pub use crate::text_inference_backend_types::*;
+    pub use crate::tokenizer_types::*;
+    pub use crate::tool_call_types::*;
 }
@@ -0,0 +1,118 @@
+use bevy::asset::LoadedFolder;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use cursor_hero_text_asset_types::text_asset_loader_types::TextAsset;
+
+/// Something that can be bound to a name when rendering a prompt template
+#[derive(Reflect, Debug, PartialEq, Eq, Clone)]
+pub enum PromptValue {
+    Text(String),
+    Bool(bool),
+    List(Vec<PromptValue>),
+    Object(PromptVariables),
+}
+
+impl PromptValue {
+    /// Decides which branch of an `{{#if}}` block is rendered
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            PromptValue::Text(text) => !text.is_empty(),
+            PromptValue::Bool(value) => *value,
+            PromptValue::List(items) => !items.is_empty(),
+            PromptValue::Object(fields) => !fields.is_empty(),
+        }
+    }
+}
+
+impl From<&str> for PromptValue {
+    fn from(value: &str) -> Self {
+        PromptValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for PromptValue {
+    fn from(value: String) -> Self {
+        PromptValue::Text(value)
+    }
+}
+
+impl From<bool> for PromptValue {
+    fn from(value: bool) -> Self {
+        PromptValue::Bool(value)
+    }
+}
+
+impl From<PromptVariables> for PromptValue {
+    fn from(value: PromptVariables) -> Self {
+        PromptValue::Object(value)
+    }
+}
+
+impl<T: Into<PromptValue>> From<Vec<T>> for PromptValue {
+    fn from(value: Vec<T>) -> Self {
+        PromptValue::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Reflect, Debug, Default, PartialEq, Eq, Clone)]
+pub struct PromptVariables {
+    values: HashMap<String, PromptValue>,
+}
+
+impl PromptVariables {
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<PromptValue>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<PromptValue>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PromptValue> {
+        self.values.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// A template from `prompt_templates/` along with the variables to render it with
+#[derive(Reflect, Debug, PartialEq, Eq, Clone)]
+pub struct PromptTemplateRef {
+    /// File stem of the template, `chat` for `prompt_templates/chat.txt`
+    pub name: String,
+    pub variables: PromptVariables,
+}
+
+impl PromptTemplateRef {
+    pub fn new(name: impl Into<String>, variables: PromptVariables) -> Self {
+        Self {
+            name: name.into(),
+            variables,
+        }
+    }
+}
+
+/// Every template in `prompt_templates/`, keyed by file stem.
+///
+/// The handles point at [`TextAsset`]s, so edits to the files are picked up whenever the asset is reloaded.
+#[derive(Resource, Debug, Default)]
+pub struct PromptTemplates {
+    pub folder: Handle<LoadedFolder>,
+    pub templates: HashMap<String, Handle<TextAsset>>,
+}
+
+impl PromptTemplates {
+    pub fn get(&self, name: &str) -> Option<&Handle<TextAsset>> {
+        self.templates.get(name)
+    }
+
+    pub fn name_of(&self, id: AssetId<TextAsset>) -> Option<&str> {
+        self.templates
+            .iter()
+            .find(|(_, handle)| handle.id() == id)
+            .map(|(name, _)| name.as_str())
+    }
+}
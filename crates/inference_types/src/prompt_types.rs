@@ -1,6 +1,7 @@
 use bevy::prelude::*;
-use cursor_hero_text_asset_types::text_asset_loader_types::TextAsset;
 
+use crate::prelude::PromptTemplateRef;
+use crate::prelude::PromptVariables;
 use crate::prelude::TextInferenceOptions;
 
 #[derive(Reflect, Debug, PartialEq, Eq, Clone)]
@@ -11,6 +12,20 @@ pub enum TextPrompt {
     },
     Chat {
         chat_history: String,
+        /// Variables for the `persona` template, such as `agent_name`
+        persona: PromptVariables,
+        options: Option<TextInferenceOptions>,
+    },
+    /// Any template from `prompt_templates/`
+    Template {
+        template: PromptTemplateRef,
+        options: Option<TextInferenceOptions>,
+    },
+    /// Structured conversation for backends with a chat endpoint, see [`render_chat_messages`] for the others
+    Messages {
+        /// Rendered and prepended as a system message before the prompt reaches a backend
+        system: Option<PromptTemplateRef>,
+        messages: Vec<ChatMessage>,
         options: Option<TextInferenceOptions>,
     },
 }
@@ -20,8 +35,88 @@ impl TextPrompt {
         match self {
             TextPrompt::Raw { options, .. } => options.clone(),
             TextPrompt::Chat { options, .. } => options.clone(),
+            TextPrompt::Template { options, .. } => options.clone(),
+            TextPrompt::Messages { options, .. } => options.clone(),
+        }
+    }
+}
+
+#[derive(Reflect, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChatRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl ChatRole {
+    /// Role name as used by Ollama and OpenAI-style chat APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+        }
+    }
+}
+
+#[derive(Reflect, Debug, PartialEq, Eq, Clone)]
+pub struct ChatMessage {
+    pub role: ChatRole,
+    /// Who is speaking, for conversations with more than one user or assistant
+    pub name: Option<String>,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            name: None,
+            content: content.into(),
         }
     }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+}
+
+/// Flattens a conversation into a completion prompt for backends without a chat endpoint.
+///
+/// The prompt ends with a turn for the most recent assistant so the completion is its reply.
+pub fn render_chat_messages(messages: &[ChatMessage]) -> String {
+    let label = |role: ChatRole, name: Option<&str>| {
+        let role = match role {
+            ChatRole::System => "(System)",
+            ChatRole::User => "(User)",
+            ChatRole::Assistant => "(Agent)",
+        };
+        match name {
+            Some(name) => format!("{} {}", role, name),
+            None => role.to_string(),
+        }
+    };
+    let mut rendered = String::new();
+    for message in messages {
+        if message.role == ChatRole::System && message.name.is_none() {
+            rendered.push_str(message.content.trim());
+            rendered.push_str("\n\n");
+        } else {
+            rendered.push_str(&label(message.role, message.name.as_deref()));
+            rendered.push_str(": ");
+            rendered.push_str(message.content.trim());
+            rendered.push('\n');
+        }
+    }
+    let assistant_name = messages
+        .iter()
+        .rev()
+        .find(|message| message.role == ChatRole::Assistant)
+        .and_then(|message| message.name.as_deref());
+    rendered.push_str(&label(ChatRole::Assistant, assistant_name));
+    rendered.push(':');
+    rendered
 }
 
 #[derive(Reflect, Debug, PartialEq, Eq, Clone)]
@@ -30,19 +125,35 @@ pub struct MaterializedTextPrompt {
     pub materialized: String,
 }
 
-#[derive(Resource, Debug, Default, Reflect, PartialEq, Eq, Clone)]
-#[reflect(Resource)]
-pub struct TextPromptHandles {
-    pub raw: Handle<TextAsset>,
-    pub chat: Handle<TextAsset>,
-}
-
 #[derive(Reflect, Debug, PartialEq, Eq, Clone)]
 pub enum SpeechPrompt {
-    Raw { content: String },
+    Raw {
+        content: String,
+        /// Unset for the default voice
+        voice: Option<String>,
+    },
 }
 
 #[derive(Reflect, Debug, PartialEq, Eq, Clone)]
 pub enum TranscriptionPrompt {
     Raw { content: Vec<u8> },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_ends_with_assistant_turn() {
+        let rendered = render_chat_messages(&[
+            ChatMessage::new(ChatRole::System, "You are helpful."),
+            ChatMessage::new(ChatRole::User, "Hello").with_name("Tume Eena"),
+            ChatMessage::new(ChatRole::Assistant, "Hi!").with_name("Ithia Tig"),
+            ChatMessage::new(ChatRole::User, "How are you?").with_name("Tume Eena"),
+        ]);
+        assert_eq!(
+            rendered,
+            "You are helpful.\n\n(User) Tume Eena: Hello\n(Agent) Ithia Tig: Hi!\n(User) Tume Eena: How are you?\n(Agent) Ithia Tig:"
+        );
+    }
+}
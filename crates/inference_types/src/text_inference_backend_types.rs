@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use bevy::utils::HashMap;
+
+use crate::prelude::*;
+
+pub type TextInferenceBackendError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Something that can turn a materialized prompt into a completion.
+///
+/// Backends are registered in [`TextInferenceBackends`] and invoked from the text inference worker thread,
+/// which is why the returned future must be `Send`.
+pub trait TextInferenceBackend: Send + Sync + 'static {
+    /// Unique name used to select this backend.
+    fn name(&self) -> &str;
+
+    fn generate<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>>;
+
+    /// Like [`TextInferenceBackend::generate`], but reports pieces of the response through `on_delta` as they arrive.
+    ///
+    /// Backends that can't stream produce the whole response at once without calling `on_delta`.
+    fn generate_streaming<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        _on_delta: &'a (dyn Fn(&str) + Send + Sync),
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        self.generate(prompt, config)
+    }
+
+    /// Embeds `text` using [`InferenceConfig::embedding_model`], see [`EmbeddingInferenceEvent`]
+    fn embed<'a>(
+        &'a self,
+        _text: &'a str,
+        _config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<Vec<f32>, TextInferenceBackendError>> {
+        Box::pin(async move { Err(format!("{} does not support embeddings", self.name()).into()) })
+    }
+
+    /// Whether [`TextInferenceBackend::generate_with_tools`] can produce tool calls
+    fn supports_tools(&self) -> bool {
+        false
+    }
+
+    /// Like [`TextInferenceBackend::generate`], but the model may call the given tools instead of or in addition to replying.
+    fn generate_with_tools<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        _tools: &'a [ToolDefinition],
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(async move {
+            Ok(TextInferenceOutput {
+                text: self.generate(prompt, config).await?,
+                tool_calls: Vec::new(),
+                usage: None,
+            })
+        })
+    }
+
+    /// Like [`TextInferenceBackend::generate_streaming`], but also reports token usage when the backend knows it
+    fn generate_streaming_output<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        on_delta: &'a (dyn Fn(&str) + Send + Sync),
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(async move {
+            Ok(TextInferenceOutput {
+                text: self.generate_streaming(prompt, config, on_delta).await?,
+                tool_calls: Vec::new(),
+                usage: None,
+            })
+        })
+    }
+}
+
+impl std::fmt::Debug for dyn TextInferenceBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TextInferenceBackend({})", self.name())
+    }
+}
+
+/// Registry of the available text inference backends.
+///
+/// The first backend registered becomes the active one.
+/// Individual sessions can be pointed at a different backend without changing the default.
+#[derive(Resource, Default)]
+pub struct TextInferenceBackends {
+    backends: HashMap<String, Arc<dyn TextInferenceBackend>>,
+    active: Option<String>,
+    session_overrides: HashMap<Entity, String>,
+}
+
+impl TextInferenceBackends {
+    pub fn register(&mut self, backend: impl TextInferenceBackend) {
+        let name = backend.name().to_string();
+        if self.backends.contains_key(&name) {
+            warn!(
+                "Replacing already registered text inference backend {}",
+                name
+            );
+        }
+        if self.active.is_none() {
+            self.active = Some(name.clone());
+        }
+        self.backends.insert(name, Arc::new(backend));
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.backends.keys().map(|name| name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn TextInferenceBackend>> {
+        self.backends.get(name).cloned()
+    }
+
+    pub fn active_name(&self) -> Option<&str> {
+        self.active.as_deref()
+    }
+
+    /// Returns false if no backend by that name is registered.
+    pub fn set_active(&mut self, name: &str) -> bool {
+        if !self.backends.contains_key(name) {
+            return false;
+        }
+        self.active = Some(name.to_string());
+        true
+    }
+
+    /// Returns false if no backend by that name is registered.
+    pub fn set_session_backend(&mut self, session_id: Entity, name: &str) -> bool {
+        if !self.backends.contains_key(name) {
+            return false;
+        }
+        self.session_overrides.insert(session_id, name.to_string());
+        true
+    }
+
+    pub fn clear_session_backend(&mut self, session_id: Entity) {
+        self.session_overrides.remove(&session_id);
+    }
+
+    pub fn for_session(&self, session_id: Entity) -> Option<Arc<dyn TextInferenceBackend>> {
+        let name = self
+            .session_overrides
+            .get(&session_id)
+            .or(self.active.as_ref())?;
+        self.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NamedBackend(&'static str);
+    impl TextInferenceBackend for NamedBackend {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn generate<'a>(
+            &'a self,
+            _prompt: &'a MaterializedTextPrompt,
+            _config: &'a InferenceConfig,
+        ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+            Box::pin(async move { Ok(self.0.to_string()) })
+        }
+    }
+
+    #[test]
+    fn first_registered_is_active() {
+        let mut backends = TextInferenceBackends::default();
+        assert!(backends.for_session(Entity::PLACEHOLDER).is_none());
+        backends.register(NamedBackend("first"));
+        backends.register(NamedBackend("second"));
+        assert_eq!(backends.active_name(), Some("first"));
+        assert!(!backends.set_active("missing"));
+        assert!(backends.set_active("second"));
+        assert_eq!(backends.active_name(), Some("second"));
+    }
+
+    #[test]
+    fn session_override() {
+        let mut backends = TextInferenceBackends::default();
+        backends.register(NamedBackend("first"));
+        backends.register(NamedBackend("second"));
+        let session_id = Entity::from_raw(7);
+        assert!(backends.set_session_backend(session_id, "second"));
+        assert_eq!(backends.for_session(session_id).unwrap().name(), "second");
+        assert_eq!(
+            backends.for_session(Entity::PLACEHOLDER).unwrap().name(),
+            "first"
+        );
+        backends.clear_session_backend(session_id);
+        assert_eq!(backends.for_session(session_id).unwrap().name(), "first");
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+/// Used when [`crate::inference_types::InferenceConfig::context_length`] is unset, matches the Ollama default
+pub const DEFAULT_CONTEXT_LENGTH: usize = 2048;
+
+/// Counts tokens the way a model would, so prompts can be sized to fit its context window.
+pub trait Tokenizer: Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Longest prefix of `text` that is at most `max_tokens` long.
+    fn truncate<'t>(&self, text: &'t str, max_tokens: usize) -> &'t str {
+        if self.count_tokens(text) <= max_tokens {
+            return text;
+        }
+        let boundaries = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .collect::<Vec<_>>();
+        // binary search for the last boundary that still fits
+        let (mut low, mut high) = (0, boundaries.len() - 1);
+        while low < high {
+            let mid = (low + high + 1) / 2;
+            if self.count_tokens(&text[..boundaries[mid]]) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        &text[..boundaries[low]]
+    }
+}
+
+impl std::fmt::Debug for dyn Tokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Tokenizer({})", self.name())
+    }
+}
+
+/// Estimates without knowing the model's vocabulary.
+///
+/// English averages about four characters or three quarters of a word per token, the larger estimate wins.
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        let by_chars = (text.chars().count() + 3) / 4;
+        let by_words = (text.split_whitespace().count() * 4 + 2) / 3;
+        by_chars.max(by_words)
+    }
+}
+
+/// The tokenizer used for prompt budgeting, replace it to match the model in use.
+#[derive(Resource, Clone, Debug)]
+pub struct TextTokenizer(pub Arc<dyn Tokenizer>);
+
+impl Default for TextTokenizer {
+    fn default() -> Self {
+        Self(Arc::new(HeuristicTokenizer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heuristic_counts() {
+        assert_eq!(HeuristicTokenizer.count_tokens(""), 0);
+        assert_eq!(HeuristicTokenizer.count_tokens("abcdefghijkl"), 3);
+        assert_eq!(HeuristicTokenizer.count_tokens("a b c"), 4);
+    }
+
+    #[test]
+    fn truncate_fits_budget() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let truncated = HeuristicTokenizer.truncate(text, 4);
+        assert!(HeuristicTokenizer.count_tokens(truncated) <= 4);
+        assert!(text.starts_with(truncated));
+        assert!(!truncated.is_empty());
+        assert_eq!(HeuristicTokenizer.truncate(text, 100), text);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::inference_metrics_types::TextInferenceUsage;
+use bevy::prelude::*;
+
+/// A toolbelt tool offered to the model as a function it can call
+#[derive(Reflect, Debug, Clone, PartialEq, Eq)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub description: String,
+    pub actions: Vec<String>,
+}
+
+impl ToolDefinition {
+    /// Tool names contain spaces, which function calling APIs reject
+    pub fn function_name(&self) -> String {
+        function_name(&self.name)
+    }
+
+    pub fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": self.actions,
+                    "description": "Which action of the tool to perform",
+                },
+                "x": {
+                    "type": "number",
+                    "description": "Horizontal direction for actions that take one, from -1 (left) to 1 (right)",
+                },
+                "y": {
+                    "type": "number",
+                    "description": "Vertical direction for actions that take one, from -1 (down) to 1 (up)",
+                },
+            },
+            "required": ["action"],
+        })
+    }
+
+    /// Function definition in the format shared by Ollama and OpenAI-style APIs
+    pub fn function_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.function_name(),
+                "description": self.description,
+                "parameters": self.parameters_schema(),
+            }
+        })
+    }
+}
+
+pub fn function_name(tool_name: &str) -> String {
+    tool_name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// The model asking for a tool action to be performed
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub struct ToolCall {
+    /// Matches [`ToolDefinition::function_name`]
+    pub function_name: String,
+    pub action: String,
+    pub direction: Option<Vec2>,
+}
+
+impl ToolCall {
+    /// `arguments` may be an object or a string containing one, backends differ
+    pub fn parse(function_name: &str, arguments: &serde_json::Value) -> Result<Self, String> {
+        let parsed;
+        let arguments = match arguments {
+            serde_json::Value::String(text) => {
+                parsed = serde_json::from_str::<serde_json::Value>(text)
+                    .map_err(|e| format!("Tool call arguments are not JSON: {}", e))?;
+                &parsed
+            }
+            other => other,
+        };
+        let Some(action) = arguments.get("action").and_then(|action| action.as_str()) else {
+            return Err(format!(
+                "Tool call to {} is missing an action: {}",
+                function_name, arguments
+            ));
+        };
+        let axis = |name: &str| arguments.get(name).and_then(|value| value.as_f64());
+        let direction = match (axis("x"), axis("y")) {
+            (None, None) => None,
+            (x, y) => Some(Vec2::new(
+                x.unwrap_or_default() as f32,
+                y.unwrap_or_default() as f32,
+            )),
+        };
+        Ok(Self {
+            function_name: function_name.to_string(),
+            action: action.to_string(),
+            direction,
+        })
+    }
+}
+
+/// What a backend produced when tools were offered
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInferenceOutput {
+    pub text: String,
+    pub tool_calls: Vec<ToolCall>,
+    /// Unset when the backend does not report token counts
+    pub usage: Option<TextInferenceUsage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tool_calls() {
+        let call = ToolCall::parse(
+            "movement_tool",
+            &serde_json::json!({"action": "Move", "x": 1, "y": -0.5}),
+        )
+        .unwrap();
+        assert_eq!(call.action, "Move");
+        assert_eq!(call.direction, Some(Vec2::new(1.0, -0.5)));
+
+        let call =
+            ToolCall::parse("cube_tool", &serde_json::json!("{\"action\": \"Spawn\"}")).unwrap();
+        assert_eq!(call.action, "Spawn");
+        assert_eq!(call.direction, None);
+
+        assert!(ToolCall::parse("cube_tool", &serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn function_names_are_api_safe() {
+        assert_eq!(function_name("Cube Tool"), "cube_tool");
+    }
+}
@@ -8,107 +8,76 @@ use cursor_hero_memory_types::prelude::*;
 use serde::Deserialize;
 use serde::Serialize;
 
-pub struct AppMemoryPlugin;
-
-impl Plugin for AppMemoryPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(AppMemoryConfig::default());
-        app.add_systems(Update, persist.pipe(handle_persist_errors));
-        app.add_systems(Update, restore.pipe(handle_restore_errors));
-    }
-}
-const PERSIST_FILE_NAME: &str = "apps.json";
-
-// not moved to lib to ensure log contains this module name
-fn handle_persist_errors(In(result): In<Result<PersistSuccess, PersistError>>) {
-    if let Err(e) = result {
-        error!("Persist error occurred: {:?}", e);
-    } else if let Ok(PersistSuccess::WritePerformed) = result {
-        debug!("Persisted succeeded");
-    }
-}
-
-fn handle_restore_errors(In(result): In<Result<RestoreSuccess, RestoreError>>) {
-    if let Err(e) = result {
-        error!("Restore error occurred: {:?}", e);
-    } else if let Ok(RestoreSuccess::Performed) = result {
-        info!("Restore succeeded");
-    }
-}
-
-#[derive(Debug, Resource, Reflect)]
-#[reflect(Resource)]
-struct AppMemoryConfig {
-    pub debounce_timer: Timer,
-}
-impl Default for AppMemoryConfig {
-    fn default() -> Self {
-        Self {
-            debounce_timer: Timer::from_seconds(10.0, TimerMode::Repeating),
-        }
-    }
-}
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-struct DiskData {
+pub struct AppMemory {
     calculator_positions: Vec<Vec2>,
 }
 
-fn persist(
-    mut config: ResMut<AppMemoryConfig>,
-    memory_config: Res<MemoryConfig>,
-    mut debounce: Local<Option<DiskData>>,
-    time: Res<Time>,
-    calculator_query: Query<&Transform, With<Calculator>>,
-) -> Result<PersistSuccess, PersistError> {
-    if !config.debounce_timer.tick(time.delta()).just_finished() {
-        return Ok(PersistSuccess::Cooldown);
-    }
+impl Persistable for AppMemory {
+    const FILE_NAME: &'static str = "apps.json";
+    const RESTORE_STRATEGY: RestoreStrategy = RestoreStrategy::WhenReady;
 
-    let mut calculator_positions = vec![];
-    for transform in calculator_query.iter() {
-        calculator_positions.push(transform.translation.xy());
-    }
-    let data = DiskData {
-        calculator_positions,
-    };
+    type PersistParam = Query<'static, 'static, &'static Transform, With<Calculator>>;
+    type RestoreParam = (
+        EventWriter<'static, SpawnCalculatorRequestEvent>,
+        Query<'static, 'static, Entity, Added<AgentEnvironment>>,
+        Query<'static, 'static, Entity, With<AgentEnvironment>>,
+        Query<'static, 'static, Entity, With<Calculator>>,
+        Commands<'static, 'static>,
+    );
 
-    if debounce.is_none() || debounce.as_ref().unwrap() != &data {
-        let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Persist)
-            .map_err(PersistError::Io)?;
-        write_to_disk(file, &data)?;
-        *debounce = Some(data);
-        Ok(PersistSuccess::WritePerformed)
-    } else {
-        Ok(PersistSuccess::Debounce)
+    fn collect(
+        calculator_query: &mut Query<&Transform, With<Calculator>>,
+    ) -> Result<Option<Self>, PersistError> {
+        let mut calculator_positions = vec![];
+        for transform in calculator_query.iter() {
+            calculator_positions.push(transform.translation.xy());
+        }
+        Ok(Some(Self {
+            calculator_positions,
+        }))
     }
-}
 
-fn restore(
-    memory_config: Res<MemoryConfig>,
-    mut calculator_spawn_events: EventWriter<SpawnCalculatorRequestEvent>,
-    environment_query: Query<Entity, Added<AgentEnvironment>>,
-) -> Result<RestoreSuccess, RestoreError> {
-    if environment_query.is_empty() {
-        return Ok(RestoreSuccess::NoAction);
+    fn ready(
+        (_, added_query, ..): &mut (
+            EventWriter<SpawnCalculatorRequestEvent>,
+            Query<Entity, Added<AgentEnvironment>>,
+            Query<Entity, With<AgentEnvironment>>,
+            Query<Entity, With<Calculator>>,
+            Commands,
+        ),
+    ) -> bool {
+        !added_query.is_empty()
     }
 
-    let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Restore)
-        .map_err(RestoreError::Io)?;
-    let data: DiskData = read_from_disk(file)?;
-
-    for environment in environment_query.iter() {
-        let environment_id = environment;
-        info!("Restoring calculator into {environment_id:?}");
+    fn restore(
+        self,
+        (calculator_spawn_events, _, environment_query, calculator_query, commands): &mut (
+            EventWriter<SpawnCalculatorRequestEvent>,
+            Query<Entity, Added<AgentEnvironment>>,
+            Query<Entity, With<AgentEnvironment>>,
+            Query<Entity, With<Calculator>>,
+            Commands,
+        ),
+    ) -> Result<RestoreSuccess, RestoreError> {
+        // the saved positions replace any calculators that are already open
+        for calculator_id in calculator_query.iter() {
+            commands.entity(calculator_id).despawn_recursive();
+        }
+        for environment in environment_query.iter() {
+            let environment_id = environment;
+            info!("Restoring calculator into {environment_id:?}");
 
-        for position in &data.calculator_positions {
-            calculator_spawn_events.send(SpawnCalculatorRequestEvent {
-                environment_id,
-                state: CalculatorState::default(),
-                theme: CalculatorThemeKind::WindowsDark,
-                position: *position,
-            });
+            for position in &self.calculator_positions {
+                calculator_spawn_events.send(SpawnCalculatorRequestEvent {
+                    environment_id,
+                    state: CalculatorState::default(),
+                    theme: CalculatorThemeKind::WindowsDark,
+                    position: *position,
+                });
+            }
         }
-    }
 
-    Ok(RestoreSuccess::Performed)
+        Ok(RestoreSuccess::Performed)
+    }
 }
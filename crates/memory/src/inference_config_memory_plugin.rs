@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use cursor_hero_inference_types::prelude::InferenceConfig;
+use cursor_hero_memory_types::prelude::*;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(transparent)]
+pub(crate) struct InferenceConfigMemory(InferenceConfig);
+
+impl Persistable for InferenceConfigMemory {
+    const FILE_NAME: &'static str = "inference_config.json";
+    const RESTORE_STRATEGY: RestoreStrategy = RestoreStrategy::Startup;
+    const VERSION: u32 = 2;
+
+    type PersistParam = Res<'static, InferenceConfig>;
+    type RestoreParam = ResMut<'static, InferenceConfig>;
+
+    fn collect(inference_config: &mut Res<InferenceConfig>) -> Result<Option<Self>, PersistError> {
+        Ok(Some(Self(inference_config.clone())))
+    }
+
+    fn migrations() -> MigrationRegistry {
+        MigrationRegistry::default()
+            // v1 had a single base_url shared by every backend, it pointed at Ollama
+            .with(1, |mut data| {
+                let Some(fields) = data.as_object_mut() else {
+                    return Err("Inference config is not an object".to_string());
+                };
+                let mut base_urls = InferenceConfig::default().base_urls;
+                if let Some(serde_json::Value::String(base_url)) = fields.remove("base_url") {
+                    base_urls.insert("ollama".to_string(), base_url);
+                }
+                fields.insert(
+                    "base_urls".to_string(),
+                    serde_json::to_value(base_urls).map_err(|e| e.to_string())?,
+                );
+                Ok(data)
+            })
+    }
+
+    fn restore(
+        self,
+        inference_config: &mut ResMut<InferenceConfig>,
+    ) -> Result<RestoreSuccess, RestoreError> {
+        let Self(data) = self;
+        info!(
+            "Restoring inference config, model {} at {:?}",
+            data.model, data.base_urls
+        );
+        **inference_config = data;
+
+        Ok(RestoreSuccess::Performed)
+    }
+}
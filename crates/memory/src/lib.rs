@@ -1,10 +1,14 @@
 mod agent_observation_memory_plugin;
 pub mod app_memory_plugin;
+mod inference_config_memory_plugin;
 mod main_camera_memory_plugin;
 mod main_character_memory_plugin;
 mod memory_plugin;
+mod observation_history_plugin;
+mod observation_transcript_plugin;
 pub mod primary_window_memory_plugin;
 mod ui_data_memory_plugin;
+mod vector_memory_plugin;
 mod voice_to_text_memory_plugin;
 
 pub mod prelude {
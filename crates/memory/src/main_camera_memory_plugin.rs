@@ -4,90 +4,51 @@ use cursor_hero_memory_types::prelude::*;
 use serde::Deserialize;
 use serde::Serialize;
 
-pub struct MainCameraMemoryPlugin;
-
-impl Plugin for MainCameraMemoryPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(MainCameraMemoryConfig::default());
-        app.add_systems(Update, persist.pipe(handle_persist_errors));
-        app.add_systems(Update, restore.pipe(handle_restore_errors));
-    }
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) struct MainCameraMemory {
+    scale: Vec3,
 }
-const PERSIST_FILE_NAME: &str = "main_camera.json";
 
-// not moved to lib to ensure log contains this module name
-fn handle_persist_errors(In(result): In<Result<PersistSuccess, PersistError>>) {
-    if let Err(e) = result {
-        error!("Persist error occurred: {:?}", e);
-    } else if let Ok(PersistSuccess::WritePerformed) = result {
-        debug!("Persisted succeeded");
-    }
-}
+impl Persistable for MainCameraMemory {
+    const FILE_NAME: &'static str = "main_camera.json";
+    const RESTORE_STRATEGY: RestoreStrategy = RestoreStrategy::WhenReady;
 
-fn handle_restore_errors(In(result): In<Result<RestoreSuccess, RestoreError>>) {
-    if let Err(e) = result {
-        error!("Restore error occurred: {:?}", e);
-    } else if let Ok(RestoreSuccess::Performed) = result {
-        info!("Restore succeeded");
-    }
-}
+    type PersistParam = Query<'static, 'static, &'static Transform, With<MainCamera>>;
+    type RestoreParam = (
+        Query<'static, 'static, Entity, Added<MainCamera>>,
+        Query<'static, 'static, &'static mut Transform, With<MainCamera>>,
+    );
 
-#[derive(Debug, Resource, Reflect)]
-#[reflect(Resource)]
-struct MainCameraMemoryConfig {
-    pub debounce_timer: Timer,
-}
-impl Default for MainCameraMemoryConfig {
-    fn default() -> Self {
-        Self {
-            debounce_timer: Timer::from_seconds(10.0, TimerMode::Repeating),
-        }
+    fn collect(
+        camera_query: &mut Query<&Transform, With<MainCamera>>,
+    ) -> Result<Option<Self>, PersistError> {
+        let camera_transform = camera_query.get_single().map_err(|_| PersistError::Query)?;
+        Ok(Some(Self {
+            scale: camera_transform.scale,
+        }))
     }
-}
-
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
-struct DiskData {
-    scale: Vec3,
-}
 
-fn persist(
-    mut config: ResMut<MainCameraMemoryConfig>,
-    memory_config: Res<MemoryConfig>,
-    mut debounce: Local<Option<DiskData>>,
-    time: Res<Time>,
-    camera_query: Query<&Transform, With<MainCamera>>,
-) -> Result<PersistSuccess, PersistError> {
-    if !config.debounce_timer.tick(time.delta()).just_finished() {
-        return Ok(PersistSuccess::Cooldown);
+    fn ready(
+        (added_query, _): &mut (
+            Query<Entity, Added<MainCamera>>,
+            Query<&mut Transform, With<MainCamera>>,
+        ),
+    ) -> bool {
+        !added_query.is_empty()
     }
 
-    let camera_transform = camera_query.get_single().map_err(|_| PersistError::Query)?;
-
-    let data = DiskData {
-        scale: camera_transform.scale,
-    };
-    if debounce.is_none() || debounce.as_ref().unwrap() != &data {
-        let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Persist)
-            .map_err(PersistError::Io)?;
-        write_to_disk(file, &data)?;
-        *debounce = Some(data);
-        Ok(PersistSuccess::WritePerformed)
-    } else {
-        Ok(PersistSuccess::Debounce)
+    fn restore(
+        self,
+        (_, camera_query): &mut (
+            Query<Entity, Added<MainCamera>>,
+            Query<&mut Transform, With<MainCamera>>,
+        ),
+    ) -> Result<RestoreSuccess, RestoreError> {
+        let mut camera_transform = camera_query
+            .get_single_mut()
+            .map_err(|_| RestoreError::Query)?;
+        info!("Restoring main camera scale to {:?}", self.scale);
+        camera_transform.scale = self.scale;
+        Ok(RestoreSuccess::Performed)
     }
 }
-
-fn restore(
-    memory_config: Res<MemoryConfig>,
-    mut camera_query: Query<&mut Transform, Added<MainCamera>>,
-) -> Result<RestoreSuccess, RestoreError> {
-    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
-        return Ok(RestoreSuccess::NoAction);
-    };
-    let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Restore)
-        .map_err(RestoreError::Io)?;
-    let data: DiskData = read_from_disk(file)?;
-    info!("Restoring main camera scale to {:?}", data.scale);
-    camera_transform.scale = data.scale;
-    Ok(RestoreSuccess::Performed)
-}
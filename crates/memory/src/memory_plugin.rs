@@ -1,14 +1,20 @@
 use bevy::prelude::*;
 use cursor_hero_memory_types::prelude::MemoryConfig;
 use cursor_hero_memory_types::prelude::MemoryPluginBuildConfig;
+use cursor_hero_memory_types::prelude::PersistPlugin;
+use cursor_hero_memory_types::prelude::VectorMemory;
 
-use crate::agent_observation_memory_plugin::AgentObservationMemoryPlugin;
-use crate::app_memory_plugin::AppMemoryPlugin;
-use crate::main_camera_memory_plugin::MainCameraMemoryPlugin;
-use crate::main_character_memory_plugin::MainCharacterMemoryPlugin;
-use crate::primary_window_memory_plugin::PrimaryWindowMemoryPlugin;
-use crate::ui_data_memory_plugin::UIDataMemoryPlugin;
-use crate::voice_to_text_memory_plugin::VoiceToTextMemoryPlugin;
+use crate::agent_observation_memory_plugin::AgentObservationMemory;
+use crate::app_memory_plugin::AppMemory;
+use crate::inference_config_memory_plugin::InferenceConfigMemory;
+use crate::main_camera_memory_plugin::MainCameraMemory;
+use crate::main_character_memory_plugin::MainCharacterMemory;
+use crate::observation_history_plugin::ObservationHistoryPlugin;
+use crate::observation_transcript_plugin::ObservationTranscriptPlugin;
+use crate::primary_window_memory_plugin::PrimaryWindowMemory;
+use crate::ui_data_memory_plugin::UIDataMemory;
+use crate::vector_memory_plugin::VectorMemoryPlugin;
+use crate::voice_to_text_memory_plugin::VoiceToTextMemory;
 pub struct MemoryPlugin {
     pub config: MemoryConfig,
     pub build_config: MemoryPluginBuildConfig,
@@ -18,25 +24,38 @@ impl Plugin for MemoryPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.config.clone());
         if self.build_config.main_character_memory_enabled {
-            app.add_plugins(MainCharacterMemoryPlugin);
+            app.add_plugins(PersistPlugin::<MainCharacterMemory>::default());
         }
         if self.build_config.primary_window_memory_enabled {
-            app.add_plugins(PrimaryWindowMemoryPlugin);
+            app.add_plugins(PersistPlugin::<PrimaryWindowMemory>::default());
         }
         if self.build_config.main_camera_memory_enabled {
-            app.add_plugins(MainCameraMemoryPlugin);
+            app.add_plugins(PersistPlugin::<MainCameraMemory>::default());
         }
         if self.build_config.voice_to_text_memory_enabled {
-            app.add_plugins(VoiceToTextMemoryPlugin);
+            app.add_plugins(PersistPlugin::<VoiceToTextMemory>::default());
         }
         if self.build_config.agent_observation_memory_enabled {
-            app.add_plugins(AgentObservationMemoryPlugin);
+            app.add_plugins(PersistPlugin::<AgentObservationMemory>::default());
+        }
+        if self.build_config.observation_history_enabled {
+            app.add_plugins(ObservationHistoryPlugin);
+        }
+        if self.build_config.observation_transcripts_enabled {
+            app.add_plugins(ObservationTranscriptPlugin);
         }
         if self.build_config.ui_data_memory_enabled {
-            app.add_plugins(UIDataMemoryPlugin);
+            app.add_plugins(PersistPlugin::<UIDataMemory>::default());
         }
         if self.build_config.app_memory_enabled {
-            app.add_plugins(AppMemoryPlugin);
+            app.add_plugins(PersistPlugin::<AppMemory>::default());
+        }
+        if self.build_config.inference_config_memory_enabled {
+            app.add_plugins(PersistPlugin::<InferenceConfigMemory>::default());
+        }
+        if self.build_config.vector_memory_enabled {
+            app.add_plugins(VectorMemoryPlugin);
+            app.add_plugins(PersistPlugin::<VectorMemory>::default());
         }
     }
 }
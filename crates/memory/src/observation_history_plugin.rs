@@ -0,0 +1,188 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::utils::Instant;
+use chrono::DateTime;
+use cursor_hero_character_types::prelude::*;
+use cursor_hero_memory_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+use std::path::PathBuf;
+
+use crate::agent_observation_memory_plugin::AgentObservationMemory;
+
+/// Appends every observation to an [`ObservationHistory`] in the save dir and restores agent buffers from it.
+pub struct ObservationHistoryPlugin;
+
+impl Plugin for ObservationHistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, open_history);
+        app.add_systems(Update, append_observations);
+        app.add_systems(
+            PostUpdate,
+            release_history.before(MemorySystemSet::Commands),
+        );
+        app.add_systems(Update, restore_observations);
+        app.add_systems(Last, flush_history);
+    }
+}
+const HISTORY_FILE_NAME: &str = "observations.sqlite";
+/// Appends are batched into one transaction this often
+const FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How many of the newest observations an agent starts with, older ones are left to queries
+const RESTORE_LIMIT: usize = 200;
+
+fn open_history(mut commands: Commands, memory_config: Res<MemoryConfig>) {
+    let history =
+        ObservationHistory::new(PathBuf::from(&memory_config.save_dir).join(HISTORY_FILE_NAME));
+    match history.len() {
+        Ok(0) => import_agent_memory(memory_config.as_ref(), &history),
+        Ok(len) => info!("Observation history has {} entries", len),
+        Err(e) => error!("Failed to open observation history: {:?}", e),
+    }
+    commands.insert_resource(history);
+}
+
+/// Carries over the observations saved before the history existed
+fn import_agent_memory(memory_config: &MemoryConfig, history: &ObservationHistory) {
+    let Ok(file) = get_persist_file(
+        memory_config,
+        AgentObservationMemory::FILE_NAME,
+        Usage::Restore,
+    ) else {
+        return;
+    };
+    let data: AgentObservationMemory = match read_from_disk(
+        file,
+        AgentObservationMemory::VERSION,
+        &AgentObservationMemory::migrations(),
+    ) {
+        Ok(data) => data,
+        Err(e) => {
+            warn!(
+                "Failed to import agent memory into the observation history: {:?}",
+                e
+            );
+            return;
+        }
+    };
+    for (observer, buffer) in data.observations_by_observer_name.iter() {
+        match history.append(observer, &buffer.observations) {
+            Ok(count) => info!("Imported {} observations of {}", count, observer),
+            Err(e) => error!("Failed to import observations of {}: {:?}", observer, e),
+        }
+    }
+}
+
+fn append_observations(
+    mut buffer_events: EventReader<ObservationBufferEvent>,
+    buffer_query: Query<(&Name, &ObservationBuffer)>,
+    history: Option<Res<ObservationHistory>>,
+    mut appended: Local<HashMap<Entity, DateTime<chrono::Local>>>,
+) {
+    let Some(history) = history else {
+        buffer_events.clear();
+        return;
+    };
+    for event in buffer_events.read() {
+        let ObservationBufferEvent::Updated { buffer_id } = event;
+        let Ok((name, buffer)) = buffer_query.get(*buffer_id) else {
+            continue;
+        };
+        // buffers are trimmed and summarized, so positions can't be relied on
+        // entries sharing the latest datetime are offered again and skipped by the history
+        let latest = appended.get(buffer_id).copied();
+        let entries = buffer
+            .observations
+            .iter()
+            .filter(|entry| latest.map_or(true, |latest| entry.datetime >= latest))
+            // summaries only stand in for entries that are already stored
+            .filter(|entry| entry.origin.kind() != ObservationKind::Summary);
+        history.queue(name.as_str(), entries);
+        if let Some(last) = buffer.observations.iter().map(|entry| entry.datetime).max() {
+            appended.insert(*buffer_id, last);
+        }
+    }
+}
+
+fn flush_history(
+    history: Option<Res<ObservationHistory>>,
+    mut exit_events: EventReader<AppExit>,
+    mut last_flush: Local<Option<Instant>>,
+) {
+    let exiting = exit_events.read().count() > 0;
+    let Some(history) = history else {
+        return;
+    };
+    let due = last_flush.map_or(true, |last| last.elapsed() >= FLUSH_INTERVAL);
+    if !due && !exiting {
+        return;
+    }
+    *last_flush = Some(Instant::now());
+    if let Err(e) = history.flush() {
+        error!("Failed to append observations: {:?}", e);
+    }
+}
+
+/// Closes the history before a save slot or backup swaps the file out, it opens again on the next use
+fn release_history(
+    mut command_events: EventReader<MemoryCommandEvent>,
+    history: Option<Res<ObservationHistory>>,
+) {
+    if command_events.read().count() == 0 {
+        return;
+    }
+    let Some(history) = history else {
+        return;
+    };
+    if let Err(e) = history.close() {
+        error!("Failed to close the observation history: {:?}", e);
+    }
+}
+
+fn restore_observations(
+    added_query: Query<Entity, Added<AgentCharacter>>,
+    mut agent_query: Query<(Entity, &Name, &mut ObservationBuffer), With<AgentCharacter>>,
+    mut reload_events: EventReader<MemoryReloadEvent>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    history: Option<Res<ObservationHistory>>,
+) {
+    let mut reload = false;
+    for event in reload_events.read() {
+        reload |= event.includes(HISTORY_FILE_NAME);
+    }
+    let Some(history) = history else {
+        return;
+    };
+    for agent in agent_query.iter_mut() {
+        let (agent_id, agent_name, mut agent_buffer) = agent;
+        if !reload && !added_query.contains(agent_id) {
+            continue;
+        }
+        let rows = match history.query(&ObservationHistoryQuery {
+            observer: Some(agent_name.to_string()),
+            limit: RESTORE_LIMIT,
+            ..default()
+        }) {
+            Ok(rows) => rows,
+            Err(e) => {
+                error!("Failed to restore observations of {}: {:?}", agent_name, e);
+                continue;
+            }
+        };
+        if rows.is_empty() && !reload {
+            continue;
+        }
+        info!(
+            "Restoring {} observations of {} from the history",
+            rows.len(),
+            agent_name
+        );
+        agent_buffer.observations = rows.into_iter().map(|row| row.entry).collect();
+
+        let event = SomethingObservableHappenedEvent::MemoryRestored {
+            observation_buffer_id: agent_id,
+        };
+        debug!("Sending event {:?}", event);
+        observation_events.send(event);
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use cursor_hero_character_types::prelude::*;
+use cursor_hero_memory_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+
+/// Exports and imports the observation buffers of agents as transcripts, see [`ObservationTranscriptEvent`]
+pub struct ObservationTranscriptPlugin;
+
+impl Plugin for ObservationTranscriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, handle_transcript_events);
+    }
+}
+
+fn handle_transcript_events(
+    mut transcript_events: EventReader<ObservationTranscriptEvent>,
+    mut agent_query: Query<(Entity, &Name, &mut ObservationBuffer), With<AgentCharacter>>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    memory_config: Res<MemoryConfig>,
+    history: Option<Res<ObservationHistory>>,
+) {
+    for event in transcript_events.read() {
+        match event {
+            ObservationTranscriptEvent::Export { agent_name, format } => {
+                match export_transcript(&memory_config, &agent_query, agent_name, *format) {
+                    Ok(path) => info!("Exported observations of {} to {:?}", agent_name, path),
+                    Err(e) => error!("Failed to export observations of {}: {:?}", agent_name, e),
+                }
+            }
+            ObservationTranscriptEvent::Import { agent_name, path } => {
+                match import_transcript(
+                    &memory_config,
+                    &mut agent_query,
+                    history.as_deref(),
+                    agent_name,
+                    path,
+                ) {
+                    Ok((agent_id, count)) => {
+                        info!(
+                            "Imported {} observations into {} from {:?}",
+                            count, agent_name, path
+                        );
+                        if let Some(agent_id) = agent_id {
+                            observation_events.send(
+                                SomethingObservableHappenedEvent::MemoryRestored {
+                                    observation_buffer_id: agent_id,
+                                },
+                            );
+                        }
+                    }
+                    Err(e) => error!(
+                        "Failed to import observations into {} from {:?}: {:?}",
+                        agent_name, path, e
+                    ),
+                }
+            }
+        }
+    }
+}
+
+fn export_transcript(
+    memory_config: &MemoryConfig,
+    agent_query: &Query<(Entity, &Name, &mut ObservationBuffer), With<AgentCharacter>>,
+    agent_name: &str,
+    format: TranscriptFormat,
+) -> Result<PathBuf, TranscriptError> {
+    let Some((agent_id, _, buffer)) = agent_query
+        .iter()
+        .find(|(_, name, _)| name.as_str() == agent_name)
+    else {
+        return Err(TranscriptError::UnknownAgent(agent_name.to_string()));
+    };
+    let transcript = encode_transcript(agent_name, agent_id, &buffer.observations, format)
+        .map_err(TranscriptError::Json)?;
+    let dir = memory_config.transcripts_dir();
+    std::fs::create_dir_all(&dir).map_err(TranscriptError::Io)?;
+    let path = dir.join(transcript_file_name(agent_name, format));
+    std::fs::write(&path, transcript).map_err(TranscriptError::Io)?;
+    Ok(path)
+}
+
+/// Paths that don't exist are looked up in [`MemoryConfig::transcripts_dir`].
+///
+/// Returns the agent that was seeded if it is around, and how many observations were imported.
+fn import_transcript(
+    memory_config: &MemoryConfig,
+    agent_query: &mut Query<(Entity, &Name, &mut ObservationBuffer), With<AgentCharacter>>,
+    history: Option<&ObservationHistory>,
+    agent_name: &str,
+    path: &Path,
+) -> Result<(Option<Entity>, usize), TranscriptError> {
+    let path = match path.exists() {
+        true => path.to_path_buf(),
+        false => memory_config.transcripts_dir().join(path),
+    };
+    let Some(format) = TranscriptFormat::from_path(&path) else {
+        return Err(TranscriptError::UnknownFormat(path));
+    };
+    let mut agent = agent_query
+        .iter_mut()
+        .find(|(_, name, _)| name.as_str() == agent_name);
+    if agent.is_none() && history.is_none() {
+        // nowhere to keep the observations until the agent shows up
+        return Err(TranscriptError::UnknownAgent(agent_name.to_string()));
+    }
+    let chatted_here = agent.as_ref().is_some_and(|(_, _, buffer)| {
+        buffer
+            .observations
+            .iter()
+            .any(|entry| entry.origin.kind() == ObservationKind::Chat)
+    });
+    let chatted_before = match history {
+        Some(history) => !history
+            .query(&ObservationHistoryQuery {
+                observer: Some(agent_name.to_string()),
+                kinds: vec![ObservationKind::Chat],
+                limit: 1,
+                ..default()
+            })
+            .map_err(TranscriptError::History)?
+            .is_empty(),
+        None => false,
+    };
+    if chatted_here || chatted_before {
+        return Err(TranscriptError::AgentNotFresh(agent_name.to_string()));
+    }
+
+    let transcript = std::fs::read_to_string(&path).map_err(TranscriptError::Io)?;
+    let agent_id = agent.as_ref().map(|(agent_id, _, _)| *agent_id);
+    let observations =
+        decode_transcript(&transcript, agent_id.unwrap_or(Entity::PLACEHOLDER), format)
+            .map_err(TranscriptError::Json)?;
+    // the history is what agents are restored from on the next run
+    if let Some(history) = history {
+        history
+            .append(agent_name, &observations)
+            .map_err(TranscriptError::History)?;
+    }
+    if let Some((_, _, buffer)) = agent.as_mut() {
+        // keeps what was observed since spawning, such as entering the environment
+        buffer.observations.extend(observations.iter().cloned());
+        buffer.observations.sort_by_key(|entry| entry.datetime);
+    }
+    Ok((agent_id, observations.len()))
+}
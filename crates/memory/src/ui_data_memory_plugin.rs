@@ -8,52 +8,8 @@ use cursor_hero_ui_inspector_types::prelude::UIData;
 use serde::Deserialize;
 use serde::Serialize;
 
-pub struct UIDataMemoryPlugin;
-
-impl Plugin for UIDataMemoryPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(UIDataMemoryConfig::default());
-        app.add_systems(Update, persist.pipe(handle_persist_errors));
-        app.add_systems(
-            Startup,
-            (apply_deferred, restore.pipe(handle_restore_errors)).chain(),
-        );
-    }
-}
-const PERSIST_FILE_NAME: &str = "ui_data.json";
-
-// not moved to lib to ensure log contains this module name
-fn handle_persist_errors(In(result): In<Result<PersistSuccess, PersistError>>) {
-    if let Err(e) = result {
-        error!("Persist error occurred: {:?}", e);
-    } else if let Ok(PersistSuccess::WritePerformed) = result {
-        debug!("Persisted succeeded");
-    }
-}
-
-fn handle_restore_errors(In(result): In<Result<RestoreSuccess, RestoreError>>) {
-    if let Err(e) = result {
-        error!("Restore error occurred: {:?}", e);
-    } else if let Ok(RestoreSuccess::Performed) = result {
-        info!("Restore succeeded");
-    }
-}
-
-#[derive(Debug, Resource, Reflect)]
-#[reflect(Resource)]
-struct UIDataMemoryConfig {
-    pub debounce_timer: Timer,
-}
-impl Default for UIDataMemoryConfig {
-    fn default() -> Self {
-        Self {
-            debounce_timer: Timer::from_seconds(10.0, TimerMode::Repeating),
-        }
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-struct DiskData {
+pub(crate) struct UIDataMemory {
     pub opened: InspectorWindows,
     pub scratch_pad: String,
     pub scratch_pad_mode: ScratchPadMode,
@@ -65,8 +21,8 @@ struct DiskData {
     pub expanded: Vec<DrillId>,
     pub paused: bool,
 }
-impl From<DiskData> for UIData {
-    fn from(value: DiskData) -> Self {
+impl From<UIDataMemory> for UIData {
+    fn from(value: UIDataMemory) -> Self {
         UIData {
             windows: value.opened,
             scratch_pad: value.scratch_pad,
@@ -82,7 +38,7 @@ impl From<DiskData> for UIData {
         }
     }
 }
-impl From<&UIData> for DiskData {
+impl From<&UIData> for UIDataMemory {
     fn from(value: &UIData) -> Self {
         Self {
             opened: value.windows.clone(),
@@ -99,44 +55,26 @@ impl From<&UIData> for DiskData {
     }
 }
 
-fn persist(
-    mut config: ResMut<UIDataMemoryConfig>,
-    memory_config: Res<MemoryConfig>,
-    mut debounce: Local<Option<DiskData>>,
-    time: Res<Time>,
-    ui_data: Res<UIData>,
-) -> Result<PersistSuccess, PersistError> {
-    if !config.debounce_timer.tick(time.delta()).just_finished() {
-        return Ok(PersistSuccess::Cooldown);
-    }
+impl Persistable for UIDataMemory {
+    const FILE_NAME: &'static str = "ui_data.json";
+    const RESTORE_STRATEGY: RestoreStrategy = RestoreStrategy::Startup;
 
-    let data = ui_data.as_ref().into();
-    if debounce.is_none() || debounce.as_ref().unwrap() != &data {
-        let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Persist)
-            .map_err(PersistError::Io)?;
-        write_to_disk(file, &data)?;
-        *debounce = Some(data);
-        Ok(PersistSuccess::WritePerformed)
-    } else {
-        Ok(PersistSuccess::Debounce)
-    }
-}
+    type PersistParam = Res<'static, UIData>;
+    type RestoreParam = ResMut<'static, UIData>;
 
-fn restore(
-    memory_config: Res<MemoryConfig>,
-    mut ui_data: ResMut<UIData>,
-) -> Result<RestoreSuccess, RestoreError> {
-    let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Restore)
-        .map_err(RestoreError::Io)?;
-    let data: DiskData = read_from_disk(file)?;
+    fn collect(ui_data: &mut Res<UIData>) -> Result<Option<Self>, PersistError> {
+        Ok(Some(ui_data.as_ref().into()))
+    }
 
-    info!("Restoring UI Data");
+    fn restore(self, ui_data: &mut ResMut<UIData>) -> Result<RestoreSuccess, RestoreError> {
+        info!("Restoring UI Data");
 
-    // other debug systems are hidden by default
-    // force this to be invisible at start until a global debug state is implemented
-    // data.opened.global_toggle = false;
+        // other debug systems are hidden by default
+        // force this to be invisible at start until a global debug state is implemented
+        // data.opened.global_toggle = false;
 
-    *ui_data = data.into();
+        **ui_data = self.into();
 
-    Ok(RestoreSuccess::Performed)
+        Ok(RestoreSuccess::Performed)
+    }
 }
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_memory_types::prelude::*;
+use cursor_hero_observation_types::observation_types::SomethingObservableHappenedEvent;
+use cursor_hero_ui_automation_types::prelude::*;
+
+/// Embeds observations as they happen and keeps them in a [`VectorMemory`] for similarity search.
+///
+/// Saving and restoring the memory is left to a [`PersistPlugin`] for it.
+pub struct VectorMemoryPlugin;
+
+impl Plugin for VectorMemoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VectorMemory>();
+        app.init_resource::<PendingEmbeddings>();
+        app.add_systems(Startup, spawn_session);
+        app.add_systems(Update, request_embeddings);
+        app.add_systems(Update, store_embeddings);
+    }
+}
+
+/// Texts sent for embedding that have not come back yet
+#[derive(Resource, Default)]
+struct PendingEmbeddings {
+    session_id: Option<Entity>,
+    pending: HashMap<String, VectorMemoryKind>,
+}
+
+#[derive(Component, Reflect)]
+struct VectorMemorySession;
+
+fn spawn_session(mut commands: Commands, mut pending: ResMut<PendingEmbeddings>) {
+    let session_id = commands
+        .spawn((VectorMemorySession, Name::new("Vector Memory Session")))
+        .id();
+    pending.session_id = Some(session_id);
+}
+
+fn describe(event: &SomethingObservableHappenedEvent) -> Vec<(VectorMemoryKind, String)> {
+    match event {
+        SomethingObservableHappenedEvent::Chat { .. } => {
+            vec![(VectorMemoryKind::Chat, event.to_string())]
+        }
+        SomethingObservableHappenedEvent::UISnapshot { snapshot, .. } => snapshot
+            .app_windows
+            .iter()
+            .filter(|window| !matches!(window, AppSnapshot::Unknown))
+            .map(|window| {
+                (
+                    VectorMemoryKind::UiElement,
+                    format!("{}: {}", window.variant_name(), window),
+                )
+            })
+            .collect(),
+        // restarts are not worth recalling
+        SomethingObservableHappenedEvent::MemoryRestored { .. } => Vec::new(),
+        // the summarized observations were already remembered
+        SomethingObservableHappenedEvent::Summary { .. } => Vec::new(),
+        SomethingObservableHappenedEvent::EnvironmentEntered { .. }
+        | SomethingObservableHappenedEvent::EnvironmentLeft { .. }
+        | SomethingObservableHappenedEvent::PressurePlateActivated { .. }
+        | SomethingObservableHappenedEvent::CalculatorResult { .. } => {
+            vec![(VectorMemoryKind::Observation, event.to_string())]
+        }
+        // too frequent to be worth recalling one by one
+        SomethingObservableHappenedEvent::ToolActivated { .. }
+        | SomethingObservableHappenedEvent::Click { .. }
+        | SomethingObservableHappenedEvent::WindowPositioned { .. } => Vec::new(),
+    }
+}
+
+fn request_embeddings(
+    mut observation_events: EventReader<SomethingObservableHappenedEvent>,
+    mut embedding_events: EventWriter<EmbeddingInferenceEvent>,
+    mut pending: ResMut<PendingEmbeddings>,
+    memory: Res<VectorMemory>,
+) {
+    let Some(session_id) = pending.session_id else {
+        return;
+    };
+    for event in observation_events.read() {
+        for (kind, text) in describe(event) {
+            let text = text.trim().to_string();
+            if text.is_empty()
+                || pending.pending.contains_key(&text)
+                || memory.contains(kind, &text)
+            {
+                continue;
+            }
+            pending.pending.insert(text.clone(), kind);
+            embedding_events.send(EmbeddingInferenceEvent::Request { session_id, text });
+        }
+    }
+}
+
+fn store_embeddings(
+    mut embedding_events: EventReader<EmbeddingInferenceEvent>,
+    mut pending: ResMut<PendingEmbeddings>,
+    mut memory: ResMut<VectorMemory>,
+) {
+    for event in embedding_events.read() {
+        match event {
+            EmbeddingInferenceEvent::Response {
+                session_id,
+                text,
+                embedding,
+            } if Some(*session_id) == pending.session_id => {
+                let Some(kind) = pending.pending.remove(text) else {
+                    continue;
+                };
+                memory.insert(VectorMemoryEntry {
+                    kind,
+                    text: text.clone(),
+                    datetime: chrono::Local::now(),
+                    embedding: embedding.clone(),
+                });
+            }
+            EmbeddingInferenceEvent::Failed {
+                session_id, text, ..
+            } if Some(*session_id) == pending.session_id => {
+                pending.pending.remove(text);
+            }
+            _ => {}
+        }
+    }
+}
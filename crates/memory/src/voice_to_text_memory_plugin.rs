@@ -1,7 +1,6 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
-use bevy::utils::Instant;
 
 use cursor_hero_memory_types::prelude::*;
 use cursor_hero_secret_types::secrets_types::SecretString;
@@ -10,143 +9,81 @@ use cursor_hero_voice_to_text_types::voice_to_text_types::VoiceToTextStatusEvent
 use serde::Deserialize;
 use serde::Serialize;
 
-pub struct VoiceToTextMemoryPlugin;
-
-impl Plugin for VoiceToTextMemoryPlugin {
-    fn build(&self, app: &mut App) {
-        app.insert_resource(VoiceToTextMemoryConfig::default());
-        app.add_systems(Update, persist.pipe(handle_persist_errors));
-        app.add_systems(Update, restore.pipe(handle_restore_errors));
-    }
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct VoiceToTextMemory {
+    api_key: Option<SecretString>,
 }
-const PERSIST_FILE_NAME: &str = "voice_to_text.json";
 
-// not moved to lib to ensure log contains this module name
-fn handle_persist_errors(In(result): In<Result<PersistSuccess, PersistError>>) {
-    if let Err(e) = result {
-        error!("Persist error occurred: {:?}", e);
-    } else if let Ok(PersistSuccess::WritePerformed) = result {
-        debug!("Persisted succeeded");
-    }
-}
+impl Persistable for VoiceToTextMemory {
+    const FILE_NAME: &'static str = "voice_to_text.json";
+    const RESTORE_STRATEGY: RestoreStrategy =
+        RestoreStrategy::WhenReadyWithRetry(Duration::from_secs(60));
 
-fn handle_restore_errors(In(result): In<Result<RestoreSuccess, RestoreError>>) {
-    if let Err(e) = result {
-        error!("Restore error occurred: {:?}", e);
-    } else if let Ok(RestoreSuccess::Performed) = result {
-        info!("Restore succeeded");
-    }
-}
+    type PersistParam = Res<'static, VoiceToTextStatus>;
+    type RestoreParam = (
+        ResMut<'static, VoiceToTextStatus>,
+        EventWriter<'static, VoiceToTextStatusEvent>,
+    );
 
-#[derive(Debug, Resource, Reflect)]
-#[reflect(Resource)]
-struct VoiceToTextMemoryConfig {
-    pub persist_cooldown: Timer,
-    pub restore_retry_cooldown: Duration,
-}
-impl Default for VoiceToTextMemoryConfig {
-    fn default() -> Self {
-        Self {
-            persist_cooldown: Timer::from_seconds(10.0, TimerMode::Repeating),
-            restore_retry_cooldown: Duration::from_secs(60),
-        }
+    fn collect(voice_status: &mut Res<VoiceToTextStatus>) -> Result<Option<Self>, PersistError> {
+        let api_key = match &**voice_status {
+            VoiceToTextStatus::Alive { api_key, .. }
+            | VoiceToTextStatus::Starting { api_key, .. } => Some(api_key.clone()),
+            VoiceToTextStatus::Dead => None,
+            _ => {
+                return Ok(None);
+            }
+        };
+        Ok(Some(Self { api_key }))
     }
-}
-
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-struct DiskData {
-    api_key: Option<SecretString>,
-}
 
-fn persist(
-    mut config: ResMut<VoiceToTextMemoryConfig>,
-    memory_config: Res<MemoryConfig>,
-    mut debounce: Local<Option<DiskData>>,
-    time: Res<Time>,
-    voice_status: Res<VoiceToTextStatus>,
-) -> Result<PersistSuccess, PersistError> {
-    if !config.persist_cooldown.tick(time.delta()).just_finished() {
-        return Ok(PersistSuccess::Cooldown);
+    fn ready(
+        (current_status, _): &mut (
+            ResMut<VoiceToTextStatus>,
+            EventWriter<VoiceToTextStatusEvent>,
+        ),
+    ) -> bool {
+        !matches!(
+            **current_status,
+            VoiceToTextStatus::Alive { .. }
+                | VoiceToTextStatus::Starting { .. }
+                | VoiceToTextStatus::UnknownWithCachedApiKey { .. }
+                | VoiceToTextStatus::Dead
+        )
     }
-    let api_key = match &*voice_status {
-        VoiceToTextStatus::Alive { api_key, .. } | VoiceToTextStatus::Starting { api_key, .. } => {
-            Some(api_key.clone())
-        }
-        VoiceToTextStatus::Dead => None,
-        _ => {
-            return Ok(PersistSuccess::NoAction);
-        }
-    };
-    let data = DiskData { api_key };
-    if debounce.is_none() || debounce.as_ref().unwrap() != &data {
-        let file = get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Persist)
-            .map_err(PersistError::Io)?;
-        write_to_disk(file, &data)?;
-        *debounce = Some(data);
-        Ok(PersistSuccess::WritePerformed)
-    } else {
-        Ok(PersistSuccess::Debounce)
-    }
-}
 
-fn restore(
-    config: Res<VoiceToTextMemoryConfig>,
-    memory_config: Res<MemoryConfig>,
-    mut current_status: ResMut<VoiceToTextStatus>,
-    mut status_events: EventWriter<VoiceToTextStatusEvent>,
-    mut attempted_at: Local<Option<Instant>>,
-) -> Result<RestoreSuccess, RestoreError> {
-    if matches!(
-        *current_status,
-        VoiceToTextStatus::Alive { .. }
-            | VoiceToTextStatus::Starting { .. }
-            | VoiceToTextStatus::UnknownWithCachedApiKey { .. }
-            | VoiceToTextStatus::Dead
-    ) {
-        return Ok(RestoreSuccess::NoAction);
-    }
-    let file = match get_persist_file(memory_config.as_ref(), PERSIST_FILE_NAME, Usage::Restore) {
-        Ok(file) => Ok(file),
-        Err(e) => {
-            if let Some(attempt) = *attempted_at {
-                if attempt.elapsed() > config.restore_retry_cooldown {
-                    *attempted_at = Some(Instant::now());
-                    return Err(RestoreError::Io(e));
-                } else {
-                    // Silently ignore the error and retry later
-                    return Ok(RestoreSuccess::NoAction);
-                }
-            } else {
-                *attempted_at = Some(Instant::now());
-                return Err(RestoreError::Io(e));
-            }
-        }
-    }?;
-    let data: DiskData = read_from_disk(file)?;
-    let Some(api_key) = data.api_key else {
-        return Ok(RestoreSuccess::NoAction);
-    };
+    fn restore(
+        self,
+        (current_status, status_events): &mut (
+            ResMut<VoiceToTextStatus>,
+            EventWriter<VoiceToTextStatusEvent>,
+        ),
+    ) -> Result<RestoreSuccess, RestoreError> {
+        let Some(api_key) = self.api_key else {
+            return Ok(RestoreSuccess::NoAction);
+        };
 
-    info!("Restoring api key");
+        info!("Restoring api key");
 
-    let new_status = match *current_status {
-        VoiceToTextStatus::Unknown
-        | VoiceToTextStatus::AliveButWeDontKnowTheApiKey
-        | VoiceToTextStatus::UnknownWithCachedApiKey { .. } => {
-            VoiceToTextStatus::UnknownWithCachedApiKey { api_key }
-        }
-        ref current => current.clone(),
-    };
-    if new_status != *current_status {
-        let event = VoiceToTextStatusEvent::Changed {
-            old_status: current_status.clone(),
-            new_status: new_status.clone(),
+        let new_status = match **current_status {
+            VoiceToTextStatus::Unknown
+            | VoiceToTextStatus::AliveButWeDontKnowTheApiKey
+            | VoiceToTextStatus::UnknownWithCachedApiKey { .. } => {
+                VoiceToTextStatus::UnknownWithCachedApiKey { api_key }
+            }
+            ref current => current.clone(),
         };
-        debug!("Sending event: {:?}", event);
-        status_events.send(event);
-        *current_status = new_status;
-        Ok(RestoreSuccess::Performed)
-    } else {
-        Ok(RestoreSuccess::NoAction)
+        if new_status != **current_status {
+            let event = VoiceToTextStatusEvent::Changed {
+                old_status: current_status.clone(),
+                new_status: new_status.clone(),
+            };
+            debug!("Sending event: {:?}", event);
+            status_events.send(event);
+            **current_status = new_status;
+            Ok(RestoreSuccess::Performed)
+        } else {
+            Ok(RestoreSuccess::NoAction)
+        }
     }
 }
@@ -1,7 +1,15 @@
 mod memory_types;
 mod memory_types_plugin;
+mod migration_types;
+mod persist_plugin;
+mod persistable_types;
+mod vector_memory_types;
 
 pub mod prelude {
     pub use crate::memory_types::*;
     pub use crate::memory_types_plugin::*;
+    pub use crate::migration_types::*;
+    pub use crate::persist_plugin::*;
+    pub use crate::persistable_types::*;
+    pub use crate::vector_memory_types::*;
 }
@@ -1,13 +1,82 @@
+use crate::prelude::*;
 use bevy::prelude::*;
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
 
 #[derive(Resource, Reflect, Clone)]
 pub struct MemoryConfig {
     pub save_dir: String,
+    /// How many backups of each save file are kept in [`MemoryConfig::backup_dir`]
+    pub backup_count: usize,
+    /// Minimum time between two backups of the same file
+    pub backup_interval: Duration,
+}
+
+impl MemoryConfig {
+    pub fn new(save_dir: impl Into<String>) -> Self {
+        Self {
+            save_dir: save_dir.into(),
+            backup_count: 5,
+            backup_interval: Duration::from_secs(60 * 15),
+        }
+    }
+
+    pub fn backup_dir(&self) -> PathBuf {
+        PathBuf::from(&self.save_dir).join("backups")
+    }
+
+    pub fn slots_dir(&self) -> PathBuf {
+        PathBuf::from(&self.save_dir).join("slots")
+    }
+
+    /// Where agent observation transcripts are exported to and imported from by file name
+    pub fn transcripts_dir(&self) -> PathBuf {
+        PathBuf::from(&self.save_dir).join("transcripts")
+    }
+}
+
+/// Named snapshots of the save dir, see [`MemoryCommandEvent::SaveSlot`]
+#[derive(Resource, Reflect, Debug, Default, Clone)]
+#[reflect(Resource)]
+pub struct MemorySlots {
+    /// The slot that was loaded or saved last
+    pub active: Option<String>,
+    pub available: Vec<String>,
+}
+
+#[derive(Event, Debug, Reflect, Clone)]
+pub enum MemoryCommandEvent {
+    /// Replaces a save file with one of the backups from [`list_backups`] and restores it
+    RestoreBackup {
+        file_name: String,
+        backup_name: String,
+    },
+    /// Snapshots every save file under a name, replacing an existing slot of the same name
+    SaveSlot { name: String },
+    /// Replaces every save file with the files of a slot and restores them
+    LoadSlot { name: String },
+}
+
+/// Save files changed on disk, every [`crate::prelude::PersistPlugin`] for them restores again
+#[derive(Event, Debug, Reflect, Clone)]
+pub struct MemoryReloadEvent {
+    /// `None` reloads every file
+    pub file_name: Option<String>,
+}
+
+impl MemoryReloadEvent {
+    pub fn includes(&self, file_name: &str) -> bool {
+        match &self.file_name {
+            Some(name) => name == file_name,
+            None => true,
+        }
+    }
 }
 
 #[derive(Reflect, Default)]
@@ -17,8 +86,14 @@ pub struct MemoryPluginBuildConfig {
     pub primary_window_memory_enabled: bool,
     pub main_camera_memory_enabled: bool,
     pub voice_to_text_memory_enabled: bool,
+    /// Saves every agent's whole observation buffer to a single JSON file, superseded by `observation_history_enabled`
     pub agent_observation_memory_enabled: bool,
+    pub observation_history_enabled: bool,
+    /// Handles `ObservationTranscriptEvent`s
+    pub observation_transcripts_enabled: bool,
     pub ui_data_memory_enabled: bool,
+    pub inference_config_memory_enabled: bool,
+    pub vector_memory_enabled: bool,
 }
 
 impl MemoryPluginBuildConfig {
@@ -29,8 +104,12 @@ impl MemoryPluginBuildConfig {
             primary_window_memory_enabled: true,
             main_camera_memory_enabled: true,
             voice_to_text_memory_enabled: true,
-            agent_observation_memory_enabled: true,
+            agent_observation_memory_enabled: false,
+            observation_history_enabled: true,
+            observation_transcripts_enabled: true,
             ui_data_memory_enabled: true,
+            inference_config_memory_enabled: true,
+            vector_memory_enabled: true,
         }
     }
 }
@@ -57,9 +136,17 @@ pub enum PersistSuccess {
 pub enum RestoreError {
     Io(std::io::Error),
     Json(serde_json::Error),
+    Migration(MigrationError),
     Query,
 }
 
+impl RestoreError {
+    /// Whether the file exists but can't be read by this build, see [`backup_unreadable_file`]
+    pub fn is_unreadable(&self) -> bool {
+        matches!(self, RestoreError::Json(_) | RestoreError::Migration(_))
+    }
+}
+
 #[derive(Debug)]
 pub enum RestoreSuccess {
     Performed,
@@ -94,23 +181,320 @@ pub fn get_persist_file(
     Ok(file)
 }
 
-pub fn write_to_disk<T>(mut file: File, data: &T) -> Result<PersistSuccess, PersistError>
+/// Writes the save file to a temp file first and renames it into place once it is synced,
+/// so that a crash mid-write leaves the previous save intact
+pub fn write_to_disk<T>(
+    config: &MemoryConfig,
+    file_name: &str,
+    version: u32,
+    data: &T,
+) -> Result<PersistSuccess, PersistError>
 where
     T: serde::Serialize,
 {
-    let serialized = serde_json::to_string_pretty(data).map_err(PersistError::Json)?;
-    file.write_all(serialized.as_bytes())
-        .map_err(PersistError::Io)?;
+    let serialized = encode_save_file(version, data).map_err(PersistError::Json)?;
+    write_atomically(config, file_name, serialized.as_bytes()).map_err(PersistError::Io)?;
     Ok(PersistSuccess::WritePerformed)
 }
 
-pub fn read_from_disk<T>(mut file: File) -> Result<T, RestoreError>
+fn write_atomically(
+    config: &MemoryConfig,
+    file_name: &str,
+    contents: &[u8],
+) -> Result<(), std::io::Error> {
+    let save_dir = PathBuf::from(&config.save_dir);
+    std::fs::create_dir_all(&save_dir)?;
+    let temp_path = save_dir.join(format!("{}.tmp", file_name));
+    let mut temp_file = File::create(&temp_path)?;
+    temp_file.write_all(contents)?;
+    temp_file.sync_all()?;
+    drop(temp_file);
+
+    let target_path = save_dir.join(file_name);
+    backup_file(config, file_name, &target_path, false)?;
+    std::fs::rename(temp_path, target_path)
+}
+
+/// Copies the current save file into [`MemoryConfig::backup_dir`] unless the last backup is recent,
+/// then drops the oldest backups beyond [`MemoryConfig::backup_count`]
+fn backup_file(
+    config: &MemoryConfig,
+    file_name: &str,
+    path: &Path,
+    force: bool,
+) -> Result<(), std::io::Error> {
+    if config.backup_count == 0 || !path.exists() {
+        return Ok(());
+    }
+    let mut backups = list_backups(config, file_name)?;
+    let last_backup_age = backups
+        .last()
+        .and_then(|last| last.metadata().ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+    let due = match last_backup_age {
+        Some(age) => age >= config.backup_interval,
+        None => true,
+    };
+    if !force && !due {
+        return Ok(());
+    }
+
+    let backup_dir = config.backup_dir();
+    std::fs::create_dir_all(&backup_dir)?;
+    let backup_path = backup_dir.join(format!(
+        "{}.{}",
+        file_name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+    ));
+    std::fs::copy(path, &backup_path)?;
+    backups.push(backup_path);
+    backups.dedup();
+
+    let excess = backups.len().saturating_sub(config.backup_count);
+    for old in backups.drain(..excess) {
+        std::fs::remove_file(old)?;
+    }
+    Ok(())
+}
+
+/// Backups of a save file, oldest first
+pub fn list_backups(
+    config: &MemoryConfig,
+    file_name: &str,
+) -> Result<Vec<PathBuf>, std::io::Error> {
+    let backup_dir = config.backup_dir();
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let prefix = format!("{}.", file_name);
+    let mut backups = Vec::new();
+    for entry in std::fs::read_dir(backup_dir)? {
+        let path = entry?.path();
+        let is_backup = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(&prefix));
+        if is_backup {
+            backups.push(path);
+        }
+    }
+    // timestamps sort chronologically
+    backups.sort();
+    Ok(backups)
+}
+
+/// Replaces a save file with one of its backups, the replaced file is backed up first
+pub fn restore_backup(
+    config: &MemoryConfig,
+    file_name: &str,
+    backup_name: &str,
+) -> Result<(), std::io::Error> {
+    let contents = std::fs::read(config.backup_dir().join(backup_name))?;
+    let target_path = PathBuf::from(&config.save_dir).join(file_name);
+    backup_file(config, file_name, &target_path, true)?;
+    write_atomically(config, file_name, &contents)
+}
+
+pub fn read_from_disk<T>(
+    mut file: File,
+    version: u32,
+    migrations: &MigrationRegistry,
+) -> Result<T, RestoreError>
 where
     T: serde::de::DeserializeOwned,
 {
     let mut contents = String::new();
     file.read_to_string(&mut contents)
         .map_err(RestoreError::Io)?;
-    let data = serde_json::from_str(&contents).map_err(RestoreError::Json)?;
-    Ok(data)
+    decode_save_file(&contents, version, migrations)
+}
+
+fn slot_dir(config: &MemoryConfig, name: &str) -> Result<PathBuf, std::io::Error> {
+    let valid =
+        !name.trim().is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', ':']);
+    if !valid {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid slot name {:?}", name),
+        ));
+    }
+    Ok(config.slots_dir().join(name))
+}
+
+/// The save files directly inside `dir`, skipping leftovers of interrupted writes and sqlite's side files
+fn list_save_files(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_leftover = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extension == "tmp"
+                    || extension == "unreadable"
+                    || extension.ends_with("-wal")
+                    || extension.ends_with("-shm")
+                    || extension.ends_with("-journal")
+            });
+        if entry.file_type()?.is_file() && !is_leftover {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Names of the save files in [`MemoryConfig::save_dir`]
+pub fn list_save_file_names(config: &MemoryConfig) -> Result<Vec<String>, std::io::Error> {
+    Ok(list_save_files(Path::new(&config.save_dir))?
+        .iter()
+        .filter_map(|file| file.file_name())
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .collect())
+}
+
+pub fn list_slots(config: &MemoryConfig) -> Result<Vec<String>, std::io::Error> {
+    let slots_dir = config.slots_dir();
+    if !slots_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut slots = Vec::new();
+    for entry in std::fs::read_dir(slots_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        // partially written slots are hidden
+        if entry.file_type()?.is_dir() && !name.starts_with('.') {
+            slots.push(name);
+        }
+    }
+    slots.sort();
+    Ok(slots)
+}
+
+pub fn save_slot(config: &MemoryConfig, name: &str) -> Result<(), std::io::Error> {
+    let slot_dir = slot_dir(config, name)?;
+    let temp_dir = config.slots_dir().join(format!(".{}.tmp", name));
+    if temp_dir.exists() {
+        std::fs::remove_dir_all(&temp_dir)?;
+    }
+    std::fs::create_dir_all(&temp_dir)?;
+    for file in list_save_files(Path::new(&config.save_dir))? {
+        if let Some(file_name) = file.file_name() {
+            std::fs::copy(&file, temp_dir.join(file_name))?;
+        }
+    }
+    if slot_dir.exists() {
+        std::fs::remove_dir_all(&slot_dir)?;
+    }
+    std::fs::rename(temp_dir, slot_dir)
+}
+
+/// Replaces the save files with the files of a slot, files missing from the slot are removed.
+/// Every replaced file is backed up first
+pub fn load_slot(config: &MemoryConfig, name: &str) -> Result<(), std::io::Error> {
+    let slot_dir = slot_dir(config, name)?;
+    if !slot_dir.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("slot {:?} does not exist", name),
+        ));
+    }
+    let slot_files = list_save_files(&slot_dir)?;
+    for current in list_save_files(Path::new(&config.save_dir))? {
+        let Some(file_name) = current.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        backup_file(config, file_name, &current, true)?;
+        if !slot_dir.join(file_name).exists() {
+            std::fs::remove_file(&current)?;
+        }
+    }
+    for file in slot_files {
+        let Some(file_name) = file.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        write_atomically(config, file_name, &std::fs::read(&file)?)?;
+    }
+    Ok(())
+}
+
+/// Moves a save file that failed to restore out of the way, so that the next persist doesn't overwrite the user's data
+pub fn backup_unreadable_file(
+    config: &MemoryConfig,
+    file_name: &str,
+) -> Result<PathBuf, std::io::Error> {
+    let save_dir = PathBuf::from(config.save_dir.clone());
+    let backup_path = save_dir.join(format!(
+        "{}.{}.unreadable",
+        file_name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::rename(save_dir.join(file_name), &backup_path)?;
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_keep_backups() {
+        let save_dir =
+            std::env::temp_dir().join(format!("cursor_hero_memory_test_{}", std::process::id()));
+        let mut config = MemoryConfig::new(save_dir.to_string_lossy());
+        config.backup_count = 2;
+        config.backup_interval = Duration::ZERO;
+        let read = |config: &MemoryConfig| -> u32 {
+            let file = get_persist_file(config, "test.json", Usage::Restore).unwrap();
+            read_from_disk(file, 1, &MigrationRegistry::default()).unwrap()
+        };
+
+        for value in 1..=4u32 {
+            write_to_disk(&config, "test.json", 1, &value).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(read(&config), 4);
+        assert!(!save_dir.join("test.json.tmp").exists());
+
+        let backups = list_backups(&config, "test.json").unwrap();
+        assert_eq!(backups.len(), 2);
+        let oldest = backups[0].file_name().unwrap().to_str().unwrap();
+        restore_backup(&config, "test.json", oldest).unwrap();
+        assert_eq!(read(&config), 2);
+
+        std::fs::remove_dir_all(save_dir).unwrap();
+    }
+
+    #[test]
+    fn slots_round_trip() {
+        let save_dir = std::env::temp_dir().join(format!(
+            "cursor_hero_memory_slots_test_{}",
+            std::process::id()
+        ));
+        let config = MemoryConfig::new(save_dir.to_string_lossy());
+        let read = |file_name: &str| -> Option<u32> {
+            let file = get_persist_file(&config, file_name, Usage::Restore).ok()?;
+            read_from_disk(file, 1, &MigrationRegistry::default()).ok()
+        };
+
+        write_to_disk(&config, "a.json", 1, &1u32).unwrap();
+        save_slot(&config, "work").unwrap();
+        write_to_disk(&config, "a.json", 1, &2u32).unwrap();
+        write_to_disk(&config, "b.json", 1, &3u32).unwrap();
+        assert_eq!(list_slots(&config).unwrap(), vec!["work".to_string()]);
+        assert!(save_slot(&config, "../escape").is_err());
+
+        load_slot(&config, "work").unwrap();
+        assert_eq!(read("a.json"), Some(1));
+        // files that didn't exist when the slot was saved are gone
+        assert_eq!(read("b.json"), None);
+        assert!(!list_backups(&config, "b.json").unwrap().is_empty());
+
+        std::fs::remove_dir_all(save_dir).unwrap();
+    }
 }
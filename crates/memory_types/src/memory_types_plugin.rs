@@ -1,8 +1,88 @@
+use crate::prelude::*;
 use bevy::prelude::*;
-// use crate::prelude::*;
 
 pub struct MemoryTypesPlugin;
 
+/// Runs in [`PostUpdate`], save files held open elsewhere should be released before [`MemorySystemSet::Commands`] swaps them out
+#[derive(SystemSet, Clone, Hash, Debug, PartialEq, Eq)]
+pub enum MemorySystemSet {
+    Commands,
+}
+
 impl Plugin for MemoryTypesPlugin {
-    fn build(&self, _app: &mut App) {}
+    fn build(&self, app: &mut App) {
+        app.add_event::<MemoryCommandEvent>();
+        app.add_event::<MemoryReloadEvent>();
+        app.register_type::<MemorySlots>();
+        app.init_resource::<MemorySlots>();
+        app.configure_sets(PostUpdate, MemorySystemSet::Commands);
+        app.add_systems(Startup, refresh_slots);
+        app.add_systems(
+            PostUpdate,
+            handle_memory_commands.in_set(MemorySystemSet::Commands),
+        );
+    }
+}
+
+fn handle_memory_commands(
+    mut command_events: EventReader<MemoryCommandEvent>,
+    mut reload_events: EventWriter<MemoryReloadEvent>,
+    mut slots: ResMut<MemorySlots>,
+    memory_config: Option<Res<MemoryConfig>>,
+) {
+    for event in command_events.read() {
+        let Some(memory_config) = memory_config.as_ref() else {
+            warn!("Ignoring {:?}, memory is not configured", event);
+            continue;
+        };
+        match event {
+            MemoryCommandEvent::RestoreBackup {
+                file_name,
+                backup_name,
+            } => {
+                if let Err(e) = restore_backup(memory_config, file_name, backup_name) {
+                    error!(
+                        "Failed to restore {} from {}: {:?}",
+                        file_name, backup_name, e
+                    );
+                    continue;
+                }
+                info!("Restored {} from {}", file_name, backup_name);
+                reload_events.send(MemoryReloadEvent {
+                    file_name: Some(file_name.clone()),
+                });
+            }
+            MemoryCommandEvent::SaveSlot { name } => {
+                if let Err(e) = save_slot(memory_config, name) {
+                    error!("Failed to save slot {}: {:?}", name, e);
+                    continue;
+                }
+                info!("Saved slot {}", name);
+                slots.active = Some(name.clone());
+            }
+            MemoryCommandEvent::LoadSlot { name } => {
+                if let Err(e) = load_slot(memory_config, name) {
+                    error!("Failed to load slot {}: {:?}", name, e);
+                    continue;
+                }
+                info!("Loaded slot {}", name);
+                slots.active = Some(name.clone());
+                reload_events.send(MemoryReloadEvent { file_name: None });
+            }
+        }
+        match list_slots(memory_config) {
+            Ok(available) => slots.available = available,
+            Err(e) => error!("Failed to list slots: {:?}", e),
+        }
+    }
+}
+
+fn refresh_slots(mut slots: ResMut<MemorySlots>, memory_config: Option<Res<MemoryConfig>>) {
+    let Some(memory_config) = memory_config else {
+        return;
+    };
+    match list_slots(&memory_config) {
+        Ok(available) => slots.available = available,
+        Err(e) => error!("Failed to list slots: {:?}", e),
+    }
 }
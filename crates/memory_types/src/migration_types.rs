@@ -0,0 +1,161 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+
+/// Files written before save files were versioned hold the bare data and are treated as this version
+pub const UNVERSIONED_SAVE_FILE_VERSION: u32 = 1;
+
+/// Upgrades the data of a save file from one version to the next
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// The file was written by a newer build than this one
+    FromFuture {
+        version: u32,
+        current: u32,
+    },
+    /// No migration is registered to upgrade from this version
+    Missing {
+        from_version: u32,
+    },
+    Failed {
+        from_version: u32,
+        reason: String,
+    },
+}
+
+/// The migrations of a save file, keyed by the version each one upgrades from
+#[derive(Default)]
+pub struct MigrationRegistry {
+    steps: BTreeMap<u32, Migration>,
+}
+
+impl MigrationRegistry {
+    /// Registers the step from `from_version` to `from_version + 1`
+    pub fn with(mut self, from_version: u32, migration: Migration) -> Self {
+        self.steps.insert(from_version, migration);
+        self
+    }
+
+    pub fn migrate(
+        &self,
+        mut version: u32,
+        target: u32,
+        mut data: Value,
+    ) -> Result<Value, MigrationError> {
+        if version > target {
+            return Err(MigrationError::FromFuture {
+                version,
+                current: target,
+            });
+        }
+        while version < target {
+            let migration = self.steps.get(&version).ok_or(MigrationError::Missing {
+                from_version: version,
+            })?;
+            data = migration(data).map_err(|reason| MigrationError::Failed {
+                from_version: version,
+                reason,
+            })?;
+            version += 1;
+        }
+        Ok(data)
+    }
+}
+
+#[derive(Serialize)]
+struct SaveFileRef<'a, T> {
+    version: u32,
+    data: &'a T,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SaveFile {
+    version: u32,
+    data: Value,
+}
+
+pub fn encode_save_file<T: Serialize>(version: u32, data: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(&SaveFileRef { version, data })
+}
+
+/// Reads a `{ "version": n, "data": ... }` envelope, upgrading the data to `version` before deserializing it
+pub fn decode_save_file<T: DeserializeOwned>(
+    contents: &str,
+    version: u32,
+    migrations: &MigrationRegistry,
+) -> Result<T, RestoreError> {
+    let value: Value = serde_json::from_str(contents).map_err(RestoreError::Json)?;
+    let save_file = match serde_json::from_value::<SaveFile>(value.clone()) {
+        Ok(save_file) => save_file,
+        Err(_) => SaveFile {
+            version: UNVERSIONED_SAVE_FILE_VERSION,
+            data: value,
+        },
+    };
+    let data = migrations
+        .migrate(save_file.version, version, save_file.data)
+        .map_err(RestoreError::Migration)?;
+    serde_json::from_value(data).map_err(RestoreError::Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    fn migrations() -> MigrationRegistry {
+        MigrationRegistry::default()
+            // v1 stored a tuple
+            .with(1, |data| {
+                let (x, y): (f32, f32) = serde_json::from_value(data).map_err(|e| e.to_string())?;
+                Ok(serde_json::json!({ "position": [x, y] }))
+            })
+            // v2 stored an array under a key
+            .with(2, |mut data| {
+                let position = data["position"].take();
+                let [x, y]: [f32; 2] =
+                    serde_json::from_value(position).map_err(|e| e.to_string())?;
+                Ok(serde_json::json!({ "x": x, "y": y }))
+            })
+    }
+
+    #[test]
+    fn save_file_migrations() {
+        let current = Position { x: 1.0, y: 2.0 };
+        let encoded = encode_save_file(3, &current).unwrap();
+        let decoded: Position = decode_save_file(&encoded, 3, &migrations()).unwrap();
+        assert_eq!(decoded, current);
+
+        // unversioned files are upgraded from the first version step by step
+        let decoded: Position = decode_save_file("[1.0, 2.0]", 3, &migrations()).unwrap();
+        assert_eq!(decoded, current);
+
+        let v2 = r#"{ "version": 2, "data": { "position": [1.0, 2.0] } }"#;
+        let decoded: Position = decode_save_file(v2, 3, &migrations()).unwrap();
+        assert_eq!(decoded, current);
+
+        let future = encode_save_file(4, &current).unwrap();
+        assert!(matches!(
+            decode_save_file::<Position>(&future, 3, &migrations()),
+            Err(RestoreError::Migration(MigrationError::FromFuture { .. }))
+        ));
+        assert!(matches!(
+            decode_save_file::<Position>("[1.0, 2.0]", 3, &MigrationRegistry::default()),
+            Err(RestoreError::Migration(MigrationError::Missing {
+                from_version: 1
+            }))
+        ));
+    }
+}
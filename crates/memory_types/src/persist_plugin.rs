@@ -0,0 +1,163 @@
+use bevy::ecs::system::StaticSystemParam;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use std::marker::PhantomData;
+
+use crate::prelude::*;
+
+/// Saves and restores a [`Persistable`] according to its file name, cooldown and [`RestoreStrategy`].
+pub struct PersistPlugin<T: Persistable> {
+    _marker: PhantomData<T>,
+}
+
+impl<T: Persistable> Default for PersistPlugin<T> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Persistable> Plugin for PersistPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PersistCooldown::<T>::default());
+        let restore_systems = (
+            apply_deferred,
+            restore::<T>.pipe(handle_restore_errors::<T>),
+        )
+            .chain();
+        match T::RESTORE_STRATEGY {
+            RestoreStrategy::Startup => app.add_systems(Startup, restore_systems),
+            RestoreStrategy::WhenReady | RestoreStrategy::WhenReadyWithRetry(_) => {
+                app.add_systems(Update, restore_systems)
+            }
+        };
+        // reloading first keeps the old state from being written over files that just changed on disk
+        app.add_systems(
+            Update,
+            (
+                reload::<T>.pipe(handle_restore_errors::<T>),
+                persist::<T>.pipe(handle_persist_errors::<T>),
+            )
+                .chain(),
+        );
+    }
+}
+
+#[derive(Resource)]
+pub struct PersistCooldown<T: Persistable> {
+    pub timer: Timer,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Persistable> Default for PersistCooldown<T> {
+    fn default() -> Self {
+        Self {
+            timer: Timer::new(T::PERSIST_COOLDOWN, TimerMode::Repeating),
+            _marker: PhantomData,
+        }
+    }
+}
+
+fn handle_persist_errors<T: Persistable>(In(result): In<Result<PersistSuccess, PersistError>>) {
+    if let Err(e) = result {
+        error!("Persist error occurred for {}: {:?}", T::FILE_NAME, e);
+    } else if let Ok(PersistSuccess::WritePerformed) = result {
+        debug!("Persisted {} succeeded", T::FILE_NAME);
+    }
+}
+
+fn handle_restore_errors<T: Persistable>(In(result): In<Result<RestoreSuccess, RestoreError>>) {
+    if let Err(e) = result {
+        error!("Restore error occurred for {}: {:?}", T::FILE_NAME, e);
+    } else if let Ok(RestoreSuccess::Performed) = result {
+        info!("Restore {} succeeded", T::FILE_NAME);
+    }
+}
+
+fn persist<T: Persistable>(
+    mut cooldown: ResMut<PersistCooldown<T>>,
+    memory_config: Res<MemoryConfig>,
+    mut debounce: Local<Option<T>>,
+    time: Res<Time>,
+    mut param: StaticSystemParam<T::PersistParam>,
+) -> Result<PersistSuccess, PersistError> {
+    if !cooldown.timer.tick(time.delta()).just_finished() {
+        return Ok(PersistSuccess::Cooldown);
+    }
+
+    let Some(data) = T::collect(&mut *param)? else {
+        return Ok(PersistSuccess::NoAction);
+    };
+    if debounce.as_ref() == Some(&data) {
+        return Ok(PersistSuccess::Debounce);
+    }
+    write_to_disk(memory_config.as_ref(), T::FILE_NAME, T::VERSION, &data)?;
+    *debounce = Some(data);
+    Ok(PersistSuccess::WritePerformed)
+}
+
+fn restore<T: Persistable>(
+    memory_config: Res<MemoryConfig>,
+    mut failed_at: Local<Option<Instant>>,
+    mut param: StaticSystemParam<T::RestoreParam>,
+) -> Result<RestoreSuccess, RestoreError> {
+    if !T::ready(&mut *param) {
+        return Ok(RestoreSuccess::NoAction);
+    }
+    if let (RestoreStrategy::WhenReadyWithRetry(retry_cooldown), Some(failed_at)) =
+        (T::RESTORE_STRATEGY, *failed_at)
+    {
+        if failed_at.elapsed() < retry_cooldown {
+            // Silently ignore and retry later
+            return Ok(RestoreSuccess::NoAction);
+        }
+    }
+
+    let data = match load::<T>(memory_config.as_ref()) {
+        Ok(data) => data,
+        Err(e) => {
+            *failed_at = Some(Instant::now());
+            return Err(e);
+        }
+    };
+    data.restore(&mut *param)
+}
+
+/// Restores again when the file changed on disk, regardless of [`Persistable::ready`]
+fn reload<T: Persistable>(
+    memory_config: Res<MemoryConfig>,
+    mut reload_events: EventReader<MemoryReloadEvent>,
+    mut param: StaticSystemParam<T::RestoreParam>,
+) -> Result<RestoreSuccess, RestoreError> {
+    let mut requested = false;
+    for event in reload_events.read() {
+        requested |= event.includes(T::FILE_NAME);
+    }
+    if !requested {
+        return Ok(RestoreSuccess::NoAction);
+    }
+    info!("Reloading {}", T::FILE_NAME);
+    load::<T>(memory_config.as_ref())?.restore(&mut *param)
+}
+
+fn load<T: Persistable>(memory_config: &MemoryConfig) -> Result<T, RestoreError> {
+    let data = get_persist_file(memory_config, T::FILE_NAME, Usage::Restore)
+        .map_err(RestoreError::Io)
+        .and_then(|file| read_from_disk::<T>(file, T::VERSION, &T::migrations()));
+    if let Err(e) = &data {
+        if e.is_unreadable() {
+            match backup_unreadable_file(memory_config, T::FILE_NAME) {
+                Ok(path) => warn!("Moved unreadable {} to {}", T::FILE_NAME, path.display()),
+                Err(backup_error) => {
+                    error!(
+                        "Failed to back up unreadable {}: {:?}",
+                        T::FILE_NAME,
+                        backup_error
+                    )
+                }
+            }
+        }
+    }
+    data
+}
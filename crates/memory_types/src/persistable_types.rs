@@ -0,0 +1,57 @@
+use bevy::ecs::system::SystemParam;
+use bevy::ecs::system::SystemParamItem;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::prelude::*;
+
+/// When a [`Persistable`] is read back from disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreStrategy {
+    /// Once during startup, for state that exists from the start such as resources
+    Startup,
+    /// Attempted every update while [`Persistable::ready`], e.g. when an entity to restore into was just spawned
+    WhenReady,
+    /// Like [`RestoreStrategy::WhenReady`], but a failed attempt is only retried after the given cooldown
+    WhenReadyWithRetry(Duration),
+}
+
+/// State that is saved to a file in [`MemoryConfig::save_dir`] and restored from it on the next run.
+///
+/// Implementors are the on-disk representation, gathered from the world by [`Persistable::collect`]
+/// and applied back by [`Persistable::restore`]. Add a [`crate::prelude::PersistPlugin`] to opt in.
+pub trait Persistable:
+    Serialize + DeserializeOwned + PartialEq + Clone + Send + Sync + 'static
+{
+    const FILE_NAME: &'static str;
+    const RESTORE_STRATEGY: RestoreStrategy;
+    /// How often [`Persistable::collect`] is called, unchanged data is not written again
+    const PERSIST_COOLDOWN: Duration = Duration::from_secs(10);
+    /// Bump this and register a step in [`Persistable::migrations`] whenever the saved shape changes
+    const VERSION: u32 = UNVERSIONED_SAVE_FILE_VERSION;
+
+    type PersistParam: SystemParam + 'static;
+    type RestoreParam: SystemParam + 'static;
+
+    /// Gathers the data to save, `None` when there is nothing worth saving right now
+    fn collect(
+        param: &mut SystemParamItem<Self::PersistParam>,
+    ) -> Result<Option<Self>, PersistError>;
+
+    /// Upgrades files written by older versions, see [`MigrationRegistry::with`]
+    fn migrations() -> MigrationRegistry {
+        MigrationRegistry::default()
+    }
+
+    /// Whether there is something new to restore into, the file is only read when this returns true.
+    /// A [`MemoryReloadEvent`] restores without asking, so [`Persistable::restore`] should not depend on the same change detection
+    fn ready(_param: &mut SystemParamItem<Self::RestoreParam>) -> bool {
+        true
+    }
+
+    fn restore(
+        self,
+        param: &mut SystemParamItem<Self::RestoreParam>,
+    ) -> Result<RestoreSuccess, RestoreError>;
+}
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum VectorMemoryKind {
+    Observation,
+    Chat,
+    UiElement,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorMemoryEntry {
+    pub kind: VectorMemoryKind,
+    pub text: String,
+    pub datetime: chrono::DateTime<chrono::Local>,
+    pub embedding: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorMemoryMatch<'a> {
+    pub entry: &'a VectorMemoryEntry,
+    /// Cosine similarity, 1 is identical
+    pub score: f32,
+}
+
+/// Embedded observations, chat lines and UI descriptions that can be searched by similarity.
+///
+/// Small enough that a linear scan beats maintaining an index.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VectorMemory {
+    pub entries: Vec<VectorMemoryEntry>,
+    /// When the oldest change that hasn't been persisted was made
+    #[serde(skip)]
+    pub unsaved_since: Option<Instant>,
+    #[serde(skip)]
+    pub changed_at: Option<Instant>,
+}
+
+/// Embeddings make the file large, so it is written once entries stop coming in for this long
+pub const VECTOR_MEMORY_QUIET_PERIOD: Duration = Duration::from_secs(10);
+
+/// Unless changes have been waiting this long, a steady trickle of entries still gets saved
+pub const VECTOR_MEMORY_MAX_UNSAVED: Duration = Duration::from_secs(120);
+
+impl VectorMemory {
+    /// Whether the changes should be written now, see [`VECTOR_MEMORY_QUIET_PERIOD`]
+    pub fn should_persist(&self) -> bool {
+        match (self.unsaved_since, self.changed_at) {
+            (Some(unsaved_since), Some(changed_at)) => {
+                changed_at.elapsed() >= VECTOR_MEMORY_QUIET_PERIOD
+                    || unsaved_since.elapsed() >= VECTOR_MEMORY_MAX_UNSAVED
+            }
+            _ => false,
+        }
+    }
+
+    pub fn mark_persisted(&mut self) {
+        self.unsaved_since = None;
+        self.changed_at = None;
+    }
+
+    /// The embedding stored for `text` under any kind
+    pub fn embedding_of(&self, text: &str) -> Option<&[f32]> {
+        self.entries
+            .iter()
+            .find(|entry| entry.text == text)
+            .map(|entry| entry.embedding.as_slice())
+    }
+
+    pub fn contains(&self, kind: VectorMemoryKind, text: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.kind == kind && entry.text == text)
+    }
+
+    /// Ignores texts that are already stored with the same kind
+    pub fn insert(&mut self, entry: VectorMemoryEntry) {
+        if self.contains(entry.kind, &entry.text) {
+            return;
+        }
+        self.entries.push(entry);
+        let now = Instant::now();
+        self.unsaved_since.get_or_insert(now);
+        self.changed_at = Some(now);
+    }
+
+    /// The `limit` entries most similar to `embedding`, best first.
+    ///
+    /// Entries embedded with a different number of dimensions, e.g. by another model, are skipped.
+    pub fn search(&self, embedding: &[f32], limit: usize) -> Vec<VectorMemoryMatch> {
+        let mut matches = self
+            .entries
+            .iter()
+            .filter(|entry| entry.embedding.len() == embedding.len())
+            .map(|entry| VectorMemoryMatch {
+                entry,
+                score: cosine_similarity(&entry.embedding, embedding),
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+impl Persistable for VectorMemory {
+    const FILE_NAME: &'static str = "vector_memory.json";
+    const RESTORE_STRATEGY: RestoreStrategy = RestoreStrategy::Startup;
+    // checks often, VectorMemory::should_persist decides when to write
+    const PERSIST_COOLDOWN: Duration = Duration::from_secs(1);
+
+    type PersistParam = ResMut<'static, VectorMemory>;
+    type RestoreParam = ResMut<'static, VectorMemory>;
+
+    fn collect(memory: &mut ResMut<VectorMemory>) -> Result<Option<Self>, PersistError> {
+        if !memory.should_persist() {
+            return Ok(None);
+        }
+        memory.mark_persisted();
+        Ok(Some(memory.clone()))
+    }
+
+    fn restore(self, memory: &mut ResMut<VectorMemory>) -> Result<RestoreSuccess, RestoreError> {
+        info!(
+            "Restoring vector memory, found {} entries",
+            self.entries.len()
+        );
+        **memory = self;
+        Ok(RestoreSuccess::Performed)
+    }
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>();
+    let norm_a = a.iter().map(|a| a * a).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|b| b * b).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(text: &str, embedding: Vec<f32>) -> VectorMemoryEntry {
+        VectorMemoryEntry {
+            kind: VectorMemoryKind::Chat,
+            text: text.to_string(),
+            datetime: chrono::Local::now(),
+            embedding,
+        }
+    }
+
+    #[test]
+    fn search_ranks_by_similarity() {
+        let mut memory = VectorMemory::default();
+        memory.insert(entry("east", vec![1.0, 0.0]));
+        memory.insert(entry("north", vec![0.0, 1.0]));
+        memory.insert(entry("north east", vec![1.0, 1.0]));
+        memory.insert(entry("north", vec![0.0, 1.0]));
+        memory.insert(entry("elsewhere", vec![1.0, 0.0, 0.0]));
+        assert_eq!(memory.entries.len(), 4);
+
+        let matches = memory.search(&[0.1, 1.0], 2);
+        let texts = matches
+            .iter()
+            .map(|m| m.entry.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["north", "north east"]);
+        assert!(matches[0].score > 0.99);
+        assert_eq!(
+            memory.embedding_of("north east"),
+            Some([1.0, 1.0].as_slice())
+        );
+    }
+
+    #[test]
+    fn writes_wait_for_quiet() {
+        let mut memory = VectorMemory::default();
+        assert!(!memory.should_persist());
+        memory.insert(entry("east", vec![1.0, 0.0]));
+        assert!(!memory.should_persist());
+        memory.changed_at = Some(Instant::now() - VECTOR_MEMORY_QUIET_PERIOD);
+        assert!(memory.should_persist());
+        memory.mark_persisted();
+        assert!(!memory.should_persist());
+    }
+}
@@ -26,6 +26,10 @@ impl Metrics {
         }
     }
 
+    pub fn get(&self, name: &str) -> Option<Duration> {
+        self.completed.get(name).copied()
+    }
+
     #[allow(dead_code)]
     pub fn report(&self) -> String {
         format!(
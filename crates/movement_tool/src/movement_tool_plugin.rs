@@ -1,4 +1,6 @@
 use bevy::prelude::*;
+use cursor_hero_movement_tool_types::prelude::*;
+use cursor_hero_tools::prelude::*;
 
 use crate::movement_speed_plugin::MovementSpeedPlugin;
 use crate::movement_sprint_plugin::MovementSprintPlugin;
@@ -15,5 +17,6 @@ impl Plugin for MovementToolPlugin {
         app.add_plugins(MovementTargetPlugin);
         app.add_plugins(MovementToolPopulatePlugin);
         app.add_plugins(MovementToolTickPlugin);
+        app.add_plugins(ToolCallPlugin::<MovementToolAction>::default());
     }
 }
@@ -1,5 +1,15 @@
+pub mod observation_budget;
+pub mod observation_budget_indicator_plugin;
 pub mod observation_buffer_plugin;
+pub mod observation_filter;
 pub mod observation_log_plugin;
 pub mod observation_plugin;
+pub mod observation_retention_plugin;
 pub mod observation_tool_plugin;
+pub mod observe_calculator_plugin;
 pub mod observe_chat_plugin;
+pub mod observe_click_plugin;
+pub mod observe_environment_plugin;
+pub mod observe_pressure_plate_plugin;
+pub mod observe_tool_activation_plugin;
+pub mod observe_window_position_plugin;
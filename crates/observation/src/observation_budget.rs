@@ -0,0 +1,319 @@
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+
+/// Tokens kept free for the reply
+pub const REPLY_TOKENS: usize = 256;
+
+/// The optional parts of the system prompt may grow it to at most this fraction of the budget
+const SYSTEM_PROMPT_SHARE: f32 = 0.5;
+
+/// Tokens kept free for the line describing omitted observations
+const SUMMARY_TOKENS: usize = 48;
+
+/// Cutting an observation shorter than this leaves nothing useful
+const MIN_TRUNCATED_TOKENS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetedObservation {
+    /// Position in the buffer the observation came from
+    pub index: usize,
+    pub text: String,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BudgetedObservations {
+    /// Oldest first
+    pub observations: Vec<BudgetedObservation>,
+    /// Describes the observations that did not fit
+    pub summary: Option<String>,
+    pub used_tokens: usize,
+    /// Taken up by the system prompt, see [`ObservationBudgeter::fit_system_prompt`]
+    pub system_tokens: usize,
+    pub omitted: usize,
+}
+
+/// The system prompt variables that made it into the budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FittedSystemPrompt {
+    pub variables: PromptVariables,
+    pub tokens: usize,
+    /// Optional variables that were cut short
+    pub truncated: Vec<&'static str>,
+    /// Optional variables that were left out
+    pub dropped: Vec<&'static str>,
+}
+
+impl BudgetedObservations {
+    pub fn report(&self, context_tokens: usize) -> ObservationBudget {
+        let truncated = self
+            .observations
+            .iter()
+            .filter(|observation| observation.truncated)
+            .count();
+        ObservationBudget {
+            context_tokens,
+            used_tokens: self.used_tokens + self.system_tokens + REPLY_TOKENS,
+            included: self.observations.len() - truncated,
+            truncated,
+            omitted: self.omitted,
+        }
+    }
+}
+
+/// Picks which observations make it into a prompt.
+///
+/// The newest observations are considered first so the conversation stays coherent at the end of the prompt.
+/// Older observations are cut down to `older_entry_limit` tokens, and once the budget runs out the rest are replaced by a summary line.
+pub struct ObservationBudgeter<'a> {
+    pub tokenizer: &'a dyn Tokenizer,
+    pub budget: usize,
+    /// This many of the newest observations are never cut down to `older_entry_limit`
+    pub keep_recent: usize,
+    pub older_entry_limit: usize,
+    /// Already taken out of `budget`
+    pub system_tokens: usize,
+}
+
+impl<'a> ObservationBudgeter<'a> {
+    /// Budget for a model with the given context length
+    pub fn for_context(tokenizer: &'a dyn Tokenizer, context_tokens: usize) -> Self {
+        Self {
+            tokenizer,
+            budget: context_tokens.saturating_sub(REPLY_TOKENS),
+            keep_recent: 8,
+            older_entry_limit: 128,
+            system_tokens: 0,
+        }
+    }
+
+    /// Renders the system prompt and takes its tokens out of the budget left for observations.
+    ///
+    /// `variables` always go in. The `optional` ones are added in order while the prompt stays within its share of the budget,
+    /// text that doesn't fit whole is cut short and anything else is left out.
+    pub fn fit_system_prompt<E>(
+        &mut self,
+        mut variables: PromptVariables,
+        optional: Vec<(&'static str, PromptValue)>,
+        render: impl Fn(&PromptVariables) -> Result<String, E>,
+    ) -> Result<FittedSystemPrompt, E> {
+        let max_tokens = (self.budget as f32 * SYSTEM_PROMPT_SHARE) as usize;
+        let mut tokens = self.tokenizer.count_tokens(&render(&variables)?);
+        let mut truncated = Vec::new();
+        let mut dropped = Vec::new();
+        for (name, value) in optional {
+            let mut with_value = variables.clone().with(name, value.clone());
+            let mut with_tokens = self.tokenizer.count_tokens(&render(&with_value)?);
+            if with_tokens > max_tokens {
+                let PromptValue::Text(text) = &value else {
+                    dropped.push(name);
+                    continue;
+                };
+                let excess = with_tokens - max_tokens;
+                let keep = self.tokenizer.count_tokens(text).saturating_sub(excess + 1);
+                if keep < MIN_TRUNCATED_TOKENS {
+                    dropped.push(name);
+                    continue;
+                }
+                let cut = format!("{}…", self.tokenizer.truncate(text, keep).trim_end());
+                with_value = variables.clone().with(name, cut);
+                with_tokens = self.tokenizer.count_tokens(&render(&with_value)?);
+                // token counts don't always add up across a cut
+                if with_tokens > max_tokens {
+                    dropped.push(name);
+                    continue;
+                }
+                truncated.push(name);
+            }
+            variables = with_value;
+            tokens = with_tokens;
+        }
+        self.budget = self.budget.saturating_sub(tokens);
+        self.system_tokens += tokens;
+        Ok(FittedSystemPrompt {
+            variables,
+            tokens,
+            truncated,
+            dropped,
+        })
+    }
+
+    pub fn fit(
+        &self,
+        entries: &[ObservationBufferEntry],
+        render: impl Fn(&ObservationBufferEntry) -> String,
+    ) -> BudgetedObservations {
+        let available = self.budget.saturating_sub(SUMMARY_TOKENS);
+        let mut picked = Vec::new();
+        let mut used_tokens = 0;
+        let mut first_kept = entries.len();
+        for (age, (index, entry)) in entries.iter().enumerate().rev().enumerate() {
+            let limit = match age < self.keep_recent {
+                true => usize::MAX,
+                false => self.older_entry_limit,
+            };
+            let remaining = available.saturating_sub(used_tokens);
+            let text = render(entry);
+            let tokens = self.tokenizer.count_tokens(&text);
+            if tokens <= limit.min(remaining) {
+                used_tokens += tokens;
+                picked.push(BudgetedObservation {
+                    index,
+                    text,
+                    truncated: false,
+                });
+                first_kept = index;
+                continue;
+            }
+            if limit.min(remaining) < MIN_TRUNCATED_TOKENS {
+                break;
+            }
+            // leave room for the ellipsis
+            let cut = self.tokenizer.truncate(&text, limit.min(remaining) - 1);
+            let text = format!("{}…", cut.trim_end());
+            used_tokens += self.tokenizer.count_tokens(&text);
+            picked.push(BudgetedObservation {
+                index,
+                text,
+                truncated: true,
+            });
+            first_kept = index;
+            if remaining <= limit {
+                break;
+            }
+        }
+        picked.reverse();
+
+        let omitted = &entries[..first_kept];
+        let summary = summarize_omitted(omitted);
+        if let Some(summary) = &summary {
+            used_tokens += self.tokenizer.count_tokens(summary);
+        }
+        BudgetedObservations {
+            observations: picked,
+            summary,
+            used_tokens,
+            system_tokens: self.system_tokens,
+            omitted: omitted.len(),
+        }
+    }
+}
+
+fn summarize_omitted(omitted: &[ObservationBufferEntry]) -> Option<String> {
+    let first = omitted.first()?;
+    let mut speakers = Vec::<&str>::new();
+    for entry in omitted {
+        if let SomethingObservableHappenedEvent::Chat { character_name, .. } = &entry.origin {
+            if !speakers.contains(&character_name.as_str()) {
+                speakers.push(character_name);
+            }
+        }
+    }
+    let mut summary = format!(
+        "{} earlier observations since {} were left out to fit the context window",
+        omitted.len(),
+        first.datetime.format("%Y-%m-%d %H:%M")
+    );
+    if !speakers.is_empty() {
+        summary.push_str(&format!(", including chats from {}", speakers.join(", ")));
+    }
+    summary.push('.');
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+
+    fn chat(name: &str, message: &str) -> ObservationBufferEntry {
+        ObservationBufferEntry {
+            datetime: chrono::Local::now(),
+            origin: SomethingObservableHappenedEvent::Chat {
+                environment_id: None,
+                character_id: Entity::PLACEHOLDER,
+                character_name: name.to_string(),
+                message: message.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn keeps_newest_and_summarizes_the_rest() {
+        let entries = (0..100)
+            .map(|i| {
+                chat(
+                    "Tume Eena",
+                    &format!("message number {} {}", i, "blah ".repeat(20)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let budgeter = ObservationBudgeter {
+            tokenizer: &HeuristicTokenizer,
+            budget: 400,
+            keep_recent: 2,
+            older_entry_limit: 20,
+            system_tokens: 0,
+        };
+        let budgeted = budgeter.fit(&entries, |entry| entry.origin.to_string());
+
+        assert!(budgeted.used_tokens <= 400);
+        assert!(budgeted.omitted > 0);
+        assert_eq!(
+            budgeted.observations.len() + budgeted.omitted,
+            entries.len()
+        );
+        let newest = budgeted.observations.last().unwrap();
+        assert_eq!(newest.index, 99);
+        assert!(!newest.truncated);
+        assert!(budgeted.observations[0].truncated);
+        assert!(budgeted
+            .summary
+            .unwrap()
+            .ends_with("including chats from Tume Eena."));
+    }
+
+    #[test]
+    fn system_prompt_counts_against_the_budget() {
+        let render = |variables: &PromptVariables| -> Result<String, ()> {
+            let text = |name: &str| match variables.get(name) {
+                Some(PromptValue::Text(text)) => text.clone(),
+                Some(PromptValue::List(items)) => format!("{} earlier observations", items.len()),
+                _ => String::new(),
+            };
+            Ok(format!(
+                "You are {}. {} {}",
+                text("agent_name"),
+                text("earlier_observations"),
+                text("ui_snapshot")
+            ))
+        };
+        let mut budgeter =
+            ObservationBudgeter::for_context(&HeuristicTokenizer, REPLY_TOKENS + 400);
+        let fitted = budgeter
+            .fit_system_prompt(
+                PromptVariables::default().with("agent_name", "Ithia Tig"),
+                vec![
+                    (
+                        "earlier_observations",
+                        PromptValue::from(vec!["Hello there"; 3]),
+                    ),
+                    ("ui_snapshot", PromptValue::from("window ".repeat(1000))),
+                ],
+                render,
+            )
+            .unwrap();
+
+        assert!(fitted.tokens <= 200);
+        assert_eq!(fitted.truncated, vec!["ui_snapshot"]);
+        assert!(fitted.dropped.is_empty());
+        assert_eq!(budgeter.budget, 400 - fitted.tokens);
+
+        let entries = vec![chat("Tume Eena", "Hi!")];
+        let report = budgeter
+            .fit(&entries, |entry| entry.origin.to_string())
+            .report(REPLY_TOKENS + 400);
+        assert!(report.used_tokens >= fitted.tokens + REPLY_TOKENS);
+        assert!(report.used_tokens <= report.context_tokens);
+    }
+}
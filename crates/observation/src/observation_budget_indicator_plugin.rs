@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+
+/// Shows how much of the context window is left below agents that have an [`ObservationBudget`].
+pub struct ObservationBudgetIndicatorPlugin;
+
+impl Plugin for ObservationBudgetIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_indicators);
+        app.add_systems(Update, update_indicators);
+    }
+}
+
+#[derive(Component, Debug, Reflect)]
+struct ObservationBudgetIndicator;
+
+fn spawn_indicators(
+    mut commands: Commands,
+    query: Query<(Entity, &ObservationBudget), Added<ObservationBudget>>,
+    asset_server: Res<AssetServer>,
+) {
+    for (owner, budget) in query.iter() {
+        let (value, color) = describe(budget);
+        debug!("Spawning observation budget indicator for {:?}", owner);
+        let ratio = 2.0;
+        let indicator = commands
+            .spawn((
+                Name::new("Observation Budget Indicator"),
+                ObservationBudgetIndicator,
+                Text2dBundle {
+                    text: Text::from_section(
+                        value,
+                        TextStyle {
+                            font: asset_server
+                                .load("fonts/kenney_kenney-fonts/Fonts/Kenney Mini.ttf"),
+                            font_size: 12.0 * ratio,
+                            color,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(Vec3::new(0.0, -60.0, 1.0))
+                        .with_scale(Vec3::new(1.0 / ratio, 1.0 / ratio, 1.0)),
+                    ..default()
+                },
+            ))
+            .id();
+        commands.entity(owner).add_child(indicator);
+    }
+}
+
+fn update_indicators(
+    budget_query: Query<(&ObservationBudget, &Children), Changed<ObservationBudget>>,
+    mut indicator_query: Query<&mut Text, With<ObservationBudgetIndicator>>,
+) {
+    for (budget, children) in budget_query.iter() {
+        let mut indicators = indicator_query.iter_many_mut(children);
+        while let Some(mut text) = indicators.fetch_next() {
+            let (value, color) = describe(budget);
+            text.sections[0].value = value;
+            text.sections[0].style.color = color;
+        }
+    }
+}
+
+fn describe(budget: &ObservationBudget) -> (String, Color) {
+    let headroom = budget.headroom_fraction();
+    let value = format!(
+        "context {:.0}% free ({}/{} tokens)",
+        headroom * 100.0,
+        budget.headroom(),
+        budget.context_tokens
+    );
+    let color = match headroom {
+        x if x > 0.5 => Color::GREEN,
+        x if x > 0.2 => Color::YELLOW,
+        _ => Color::RED,
+    };
+    (value, color)
+}
@@ -1,7 +1,8 @@
 use bevy::prelude::*;
-use cursor_hero_environment_types::prelude::*;
 use cursor_hero_observation_types::prelude::*;
 
+use crate::observation_filter::ObservationFilterContext;
+
 /// Responsible for storing observations inside ObservationBuckets of those who are able to observe them.
 pub struct ObservationBufferPlugin;
 
@@ -13,43 +14,18 @@ impl Plugin for ObservationBufferPlugin {
 
 fn update_buffers(
     mut observation_events: EventReader<SomethingObservableHappenedEvent>,
-    mut buffer_query: Query<(Entity, &mut ObservationBuffer, Option<&TrackedEnvironment>)>,
+    mut buffer_query: Query<(Entity, &mut ObservationBuffer, Option<&ObservationFilter>)>,
     mut buffer_events: EventWriter<ObservationBufferEvent>,
+    filter_context: ObservationFilterContext,
 ) {
+    let default_filter = ObservationFilter::default();
     for event in observation_events.read() {
         for buffer in buffer_query.iter_mut() {
-            let (buffer_id, mut buffer, buffer_environment_tag) = buffer;
+            let (buffer_id, mut buffer, buffer_filter) = buffer;
 
             // Determine if the buffer can see the event
-            let can_see = match (buffer_environment_tag, event) {
-                (
-                    Some(TrackedEnvironment {
-                        environment_id: buffer_environment_id,
-                    }),
-                    SomethingObservableHappenedEvent::Chat {
-                        environment_id: Some(event_environment_id),
-                        ..
-                    },
-                ) => *buffer_environment_id == *event_environment_id,
-                (
-                    _,
-                    SomethingObservableHappenedEvent::MemoryRestored {
-                        observation_buffer_id,
-                    },
-                ) => buffer_id == *observation_buffer_id,
-                (
-                    Some(TrackedEnvironment {
-                        environment_id: buffer_environment_id,
-                    }),
-                    SomethingObservableHappenedEvent::UISnapshot {
-                        environment_id: Some(event_environment_id),
-                        ..
-                    },
-                ) => *buffer_environment_id == *event_environment_id,
-                // A buffer outside all environments will observe all environments
-                (None, _) => true,
-                _ => false,
-            };
+            let can_see =
+                filter_context.can_see(buffer_id, buffer_filter.unwrap_or(&default_filter), event);
             if !can_see {
                 if buffer.log_level == ObservationLogLevel::All {
                     debug!("Buffer {:?} cannot see event {}", buffer_id, event)
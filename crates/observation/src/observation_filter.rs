@@ -0,0 +1,97 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use cursor_hero_environment_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+
+/// Colliders a line of sight ray can pass through before giving up
+const MAX_LINE_OF_SIGHT_HITS: u32 = 32;
+
+/// Everything the [`ObservationRule`]s need to know about the world
+#[derive(SystemParam)]
+pub struct ObservationFilterContext<'w, 's> {
+    environment_query: Query<'w, 's, &'static TrackedEnvironment>,
+    transform_query: Query<'w, 's, &'static GlobalTransform>,
+    sensor_query: Query<'w, 's, (), With<Sensor>>,
+    spatial_query: SpatialQuery<'w, 's>,
+}
+
+impl<'w, 's> ObservationFilterContext<'w, 's> {
+    pub fn can_see(
+        &self,
+        observer_id: Entity,
+        filter: &ObservationFilter,
+        event: &SomethingObservableHappenedEvent,
+    ) -> bool {
+        if let Some(observation_buffer_id) = event.observation_buffer_id() {
+            return observer_id == observation_buffer_id;
+        }
+        filter
+            .rules
+            .iter()
+            .all(|rule| self.passes(observer_id, rule, event))
+    }
+
+    fn passes(
+        &self,
+        observer_id: Entity,
+        rule: &ObservationRule,
+        event: &SomethingObservableHappenedEvent,
+    ) -> bool {
+        match rule {
+            ObservationRule::SameEnvironment => event.shares_environment(
+                self.environment_query
+                    .get(observer_id)
+                    .ok()
+                    .map(|tracked| tracked.environment_id),
+            ),
+            ObservationRule::Proximity { radius } => {
+                let Some(source_id) = event.source_id() else {
+                    return true;
+                };
+                match self.positions(observer_id, source_id) {
+                    Some((observer, source)) => observer.distance(source) <= *radius,
+                    None => false,
+                }
+            }
+            ObservationRule::LineOfSight => {
+                let Some(source_id) = event.source_id() else {
+                    return true;
+                };
+                if source_id == observer_id {
+                    return true;
+                }
+                let Some((observer, source)) = self.positions(observer_id, source_id) else {
+                    return false;
+                };
+                let distance = observer.distance(source);
+                if distance == 0.0 {
+                    return true;
+                }
+                // sensors, like the bounds of an environment, don't block the view
+                !self
+                    .spatial_query
+                    .ray_hits(
+                        observer,
+                        (source - observer) / distance,
+                        distance,
+                        MAX_LINE_OF_SIGHT_HITS,
+                        true,
+                        SpatialQueryFilter::new().without_entities([observer_id, source_id]),
+                    )
+                    .iter()
+                    .any(|hit| !self.sensor_query.contains(hit.entity))
+            }
+            ObservationRule::Characters(names) => match event.character_name() {
+                Some(character_name) => names.iter().any(|name| name == character_name),
+                None => true,
+            },
+        }
+    }
+
+    fn positions(&self, observer_id: Entity, source_id: Entity) -> Option<(Vec2, Vec2)> {
+        let observer = self.transform_query.get(observer_id).ok()?;
+        let source = self.transform_query.get(source_id).ok()?;
+        Some((observer.translation().xy(), source.translation().xy()))
+    }
+}
@@ -1,9 +1,17 @@
 use bevy::prelude::*;
 
+use crate::observation_budget_indicator_plugin::ObservationBudgetIndicatorPlugin;
 use crate::observation_buffer_plugin::ObservationBufferPlugin;
 use crate::observation_log_plugin::ObservationLogPlugin;
+use crate::observation_retention_plugin::ObservationRetentionPlugin;
 use crate::observation_tool_plugin::ObservationToolPlugin;
+use crate::observe_calculator_plugin::ObserveCalculatorPlugin;
 use crate::observe_chat_plugin::ObserveChatPlugin;
+use crate::observe_click_plugin::ObserveClickPlugin;
+use crate::observe_environment_plugin::ObserveEnvironmentPlugin;
+use crate::observe_pressure_plate_plugin::ObservePressurePlatePlugin;
+use crate::observe_tool_activation_plugin::ObserveToolActivationPlugin;
+use crate::observe_window_position_plugin::ObserveWindowPositionPlugin;
 
 pub struct ObservationPlugin;
 
@@ -12,6 +20,14 @@ impl Plugin for ObservationPlugin {
         app.add_plugins(ObservationLogPlugin);
         app.add_plugins(ObservationToolPlugin);
         app.add_plugins(ObservationBufferPlugin);
+        app.add_plugins(ObservationRetentionPlugin);
         app.add_plugins(ObserveChatPlugin);
+        app.add_plugins(ObserveEnvironmentPlugin);
+        app.add_plugins(ObserveToolActivationPlugin);
+        app.add_plugins(ObserveClickPlugin);
+        app.add_plugins(ObservePressurePlatePlugin);
+        app.add_plugins(ObserveWindowPositionPlugin);
+        app.add_plugins(ObserveCalculatorPlugin);
+        app.add_plugins(ObservationBudgetIndicatorPlugin);
     }
 }
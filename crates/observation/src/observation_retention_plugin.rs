@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use cursor_hero_agent_types::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+
+/// Trims observation buffers according to their [`ObservationRetention`], summarizing old entries through text inference.
+pub struct ObservationRetentionPlugin;
+
+impl Plugin for ObservationRetentionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RetentionCooldown>();
+        app.add_systems(Update, apply_retention);
+        app.add_systems(Update, handle_summary_outcome);
+    }
+}
+
+#[derive(Resource)]
+struct RetentionCooldown {
+    timer: Timer,
+}
+
+impl Default for RetentionCooldown {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_retention(
+    mut commands: Commands,
+    mut cooldown: ResMut<RetentionCooldown>,
+    time: Res<Time>,
+    mut buffer_query: Query<(
+        Entity,
+        &mut ObservationBuffer,
+        &ObservationRetention,
+        Option<&AgentPersona>,
+        Option<&Name>,
+    )>,
+    session_query: Query<&ObservationSummarySession>,
+    mut inference_events: EventWriter<TextInferenceEvent>,
+) {
+    if !cooldown.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let now = chrono::Local::now();
+    for buffer in buffer_query.iter_mut() {
+        let (buffer_id, mut buffer, retention, persona, name) = buffer;
+        let plan = retention.plan(&buffer.observations, now);
+        if plan.is_empty() {
+            continue;
+        }
+        let summarizing = session_query
+            .iter()
+            .any(|session| session.observation_buffer_id == buffer_id);
+
+        if !plan.summarize.is_empty() && !summarizing {
+            let run = plan
+                .summarize
+                .iter()
+                .map(|index| &buffer.observations[*index])
+                .collect::<Vec<_>>();
+            let agent_name = match (persona, name) {
+                (Some(persona), _) => persona.name.clone(),
+                (None, Some(name)) => name.to_string(),
+                (None, None) => "the agent".to_string(),
+            };
+            let variables = PromptVariables::default()
+                .with("agent_name", agent_name)
+                .with(
+                    "observations",
+                    run.iter()
+                        .map(|entry| {
+                            PromptVariables::default()
+                                .with(
+                                    "datetime",
+                                    entry.datetime.format("%Y-%m-%d %H:%M").to_string(),
+                                )
+                                .with("text", entry.origin.to_string())
+                        })
+                        .collect::<Vec<_>>(),
+                );
+            let session_id = commands
+                .spawn((
+                    Name::new("Observation Summary Session"),
+                    ObservationSummarySession {
+                        observation_buffer_id: buffer_id,
+                        run: run.into_iter().cloned().collect(),
+                    },
+                ))
+                .id();
+            info!(
+                "Summarizing {} observations of buffer {:?}",
+                plan.summarize.len(),
+                buffer_id
+            );
+            inference_events.send(TextInferenceEvent::Request {
+                session_id,
+                prompt: TextPrompt::Template {
+                    template: PromptTemplateRef::new("summary", variables),
+                    options: Some(TextInferenceOptions {
+                        num_predict: Some(256),
+                        priority: InferencePriority::Background,
+                        ..default()
+                    }),
+                },
+            });
+        }
+
+        let mut evict = plan.evict;
+        if summarizing && !plan.overflow.is_empty() {
+            warn!(
+                "Buffer {:?} is over its hard limit while a summary is pending, dropping {} observations",
+                buffer_id,
+                plan.overflow.len()
+            );
+            evict.extend(plan.overflow);
+            evict.sort();
+            evict.dedup();
+        }
+        if evict.is_empty() {
+            continue;
+        }
+        if buffer.log_level == ObservationLogLevel::All {
+            debug!(
+                "Buffer {:?} evicting {} observations",
+                buffer_id,
+                evict.len()
+            );
+        }
+        let mut index = 0;
+        buffer.observations.retain(|_| {
+            let keep = evict.binary_search(&index).is_err();
+            index += 1;
+            keep
+        });
+    }
+}
+
+fn handle_summary_outcome(
+    mut commands: Commands,
+    mut inference_events: EventReader<TextInferenceEvent>,
+    session_query: Query<&ObservationSummarySession>,
+    mut buffer_query: Query<&mut ObservationBuffer>,
+) {
+    for event in inference_events.read() {
+        let (session_id, summary) = match event {
+            TextInferenceEvent::Response {
+                session_id,
+                response,
+                ..
+            } => (session_id, Some(response.trim()).filter(|x| !x.is_empty())),
+            TextInferenceEvent::Failed { session_id, .. } => (session_id, None),
+            _ => continue,
+        };
+        let Ok(session) = session_query.get(*session_id) else {
+            continue;
+        };
+        commands.entity(*session_id).despawn_recursive();
+        let Ok(mut buffer) = buffer_query.get_mut(session.observation_buffer_id) else {
+            continue;
+        };
+
+        let (Some(position), removed) = session.take_run(&mut buffer.observations) else {
+            continue;
+        };
+        let Some(through) = removed.last().map(|entry| entry.datetime) else {
+            continue;
+        };
+        // summaries of summaries stand in for everything below them
+        let summarized = removed
+            .iter()
+            .map(|entry| match entry.origin {
+                SomethingObservableHappenedEvent::Summary { summarized, .. } => summarized,
+                _ => 1,
+            })
+            .sum();
+
+        let Some(summary) = summary else {
+            // the entries are over the limit either way, the history still has them
+            warn!(
+                "Failed to summarize {} observations of buffer {:?}, dropping them",
+                summarized, session.observation_buffer_id
+            );
+            continue;
+        };
+        info!(
+            "Replaced {} observations of buffer {:?} with a summary",
+            summarized, session.observation_buffer_id
+        );
+        buffer.observations.insert(
+            position,
+            ObservationBufferEntry {
+                datetime: through,
+                origin: SomethingObservableHappenedEvent::Summary {
+                    observation_buffer_id: session.observation_buffer_id,
+                    summarized,
+                    summary: summary.to_string(),
+                },
+            },
+        );
+    }
+}
@@ -1,19 +1,41 @@
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy::utils::HashSet;
+use cursor_hero_agent_types::prelude::*;
 use cursor_hero_character_types::character_types::AgentCharacter;
 use cursor_hero_chat_types::chat_types::ChatEvent;
+use cursor_hero_environment_types::environment_types::TrackedEnvironment;
+use cursor_hero_inference::prompt_template::PromptTemplateEngine;
 use cursor_hero_inference_types::prelude::*;
+use cursor_hero_memory_types::prelude::VectorMemory;
+use cursor_hero_memory_types::prelude::VectorMemoryMatch;
 use cursor_hero_observation_types::prelude::*;
+use cursor_hero_text_asset_types::prelude::*;
 use cursor_hero_toolbelt_types::prelude::*;
 use cursor_hero_tools::prelude::*;
+use std::collections::VecDeque;
+
+use crate::observation_budget::ObservationBudgeter;
 
 pub struct ObservationToolPlugin;
 
 impl Plugin for ObservationToolPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<UnspokenPartials>();
+        app.init_resource::<QueuedSpeech>();
         app.add_systems(Update, toolbelt_events);
         app.add_systems(Update, tool_tick);
-        app.add_systems(Update, handle_text_inference_response);
+        app.add_systems(Update, handle_text_inference_partial);
+        app.add_systems(
+            Update,
+            handle_text_inference_response.after(handle_text_inference_partial),
+        );
+        app.add_systems(Update, handle_text_inference_outcome);
         app.add_systems(Update, handle_tts_inference_response);
+        app.add_systems(
+            Update,
+            play_queued_speech.after(handle_tts_inference_response),
+        );
     }
 }
 
@@ -39,12 +61,27 @@ fn toolbelt_events(
     }
 }
 
-#[allow(clippy::type_complexity)]
+/// How many chats from before the observation buffer are recalled from the history
+const EARLIER_OBSERVATIONS: usize = 5;
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn tool_tick(
     mut tool_query: Query<(&Parent, &mut ObservationTool), With<ActiveTool>>,
-    toolbelt_query: Query<&Parent, With<Toolbelt>>,
-    mut character_query: Query<&mut ObservationBuffer>,
+    toolbelt_query: Query<(&Parent, &Children), With<Toolbelt>>,
+    toolbelt_tool_query: Query<(&Tool, &ToolCallActions)>,
+    mut character_query: Query<(&mut ObservationBuffer, Option<&AgentPersona>)>,
+    agent_query: Query<(&AgentPersona, Option<&TrackedEnvironment>)>,
     mut events: EventWriter<TextInferenceEvent>,
+    name_query: Query<&Name>,
+    environment_query: Query<&TrackedEnvironment>,
+    roster: Res<AgentRoster>,
+    config: Res<InferenceConfig>,
+    tokenizer: Res<TextTokenizer>,
+    memories: (Option<Res<ObservationHistory>>, Option<Res<VectorMemory>>),
+    prompt_assets: (Res<PromptTemplates>, Res<Assets<TextAsset>>),
+    mut unspoken: ResMut<UnspokenPartials>,
+    mut chat_events: EventWriter<ChatEvent>,
+    mut commands: Commands,
 ) {
     for tool in tool_query.iter_mut() {
         let (tool_parent, mut tool) = tool;
@@ -53,7 +90,7 @@ fn tool_tick(
             warn!("Failed to get toolbelt");
             continue;
         };
-        let toolbelt_parent = toolbelt;
+        let (toolbelt_parent, toolbelt_children) = toolbelt;
 
         let character_id = toolbelt_parent.get();
         let Ok(character) = character_query.get_mut(character_id) else {
@@ -61,24 +98,38 @@ fn tool_tick(
             continue;
         };
 
-        let character_observation_buffer = character;
-        let whats_new = tool
-            .last_inference
-            .map(|last_inference| {
-                character_observation_buffer
-                    .observations
-                    .iter()
-                    .filter(|entry| entry.datetime > last_inference)
-                    .map(|entry| entry.origin.into_whats_new(character_id))
-                    .fold(WhatsNew::Nothing, |acc, new| acc.max(new))
+        let (character_observation_buffer, persona) = character;
+        let agent_name = match persona {
+            Some(persona) => persona.name.clone(),
+            None => name_query
+                .get(character_id)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|_| "the agent".to_string()),
+        };
+        let environment_id = environment_query
+            .get(character_id)
+            .ok()
+            .map(|tracked| tracked.environment_id);
+        // everyone sharing the environment, including this agent
+        let agent_names = agent_query
+            .iter()
+            .filter(|(_, tracked)| tracked.map(|tracked| tracked.environment_id) == environment_id)
+            .map(|(persona, _)| persona.name.clone())
+            .collect::<Vec<_>>();
+
+        let whats_new = character_observation_buffer
+            .observations
+            .iter()
+            .filter(|entry| match tool.last_inference {
+                Some(last_inference) => entry.datetime > last_inference,
+                None => true,
             })
-            .unwrap_or_else(|| {
-                character_observation_buffer
-                    .observations
-                    .iter()
-                    .map(|entry| entry.origin.into_whats_new(character_id))
-                    .fold(WhatsNew::Nothing, |acc, new| acc.max(new))
-            });
+            .map(|entry| {
+                entry
+                    .origin
+                    .into_whats_new_among(character_id, &agent_name, &agent_names)
+            })
+            .fold(WhatsNew::Nothing, |acc, new| acc.max(new));
 
         // Update the field for debug viewing in the inspector
         tool._whats_new = Some(whats_new);
@@ -95,31 +146,351 @@ fn tool_tick(
             }
         }
 
-        let mut chat_history = String::new();
-        for entry in character_observation_buffer.observations.iter() {
-            // let timestamp = entry.datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-            chat_history.push_str(entry.origin.to_string().as_str());
-            chat_history.push('\n');
+        if let Some(retry_after) = tool.retry_after {
+            if retry_after > chrono::Local::now() {
+                continue;
+            }
+        }
+
+        let other_agents = agent_names
+            .iter()
+            .filter(|name| **name != agent_name)
+            .collect::<Vec<_>>();
+        let mut variables = PromptVariables::default()
+            .with("agent_name", agent_name.as_str())
+            .with("user_name", roster.user_name.as_str())
+            .with(
+                "system_prompt",
+                persona
+                    .map(|persona| persona.system_prompt.as_str())
+                    .unwrap_or_default(),
+            )
+            .with(
+                "other_agents",
+                other_agents
+                    .iter()
+                    .map(|name| PromptVariables::default().with("name", name.as_str()))
+                    .collect::<Vec<_>>(),
+            )
+            .with(
+                "current_time",
+                chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            );
+        if let Some(environment_name) =
+            environment_id.and_then(|environment_id| name_query.get(environment_id).ok())
+        {
+            variables.insert("environment_name", environment_name.to_string());
+        }
+        // left out or cut short when the system prompt would crowd out the observations
+        let mut optional_variables = Vec::<(&'static str, PromptValue)>::new();
+        // chats from before the oldest observation still in the buffer, unless a summary already covers them
+        let (history, vector_memory) = &memories;
+        if let (Some(history), Some(oldest), Ok(observer)) = (
+            history.as_ref(),
+            character_observation_buffer
+                .observations
+                .first()
+                .filter(|oldest| oldest.origin.kind() != ObservationKind::Summary),
+            name_query.get(character_id),
+        ) {
+            match history.query(&ObservationHistoryQuery {
+                observer: Some(observer.to_string()),
+                until: Some(oldest.datetime),
+                kinds: vec![ObservationKind::Chat],
+                limit: EARLIER_OBSERVATIONS,
+                ..default()
+            }) {
+                Ok(rows) if !rows.is_empty() => {
+                    optional_variables.push((
+                        "earlier_observations",
+                        rows.iter()
+                            .map(|row| {
+                                PromptVariables::default()
+                                    .with(
+                                        "datetime",
+                                        row.entry.datetime.format("%Y-%m-%d %H:%M").to_string(),
+                                    )
+                                    .with("text", row.entry.origin.to_string())
+                            })
+                            .collect::<Vec<_>>()
+                            .into(),
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to query observation history: {:?}", e),
+            }
+        }
+        if let Some(vector_memory) = vector_memory.as_ref() {
+            let recalled = recall(vector_memory, &character_observation_buffer);
+            if !recalled.is_empty() {
+                optional_variables.push((
+                    "recalled_memories",
+                    recalled
+                        .iter()
+                        .map(|recalled| {
+                            PromptVariables::default()
+                                .with(
+                                    "datetime",
+                                    recalled.entry.datetime.format("%Y-%m-%d %H:%M").to_string(),
+                                )
+                                .with("text", recalled.entry.text.as_str())
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                ));
+            }
+        }
+        if let Some(snapshot) = character_observation_buffer
+            .observations
+            .iter()
+            .rev()
+            .find_map(|entry| match &entry.origin {
+                SomethingObservableHappenedEvent::UISnapshot { snapshot, .. } => Some(snapshot),
+                _ => None,
+            })
+        {
+            optional_variables.push(("ui_snapshot", snapshot.to_string().trim().into()));
+        }
+        let tools = tool_definitions(toolbelt_children, &toolbelt_tool_query);
+        if !tools.is_empty() {
+            variables.insert(
+                "tools",
+                tools
+                    .iter()
+                    .map(|tool| {
+                        PromptVariables::default()
+                            .with("name", tool.function_name())
+                            .with("description", tool.description.as_str())
+                    })
+                    .collect::<Vec<_>>(),
+            );
         }
 
+        let template = persona
+            .map(|persona| persona.template.clone())
+            .unwrap_or_else(|| "system".to_string());
+        let context_tokens = config.context_length.unwrap_or(DEFAULT_CONTEXT_LENGTH);
+        let mut budgeter = ObservationBudgeter::for_context(tokenizer.0.as_ref(), context_tokens);
+        let engine = PromptTemplateEngine::new(&prompt_assets.0, &prompt_assets.1);
+        let render = |variables: &PromptVariables| {
+            engine.render(&PromptTemplateRef::new(
+                template.as_str(),
+                variables.clone(),
+            ))
+        };
+        let system = match budgeter.fit_system_prompt(variables.clone(), optional_variables, render)
+        {
+            Ok(fitted) => {
+                if !fitted.truncated.is_empty() || !fitted.dropped.is_empty() {
+                    debug!(
+                        "System prompt of {:?} is {} tokens, cut short {:?}, left out {:?}",
+                        character_id, fitted.tokens, fitted.truncated, fitted.dropped
+                    );
+                }
+                fitted.variables
+            }
+            Err(e) => {
+                // the request fails the same way and is retried
+                warn!(
+                    "Failed to render system prompt of {:?}: {}",
+                    character_id, e
+                );
+                variables
+            }
+        };
+        let budgeted = budgeter.fit(
+            &character_observation_buffer.observations,
+            |entry| match &entry.origin {
+                SomethingObservableHappenedEvent::Chat { message, .. } => message.clone(),
+                other => other.to_string(),
+            },
+        );
+        let report = budgeted.report(context_tokens);
+        debug!("Observation budget for {:?}: {:?}", character_id, report);
+        commands.entity(character_id).insert(report);
+
+        let mut messages = Vec::new();
+        if let Some(summary) = budgeted.summary {
+            messages.push(ChatMessage::new(ChatRole::System, summary));
+        }
+        for observation in budgeted.observations {
+            let entry = &character_observation_buffer.observations[observation.index];
+            let message = match &entry.origin {
+                SomethingObservableHappenedEvent::Chat {
+                    character_id: speaker_id,
+                    character_name,
+                    ..
+                } => {
+                    let role = match *speaker_id == character_id {
+                        true => ChatRole::Assistant,
+                        false => ChatRole::User,
+                    };
+                    ChatMessage::new(role, observation.text).with_name(character_name.as_str())
+                }
+                _ => ChatMessage::new(ChatRole::System, observation.text),
+            };
+            messages.push(message);
+        }
+
+        // keeps the model from speaking for anyone else
+        let stop = ["\n".to_string(), "(Human)".to_string()]
+            .into_iter()
+            .chain(
+                std::iter::once(&roster.user_name)
+                    .chain(agent_names.iter())
+                    .map(|name| format!("({})", name)),
+            )
+            .collect();
+        let chat_received = matches!(
+            whats_new,
+            WhatsNew::ChatReceived | WhatsNew::ChatReceivedButTheyProbablyStillThinking
+        );
+        if tool.awaiting_response && chat_received {
+            // the reply in the works answers what was said before, it would arrive after the new one started
+            debug!(
+                "Cancelling stale request of session {:?} for a newer chat",
+                character_id
+            );
+            events.send(TextInferenceEvent::Cancel {
+                session_id: character_id,
+            });
+            if unspoken.0.remove(&character_id).is_some() {
+                chat_events.send(ChatEvent::Abandoned { character_id });
+            }
+        }
         events.send(TextInferenceEvent::Request {
             session_id: character_id,
-            prompt: TextPrompt::Chat {
-                chat_history,
+            prompt: TextPrompt::Messages {
+                system: Some(PromptTemplateRef::new(template, system)),
+                messages,
                 options: Some(TextInferenceOptions {
-                    stop: Some(vec![
-                        "\n".to_string(),
-                        "(Human)".to_string(),
-                        "(Tume Eena)".to_string(),
-                        "(Ithia Tig)".to_string(),
-                    ]),
+                    stop: Some(stop),
+                    tools: Some(tools).filter(|tools| !tools.is_empty()),
+                    priority: match chat_received {
+                        true => InferencePriority::UserChat,
+                        false => InferencePriority::Observation,
+                    },
+                    model: persona.and_then(|persona| persona.model.clone()),
                     ..default()
                 }),
             },
         });
         debug!("ObservationToolPlugin: Sent observation event");
 
+        tool.previous_inference = tool.last_inference;
         tool.last_inference = Some(chrono::Local::now());
+        tool.awaiting_response = true;
+    }
+}
+
+/// How many entries of the vector memory are recalled into the system prompt
+const RECALLED_MEMORIES: usize = 5;
+
+/// Less similar entries are not worth their tokens
+const MIN_RECALL_SIMILARITY: f32 = 0.5;
+
+/// Entries of the vector memory resembling the latest observation that has been embedded.
+///
+/// Observations still in the buffer are left out, they are in the prompt already.
+fn recall<'a>(memory: &'a VectorMemory, buffer: &ObservationBuffer) -> Vec<VectorMemoryMatch<'a>> {
+    let buffered = buffer
+        .observations
+        .iter()
+        .map(|entry| entry.origin.to_string().trim().to_string())
+        .collect::<HashSet<_>>();
+    let Some(embedding) = buffer
+        .observations
+        .iter()
+        .rev()
+        .find_map(|entry| memory.embedding_of(entry.origin.to_string().trim()))
+    else {
+        return Vec::new();
+    };
+    memory
+        .search(embedding, RECALLED_MEMORIES + buffered.len())
+        .into_iter()
+        .filter(|recalled| recalled.score >= MIN_RECALL_SIMILARITY)
+        .filter(|recalled| !buffered.contains(&recalled.entry.text))
+        .take(RECALLED_MEMORIES)
+        .collect()
+}
+
+/// Tools on the toolbelt that can be driven by tool calls, see [`ToolCallActions`]
+fn tool_definitions(
+    toolbelt_children: &Children,
+    tool_query: &Query<(&Tool, &ToolCallActions)>,
+) -> Vec<ToolDefinition> {
+    tool_query
+        .iter_many(toolbelt_children)
+        .filter(|(_, actions)| !actions.0.is_empty())
+        .map(|(tool, actions)| {
+            let mut actions = actions.0.clone();
+            // keeps prompts stable between requests
+            actions.sort();
+            ToolDefinition {
+                name: tool.name.clone(),
+                description: tool.description.clone(),
+                actions,
+            }
+        })
+        .collect()
+}
+
+/// Text of streamed responses that has not been spoken yet, by session
+#[derive(Resource, Default)]
+struct UnspokenPartials(HashMap<Entity, String>);
+
+/// Splits off everything up to and including the last sentence terminator that is followed by whitespace.
+fn take_complete_sentences(buffer: &mut String) -> Option<String> {
+    let split_at = buffer
+        .char_indices()
+        .zip(buffer.chars().skip(1))
+        .filter(|((_, c), next)| matches!(c, '.' | '!' | '?' | '\n') && next.is_whitespace())
+        .map(|((i, c), _)| i + c.len_utf8())
+        .last()?;
+    let rest = buffer.split_off(split_at);
+    let sentences = std::mem::replace(buffer, rest.trim_start().to_string());
+    let sentences = sentences.trim();
+    if sentences.is_empty() {
+        None
+    } else {
+        Some(sentences.to_string())
+    }
+}
+
+fn handle_text_inference_partial(
+    mut inference_events: EventReader<TextInferenceEvent>,
+    mut chat_events: EventWriter<ChatEvent>,
+    mut tts_events: EventWriter<SpeechInferenceEvent>,
+    mut unspoken: ResMut<UnspokenPartials>,
+    agent_query: Query<Option<&AgentPersona>, With<AgentCharacter>>,
+) {
+    for event in inference_events.read() {
+        let TextInferenceEvent::Partial { session_id, delta } = event else {
+            continue;
+        };
+        let Ok(persona) = agent_query.get(*session_id) else {
+            continue;
+        };
+        chat_events.send(ChatEvent::Partial {
+            character_id: *session_id,
+            delta: delta.clone(),
+        });
+        let buffer = unspoken.0.entry(*session_id).or_default();
+        buffer.push_str(delta);
+
+        // Start speaking finished sentences while the rest of the response is still generating
+        if let Some(sentences) = take_complete_sentences(buffer) {
+            let event = SpeechInferenceEvent::Request {
+                session_id: *session_id,
+                prompt: SpeechPrompt::Raw {
+                    content: sentences,
+                    voice: persona.and_then(|persona| persona.voice.clone()),
+                },
+            };
+            debug!("Sending event: {:?}", event);
+            tts_events.send(event);
+        }
     }
 }
 
@@ -127,7 +498,8 @@ fn handle_text_inference_response(
     mut inference_events: EventReader<TextInferenceEvent>,
     mut chat_events: EventWriter<ChatEvent>,
     mut tts_events: EventWriter<SpeechInferenceEvent>,
-    agent_query: Query<(), With<AgentCharacter>>,
+    mut unspoken: ResMut<UnspokenPartials>,
+    agent_query: Query<Option<&AgentPersona>, With<AgentCharacter>>,
 ) {
     for event in inference_events.read() {
         let TextInferenceEvent::Response {
@@ -138,10 +510,13 @@ fn handle_text_inference_response(
         else {
             continue;
         };
-        if agent_query.get(*session_id).is_err() {
+        let Ok(persona) = agent_query.get(*session_id) else {
             // Only inference responses for agent sessions are to be converted to chat messages and spoken
             continue;
-        }
+        };
+
+        // Sentences already spoken while streaming must not be spoken again
+        let streamed = unspoken.0.remove(session_id);
 
         if response.is_empty() {
             debug!("Received empty response, skipping");
@@ -155,10 +530,21 @@ fn handle_text_inference_response(
         debug!("Sending event: {:?}", event);
         chat_events.send(event);
 
+        let content = match streamed {
+            Some(remainder) => remainder
+                .trim_end_matches("<dummy32000>")
+                .trim()
+                .to_string(),
+            None => response.clone(),
+        };
+        if content.is_empty() {
+            continue;
+        }
         let event = SpeechInferenceEvent::Request {
             session_id: *session_id,
             prompt: SpeechPrompt::Raw {
-                content: response.clone(),
+                content,
+                voice: persona.and_then(|persona| persona.voice.clone()),
             },
         };
         debug!("Sending event: {:?}", event);
@@ -166,11 +552,63 @@ fn handle_text_inference_response(
     }
 }
 
+fn handle_text_inference_outcome(
+    mut inference_events: EventReader<TextInferenceEvent>,
+    mut tool_query: Query<(&Parent, &mut ObservationTool)>,
+    toolbelt_query: Query<&Parent, With<Toolbelt>>,
+    mut unspoken: ResMut<UnspokenPartials>,
+    mut chat_events: EventWriter<ChatEvent>,
+) {
+    for event in inference_events.read() {
+        let (session_id, failure) = match event {
+            TextInferenceEvent::Response { session_id, .. } => (session_id, None),
+            TextInferenceEvent::Failed { session_id, reason } => (session_id, Some(reason)),
+            _ => continue,
+        };
+        if failure.is_some() && unspoken.0.remove(session_id).is_some() {
+            chat_events.send(ChatEvent::Abandoned {
+                character_id: *session_id,
+            });
+        }
+        for (tool_parent, mut tool) in tool_query.iter_mut() {
+            let Ok(toolbelt_parent) = toolbelt_query.get(tool_parent.get()) else {
+                continue;
+            };
+            if toolbelt_parent.get() != *session_id {
+                continue;
+            }
+            tool.awaiting_response = false;
+
+            let Some(reason) = failure else {
+                tool.failed_attempts = 0;
+                tool.retry_after = None;
+                continue;
+            };
+
+            // Rewind so the observations that prompted the failed request trigger a retry
+            tool.last_inference = tool.previous_inference;
+            tool.failed_attempts += 1;
+            let retry_delay = tool.retry_delay();
+            tool.retry_after = chrono::Duration::from_std(retry_delay)
+                .ok()
+                .map(|delay| chrono::Local::now() + delay);
+            warn!(
+                "Inference for session {:?} failed ({}), attempt {}, retrying in {:?}",
+                session_id, reason, tool.failed_attempts, retry_delay
+            );
+        }
+    }
+}
+
+/// Speech clips waiting for the previous clip of the same session to finish playing
+#[derive(Resource, Default)]
+struct QueuedSpeech(HashMap<Entity, VecDeque<Handle<AudioSource>>>);
+
 fn handle_tts_inference_response(
-    mut commands: Commands,
     mut tts_events: EventReader<SpeechInferenceEvent>,
     agent_query: Query<(), With<AgentCharacter>>,
     mut audio_assets: ResMut<Assets<AudioSource>>,
+    mut queued: ResMut<QueuedSpeech>,
 ) {
     for event in tts_events.read() {
         if let SpeechInferenceEvent::Response {
@@ -179,19 +617,41 @@ fn handle_tts_inference_response(
         {
             if agent_query.get(*session_id).is_ok() {
                 info!(
-                    "Received TTS response for session {:?}, playing",
+                    "Received TTS response for session {:?}, queueing",
                     session_id
                 );
                 let audio = audio_assets.add(AudioSource {
                     bytes: wav.clone().into(),
                 });
-                commands.entity(*session_id).insert({
-                    AudioBundle {
-                        source: audio,
-                        settings: PlaybackSettings::REMOVE.with_spatial(true),
-                    }
-                });
+                queued.0.entry(*session_id).or_default().push_back(audio);
             }
         }
     }
 }
+
+fn play_queued_speech(
+    mut commands: Commands,
+    mut queued: ResMut<QueuedSpeech>,
+    playing_query: Query<(), With<Handle<AudioSource>>>,
+    agent_query: Query<(), With<AgentCharacter>>,
+) {
+    queued
+        .0
+        .retain(|session_id, clips| agent_query.contains(*session_id) && !clips.is_empty());
+    for (session_id, clips) in queued.0.iter_mut() {
+        // PlaybackSettings::REMOVE takes the audio components off the agent once the previous clip is done
+        if playing_query.contains(*session_id) {
+            continue;
+        }
+        let Some(audio) = clips.pop_front() else {
+            continue;
+        };
+        info!("Playing TTS for session {:?}", session_id);
+        commands.entity(*session_id).insert({
+            AudioBundle {
+                source: audio,
+                settings: PlaybackSettings::REMOVE.with_spatial(true),
+            }
+        });
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+use cursor_hero_calculator_app_types::calculator_app_types::CalculatorResultEvent;
+use cursor_hero_observation_types::prelude::*;
+
+pub struct ObserveCalculatorPlugin;
+
+impl Plugin for ObserveCalculatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_calculator_results);
+    }
+}
+
+fn observe_calculator_results(
+    mut result_events: EventReader<CalculatorResultEvent>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+) {
+    for result in result_events.read() {
+        let event = SomethingObservableHappenedEvent::CalculatorResult {
+            environment_id: Some(result.environment_id),
+            calculator_id: result.calculator_id,
+            expression: result.expression.clone(),
+            value: result.value.clone(),
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
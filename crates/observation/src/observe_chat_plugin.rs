@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use cursor_hero_agent_types::prelude::*;
 use cursor_hero_character_types::prelude::*;
 use cursor_hero_chat_types::prelude::*;
 use cursor_hero_environment_types::environment_types::TrackedEnvironment;
@@ -11,16 +12,27 @@ impl Plugin for ObserveChatPlugin {
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn observe_chat(
     mut chat_events: EventReader<ChatEvent>,
     mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
-    character_query: Query<(Option<&Name>, Option<&TrackedEnvironment>), With<Character>>,
+    character_query: Query<
+        (
+            Option<&Name>,
+            Option<&AgentPersona>,
+            Option<&TrackedEnvironment>,
+        ),
+        With<Character>,
+    >,
 ) {
     for event in chat_events.read() {
         let ChatEvent::Chat {
             character_id,
             message,
-        } = event;
+        } = event
+        else {
+            continue;
+        };
         let Ok(character) = character_query.get(*character_id) else {
             warn!(
                 "Chat event for unknown character? character_id {:?}",
@@ -28,8 +40,13 @@ fn observe_chat(
             );
             continue;
         };
-        let (character_name, character_environment_tag) = character;
+        let (character_name, character_persona, character_environment_tag) = character;
 
+        // agents are addressed by their persona name
+        let character_name = match character_persona {
+            Some(persona) => Some(persona.name.clone()),
+            None => character_name.map(|name| name.to_string()),
+        };
         let Some(character_name) = character_name else {
             warn!(
                 "Chat event for character with no name? character_id {:?}",
@@ -43,7 +60,7 @@ fn observe_chat(
         let event = SomethingObservableHappenedEvent::Chat {
             environment_id,
             character_id: *character_id,
-            character_name: character_name.to_string(),
+            character_name,
             message: message.clone(),
         };
         debug!("Sending event: {:?}", event);
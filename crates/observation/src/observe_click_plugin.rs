@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use cursor_hero_agent_types::prelude::*;
+use cursor_hero_cursor_types::prelude::*;
+use cursor_hero_environment_types::environment_types::TrackedEnvironment;
+use cursor_hero_observation_types::prelude::*;
+
+pub struct ObserveClickPlugin;
+
+impl Plugin for ObserveClickPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_clicks);
+    }
+}
+
+fn observe_clicks(
+    mut click_events: EventReader<ClickEvent>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    cursor_query: Query<(&Parent, Option<&TrackedEnvironment>), With<Cursor>>,
+    character_query: Query<(Option<&Name>, Option<&AgentPersona>)>,
+    target_query: Query<&Name>,
+) {
+    for event in click_events.read() {
+        // presses and releases are implied by the click
+        let ClickEvent::Clicked {
+            target_id,
+            cursor_id,
+            way,
+            ..
+        } = event
+        else {
+            continue;
+        };
+        let Ok((cursor_parent, cursor_environment_tag)) = cursor_query.get(*cursor_id) else {
+            continue;
+        };
+        let character_id = cursor_parent.get();
+        let character_name = match character_query.get(character_id) {
+            Ok((_, Some(persona))) => persona.name.clone(),
+            Ok((Some(name), None)) => name.to_string(),
+            _ => continue,
+        };
+        let target_name = target_query
+            .get(*target_id)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| "something".to_string());
+
+        let event = SomethingObservableHappenedEvent::Click {
+            environment_id: cursor_environment_tag.map(|tag| tag.environment_id),
+            character_id,
+            character_name,
+            target_id: *target_id,
+            target_name,
+            way: format!("{:?}", way),
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
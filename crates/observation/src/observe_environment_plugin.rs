@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use cursor_hero_agent_types::prelude::*;
+use cursor_hero_cursor_types::prelude::*;
+use cursor_hero_environment_types::environment_types::TrackedEnvironment;
+use cursor_hero_observation_types::prelude::*;
+
+/// Turns changes of [`TrackedEnvironment`] into observations of things entering and leaving environments.
+pub struct ObserveEnvironmentPlugin;
+
+impl Plugin for ObserveEnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_environment_changes);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn observe_environment_changes(
+    // cursors follow their characters around, the character is observed instead
+    tracked_query: Query<
+        (
+            Entity,
+            &TrackedEnvironment,
+            Option<&Name>,
+            Option<&AgentPersona>,
+        ),
+        (Changed<TrackedEnvironment>, Without<Cursor>),
+    >,
+    mut removed: RemovedComponents<TrackedEnvironment>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    // the environment and name each thing was last seen with, names are gone once a thing is despawned
+    mut known: Local<HashMap<Entity, (Entity, String)>>,
+    mut primed: Local<bool>,
+) {
+    // everything counts as changed on the first run, things that were already there didn't just enter
+    let priming = !*primed;
+    *primed = true;
+
+    for entity_id in removed.read() {
+        let Some((environment_id, entity_name)) = known.remove(&entity_id) else {
+            continue;
+        };
+        let event = SomethingObservableHappenedEvent::EnvironmentLeft {
+            environment_id,
+            entity_id,
+            entity_name,
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+
+    for thing in tracked_query.iter() {
+        let (entity_id, tracked, name, persona) = thing;
+        let entity_name = match (persona, name) {
+            (Some(persona), _) => persona.name.clone(),
+            (None, Some(name)) => name.to_string(),
+            (None, None) => continue,
+        };
+        let previous = known.insert(entity_id, (tracked.environment_id, entity_name.clone()));
+        if priming {
+            continue;
+        }
+        match previous {
+            Some((environment_id, _)) if environment_id == tracked.environment_id => continue,
+            Some((environment_id, entity_name)) => {
+                let event = SomethingObservableHappenedEvent::EnvironmentLeft {
+                    environment_id,
+                    entity_id,
+                    entity_name,
+                };
+                debug!("Sending event: {:?}", event);
+                observation_events.send(event);
+            }
+            None => {}
+        }
+        let event = SomethingObservableHappenedEvent::EnvironmentEntered {
+            environment_id: tracked.environment_id,
+            entity_id,
+            entity_name,
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use cursor_hero_environment_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+use cursor_hero_pressure_plate::pressure_plate_plugin::PressurePlateActivationEvent;
+
+pub struct ObservePressurePlatePlugin;
+
+impl Plugin for ObservePressurePlatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_pressure_plates);
+    }
+}
+
+fn observe_pressure_plates(
+    mut activation_events: EventReader<PressurePlateActivationEvent>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    name_query: Query<&Name>,
+    parent_query: Query<&Parent>,
+    tracked_query: Query<&TrackedEnvironment>,
+    environment_query: Query<(), With<EnvironmentKind>>,
+) {
+    for event in activation_events.read() {
+        let plate_id = event.0;
+
+        // plates sit somewhere below the environment they are placed in
+        let mut current = plate_id;
+        let environment_id = loop {
+            if let Ok(tracked) = tracked_query.get(current) {
+                break Some(tracked.environment_id);
+            }
+            if environment_query.contains(current) {
+                break Some(current);
+            }
+            match parent_query.get(current) {
+                Ok(parent) => current = parent.get(),
+                Err(_) => break None,
+            }
+        };
+
+        let event = SomethingObservableHappenedEvent::PressurePlateActivated {
+            environment_id,
+            plate_id,
+            plate_name: name_query
+                .get(plate_id)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|_| "A pressure plate".to_string()),
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use cursor_hero_agent_types::prelude::*;
+use cursor_hero_environment_types::environment_types::TrackedEnvironment;
+use cursor_hero_observation_types::prelude::*;
+use cursor_hero_toolbelt_types::prelude::*;
+
+pub struct ObserveToolActivationPlugin;
+
+impl Plugin for ObserveToolActivationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_tool_activation);
+    }
+}
+
+fn observe_tool_activation(
+    mut activation_events: EventReader<ToolActivationEvent>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+    tool_query: Query<(&Name, &Parent)>,
+    toolbelt_query: Query<&Parent, With<Toolbelt>>,
+    character_query: Query<(
+        Option<&Name>,
+        Option<&AgentPersona>,
+        Option<&TrackedEnvironment>,
+    )>,
+) {
+    for event in activation_events.read() {
+        let (tool_id, active) = match event {
+            ToolActivationEvent::Activate(tool_id) => (tool_id, true),
+            ToolActivationEvent::Deactivate(tool_id) => (tool_id, false),
+        };
+        let Ok((tool_name, tool_parent)) = tool_query.get(*tool_id) else {
+            continue;
+        };
+        let Ok(toolbelt_parent) = toolbelt_query.get(tool_parent.get()) else {
+            continue;
+        };
+        let character_id = toolbelt_parent.get();
+        let Ok(character) = character_query.get(character_id) else {
+            continue;
+        };
+        let (character_name, character_persona, character_environment_tag) = character;
+        let character_name = match (character_persona, character_name) {
+            (Some(persona), _) => persona.name.clone(),
+            (None, Some(name)) => name.to_string(),
+            (None, None) => continue,
+        };
+
+        let event = SomethingObservableHappenedEvent::ToolActivated {
+            environment_id: character_environment_tag.map(|tag| tag.environment_id),
+            character_id,
+            character_name,
+            tool_name: tool_name.to_string(),
+            active,
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use bevy::window::WindowPosition;
+use cursor_hero_observation_types::prelude::*;
+use cursor_hero_window_position_types::window_position_types::WindowPositionCommand;
+
+pub struct ObserveWindowPositionPlugin;
+
+impl Plugin for ObserveWindowPositionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, observe_window_position_commands);
+    }
+}
+
+fn observe_window_position_commands(
+    mut window_commands: EventReader<WindowPositionCommand>,
+    mut observation_events: EventWriter<SomethingObservableHappenedEvent>,
+) {
+    for command in window_commands.read() {
+        let mut changes = Vec::new();
+        match command.position {
+            Some(WindowPosition::At(position)) => {
+                changes.push(format!("moved to ({}, {})", position.x, position.y))
+            }
+            Some(WindowPosition::Centered(_)) => changes.push("centered".to_string()),
+            Some(WindowPosition::Automatic) | None => {}
+        }
+        if let Some(resolution) = &command.resolution {
+            changes.push(format!(
+                "resized to {}x{}",
+                resolution.physical_width(),
+                resolution.physical_height()
+            ));
+        }
+        if let Some(mode) = command.mode {
+            changes.push(format!("switched to {:?} mode", mode));
+        }
+        if changes.is_empty() {
+            continue;
+        }
+
+        let event = SomethingObservableHappenedEvent::WindowPositioned {
+            window_id: command.window,
+            description: changes.join(", "),
+        };
+        debug!("Sending event: {:?}", event);
+        observation_events.send(event);
+    }
+}
@@ -1,8 +1,16 @@
 #![feature(trivial_bounds)]
+pub mod observation_filter_types;
+pub mod observation_history_types;
+pub mod observation_retention_types;
+pub mod observation_transcript_types;
 pub mod observation_types;
 pub mod observation_types_plugin;
 
 pub mod prelude {
+    pub use crate::observation_filter_types::*;
+    pub use crate::observation_history_types::*;
+    pub use crate::observation_retention_types::*;
+    pub use crate::observation_transcript_types::*;
     pub use crate::observation_types::*;
     pub use crate::observation_types_plugin::ObservationTypesPlugin;
 }
@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+/// Decides which observations reach the `ObservationBuffer` of the same entity, every rule has to pass.
+///
+/// Buffers without a filter use [`ObservationFilter::default`].
+/// Observations meant for one buffer, like `SomethingObservableHappenedEvent::MemoryRestored`, are not filtered.
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+pub struct ObservationFilter {
+    pub rules: Vec<ObservationRule>,
+}
+
+impl Default for ObservationFilter {
+    fn default() -> Self {
+        Self {
+            rules: vec![ObservationRule::SameEnvironment],
+        }
+    }
+}
+
+impl ObservationFilter {
+    pub fn with(mut self, rule: ObservationRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum ObservationRule {
+    /// Only observations from the observer's environment, an observer outside all environments observes every environment.
+    /// Moving the game window concerns every environment
+    SameEnvironment,
+    /// Only observations whose source is within this distance of the observer
+    Proximity { radius: f32 },
+    /// Only observations whose source isn't hidden behind a solid collider
+    LineOfSight,
+    /// Only observations caused by these characters, by name.
+    /// Observations no character caused are unaffected.
+    Characters(Vec<String>),
+}
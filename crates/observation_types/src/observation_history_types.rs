@@ -0,0 +1,500 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use bevy::prelude::*;
+use chrono::DateTime;
+use chrono::Local;
+use chrono::TimeZone;
+use rusqlite::Connection;
+use rusqlite::ToSql;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::prelude::*;
+
+#[derive(Debug, Reflect, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ObservationKind {
+    Chat,
+    MemoryRestored,
+    UISnapshot,
+    Summary,
+    EnvironmentEntered,
+    EnvironmentLeft,
+    ToolActivated,
+    Click,
+    PressurePlateActivated,
+    WindowPositioned,
+    CalculatorResult,
+}
+
+impl ObservationKind {
+    pub const ALL: [ObservationKind; 11] = [
+        ObservationKind::Chat,
+        ObservationKind::MemoryRestored,
+        ObservationKind::UISnapshot,
+        ObservationKind::Summary,
+        ObservationKind::EnvironmentEntered,
+        ObservationKind::EnvironmentLeft,
+        ObservationKind::ToolActivated,
+        ObservationKind::Click,
+        ObservationKind::PressurePlateActivated,
+        ObservationKind::WindowPositioned,
+        ObservationKind::CalculatorResult,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObservationKind::Chat => "chat",
+            ObservationKind::MemoryRestored => "memory_restored",
+            ObservationKind::UISnapshot => "ui_snapshot",
+            ObservationKind::Summary => "summary",
+            ObservationKind::EnvironmentEntered => "environment_entered",
+            ObservationKind::EnvironmentLeft => "environment_left",
+            ObservationKind::ToolActivated => "tool_activated",
+            ObservationKind::Click => "click",
+            ObservationKind::PressurePlateActivated => "pressure_plate_activated",
+            ObservationKind::WindowPositioned => "window_positioned",
+            ObservationKind::CalculatorResult => "calculator_result",
+        }
+    }
+}
+
+impl SomethingObservableHappenedEvent {
+    pub fn kind(&self) -> ObservationKind {
+        match self {
+            SomethingObservableHappenedEvent::Chat { .. } => ObservationKind::Chat,
+            SomethingObservableHappenedEvent::MemoryRestored { .. } => {
+                ObservationKind::MemoryRestored
+            }
+            SomethingObservableHappenedEvent::UISnapshot { .. } => ObservationKind::UISnapshot,
+            SomethingObservableHappenedEvent::Summary { .. } => ObservationKind::Summary,
+            SomethingObservableHappenedEvent::EnvironmentEntered { .. } => {
+                ObservationKind::EnvironmentEntered
+            }
+            SomethingObservableHappenedEvent::EnvironmentLeft { .. } => {
+                ObservationKind::EnvironmentLeft
+            }
+            SomethingObservableHappenedEvent::ToolActivated { .. } => {
+                ObservationKind::ToolActivated
+            }
+            SomethingObservableHappenedEvent::Click { .. } => ObservationKind::Click,
+            SomethingObservableHappenedEvent::PressurePlateActivated { .. } => {
+                ObservationKind::PressurePlateActivated
+            }
+            SomethingObservableHappenedEvent::WindowPositioned { .. } => {
+                ObservationKind::WindowPositioned
+            }
+            SomethingObservableHappenedEvent::CalculatorResult { .. } => {
+                ObservationKind::CalculatorResult
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ObservationHistoryError {
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+    InvalidDatetime(i64),
+}
+
+/// Filters for [`ObservationHistory::query`], unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObservationHistoryQuery {
+    pub observer: Option<String>,
+    pub since: Option<DateTime<Local>>,
+    /// Exclusive
+    pub until: Option<DateTime<Local>>,
+    /// Empty matches every kind
+    pub kinds: Vec<ObservationKind>,
+    /// Case-insensitive substring of the observation text
+    pub text: Option<String>,
+    /// Only the newest matches are returned when more match, 0 returns all of them
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservationHistoryRow {
+    pub observer: String,
+    pub entry: ObservationBufferEntry,
+}
+
+/// Every observation ever made, appended to a SQLite database as it is observed.
+///
+/// One connection is kept open in WAL mode, [`ObservationHistory::close`] releases it so that the file can be swapped out by backups and save slots.
+#[derive(Resource)]
+pub struct ObservationHistory {
+    pub path: PathBuf,
+    /// Opened on first use
+    connection: Mutex<Option<Connection>>,
+    /// Entries from [`ObservationHistory::queue`] waiting for [`ObservationHistory::flush`]
+    pending: Mutex<Vec<(String, ObservationBufferEntry)>>,
+}
+
+impl std::fmt::Debug for ObservationHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObservationHistory")
+            .field("path", &self.path)
+            .field("pending", &self.pending.lock().map(|pending| pending.len()))
+            .finish()
+    }
+}
+
+impl ObservationHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            connection: Mutex::new(None),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn with_connection<R>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<R, ObservationHistoryError>,
+    ) -> Result<R, ObservationHistoryError> {
+        // a panic while holding the lock leaves nothing half-written, sqlite rolls back
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if connection.is_none() {
+            *connection = Some(self.open()?);
+        }
+        f(connection.as_mut().expect("connection was just opened"))
+    }
+
+    fn open(&self) -> Result<Connection, ObservationHistoryError> {
+        if let Some(parent) = self.path.parent() {
+            // a missing directory surfaces as a failure to open below
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let connection = Connection::open(&self.path).map_err(ObservationHistoryError::Sqlite)?;
+        connection
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                CREATE TABLE IF NOT EXISTS observations (
+                    id INTEGER PRIMARY KEY,
+                    observer TEXT NOT NULL,
+                    datetime INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    text TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    UNIQUE (observer, datetime, payload)
+                );
+                CREATE INDEX IF NOT EXISTS observations_by_observer
+                    ON observations (observer, datetime);",
+            )
+            .map_err(ObservationHistoryError::Sqlite)?;
+        Ok(connection)
+    }
+
+    /// Returns how many entries were new, entries that were already stored are skipped
+    pub fn append<'a>(
+        &self,
+        observer: &str,
+        entries: impl IntoIterator<Item = &'a ObservationBufferEntry>,
+    ) -> Result<usize, ObservationHistoryError> {
+        self.with_connection(|connection| {
+            insert(
+                connection,
+                entries.into_iter().map(|entry| (observer, entry)),
+            )
+        })
+    }
+
+    /// Like [`ObservationHistory::append`], but the entries are written together by the next [`ObservationHistory::flush`]
+    pub fn queue<'a>(
+        &self,
+        observer: &str,
+        entries: impl IntoIterator<Item = &'a ObservationBufferEntry>,
+    ) {
+        let mut pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        pending.extend(
+            entries
+                .into_iter()
+                .map(|entry| (observer.to_string(), entry.clone())),
+        );
+    }
+
+    /// Writes the queued entries in one transaction, returns how many were new.
+    /// Failed entries are dropped rather than retried every flush
+    pub fn flush(&self) -> Result<usize, ObservationHistoryError> {
+        let pending = std::mem::take(
+            &mut *self
+                .pending
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        self.with_connection(|connection| {
+            insert(
+                connection,
+                pending
+                    .iter()
+                    .map(|(observer, entry)| (observer.as_str(), entry)),
+            )
+        })
+    }
+
+    /// Flushes and closes the connection, the next call opens it again
+    pub fn close(&self) -> Result<(), ObservationHistoryError> {
+        self.flush()?;
+        let connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        match connection {
+            Some(connection) => connection
+                .close()
+                .map_err(|(_, e)| ObservationHistoryError::Sqlite(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Matching rows, oldest first, queued entries included
+    pub fn query(
+        &self,
+        query: &ObservationHistoryQuery,
+    ) -> Result<Vec<ObservationHistoryRow>, ObservationHistoryError> {
+        self.flush()?;
+        let mut sql =
+            "SELECT observer, datetime, payload FROM observations WHERE 1 = 1".to_string();
+        let mut params: Vec<Box<dyn ToSql>> = Vec::new();
+        if let Some(observer) = &query.observer {
+            sql.push_str(" AND observer = ?");
+            params.push(Box::new(observer.clone()));
+        }
+        if let Some(since) = query.since {
+            sql.push_str(" AND datetime >= ?");
+            params.push(Box::new(since.timestamp_millis()));
+        }
+        if let Some(until) = query.until {
+            sql.push_str(" AND datetime < ?");
+            params.push(Box::new(until.timestamp_millis()));
+        }
+        if !query.kinds.is_empty() {
+            let placeholders = vec!["?"; query.kinds.len()].join(", ");
+            sql.push_str(&format!(" AND kind IN ({})", placeholders));
+            for kind in query.kinds.iter() {
+                params.push(Box::new(kind.as_str()));
+            }
+        }
+        if let Some(text) = query.text.as_ref().filter(|text| !text.is_empty()) {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            sql.push_str(" AND text LIKE ? ESCAPE '\\'");
+            params.push(Box::new(format!("%{}%", escaped)));
+        }
+        sql.push_str(" ORDER BY datetime DESC, id DESC");
+        if query.limit > 0 {
+            sql.push_str(" LIMIT ?");
+            params.push(Box::new(query.limit as i64));
+        }
+
+        let rows = self.with_connection(|connection| {
+            let mut statement = connection
+                .prepare(&sql)
+                .map_err(ObservationHistoryError::Sqlite)?;
+            let rows = statement
+                .query_map(
+                    rusqlite::params_from_iter(params.iter().map(|param| param.as_ref())),
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, String>(2)?,
+                        ))
+                    },
+                )
+                .map_err(ObservationHistoryError::Sqlite)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(ObservationHistoryError::Sqlite)?;
+            Ok(rows)
+        })?;
+
+        let mut results = Vec::new();
+        for (observer, datetime, payload) in rows {
+            let datetime = Local
+                .timestamp_millis_opt(datetime)
+                .single()
+                .ok_or(ObservationHistoryError::InvalidDatetime(datetime))?;
+            let origin = serde_json::from_str(&payload).map_err(ObservationHistoryError::Json)?;
+            results.push(ObservationHistoryRow {
+                observer,
+                entry: ObservationBufferEntry { datetime, origin },
+            });
+        }
+        results.reverse();
+        Ok(results)
+    }
+
+    pub fn len(&self) -> Result<usize, ObservationHistoryError> {
+        self.flush()?;
+        let count: i64 = self.with_connection(|connection| {
+            connection
+                .query_row("SELECT COUNT(*) FROM observations", [], |row| row.get(0))
+                .map_err(ObservationHistoryError::Sqlite)
+        })?;
+        Ok(count as usize)
+    }
+
+    pub fn observers(&self) -> Result<Vec<String>, ObservationHistoryError> {
+        self.flush()?;
+        self.with_connection(|connection| {
+            let mut statement = connection
+                .prepare_cached("SELECT DISTINCT observer FROM observations ORDER BY observer")
+                .map_err(ObservationHistoryError::Sqlite)?;
+            let observers = statement
+                .query_map([], |row| row.get(0))
+                .map_err(ObservationHistoryError::Sqlite)?
+                .collect::<Result<Vec<String>, _>>()
+                .map_err(ObservationHistoryError::Sqlite)?;
+            Ok(observers)
+        })
+    }
+}
+
+fn insert<'a>(
+    connection: &mut Connection,
+    rows: impl IntoIterator<Item = (&'a str, &'a ObservationBufferEntry)>,
+) -> Result<usize, ObservationHistoryError> {
+    let transaction = connection
+        .transaction()
+        .map_err(ObservationHistoryError::Sqlite)?;
+    let mut inserted = 0;
+    {
+        let mut statement = transaction
+            .prepare_cached(
+                "INSERT OR IGNORE INTO observations (observer, datetime, kind, text, payload)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(ObservationHistoryError::Sqlite)?;
+        for (observer, entry) in rows {
+            let payload =
+                serde_json::to_string(&entry.origin).map_err(ObservationHistoryError::Json)?;
+            inserted += statement
+                .execute(rusqlite::params![
+                    observer,
+                    entry.datetime.timestamp_millis(),
+                    entry.origin.kind().as_str(),
+                    entry.origin.to_string(),
+                    payload,
+                ])
+                .map_err(ObservationHistoryError::Sqlite)?;
+        }
+    }
+    transaction
+        .commit()
+        .map_err(ObservationHistoryError::Sqlite)?;
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The history keeps millisecond precision
+    fn minutes_ago(minutes: i64) -> DateTime<Local> {
+        let datetime = Local::now() - chrono::Duration::minutes(minutes);
+        Local
+            .timestamp_millis_opt(datetime.timestamp_millis())
+            .unwrap()
+    }
+
+    fn chat(minutes: i64, message: &str) -> ObservationBufferEntry {
+        ObservationBufferEntry {
+            datetime: minutes_ago(minutes),
+            origin: SomethingObservableHappenedEvent::Chat {
+                environment_id: None,
+                character_id: Entity::from_raw(1),
+                character_name: "Tume Eena".to_string(),
+                message: message.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn append_and_query() {
+        let path = std::env::temp_dir().join(format!(
+            "cursor_hero_observation_history_test_{}.sqlite",
+            std::process::id()
+        ));
+        let history = ObservationHistory::new(&path);
+        let restored = ObservationBufferEntry {
+            datetime: minutes_ago(20),
+            origin: SomethingObservableHappenedEvent::MemoryRestored {
+                observation_buffer_id: Entity::from_raw(2),
+            },
+        };
+        let entries = vec![
+            restored,
+            chat(10, "Hello there"),
+            chat(5, "Build 100% of the bricks"),
+        ];
+        assert_eq!(history.append("Ithia Tig", &entries).unwrap(), 3);
+        // already stored
+        assert_eq!(history.append("Ithia Tig", &entries[1..]).unwrap(), 0);
+        assert_eq!(history.append("Oro Vell", &entries[1..2]).unwrap(), 1);
+        assert_eq!(history.len().unwrap(), 4);
+
+        // queued entries are written together and visible to queries
+        let queued = chat(1, "Queued");
+        history.queue("Oro Vell", [&queued]);
+        history.queue("Oro Vell", [&queued]);
+        assert_eq!(history.len().unwrap(), 5);
+        assert_eq!(history.flush().unwrap(), 0);
+
+        let query = |query: ObservationHistoryQuery| {
+            history
+                .query(&query)
+                .unwrap()
+                .into_iter()
+                .map(|row| row.entry)
+                .collect::<Vec<_>>()
+        };
+        let ithia = ObservationHistoryQuery {
+            observer: Some("Ithia Tig".to_string()),
+            ..default()
+        };
+        assert_eq!(query(ithia.clone()), entries);
+        assert_eq!(
+            query(ObservationHistoryQuery {
+                kinds: vec![ObservationKind::Chat],
+                limit: 1,
+                ..ithia.clone()
+            }),
+            entries[2..]
+        );
+        assert_eq!(
+            query(ObservationHistoryQuery {
+                text: Some("100%".to_string()),
+                ..default()
+            }),
+            entries[2..]
+        );
+        assert_eq!(
+            query(ObservationHistoryQuery {
+                since: Some(minutes_ago(15)),
+                until: Some(minutes_ago(7)),
+                ..ithia
+            }),
+            entries[1..2]
+        );
+
+        history.close().unwrap();
+        assert!(!PathBuf::from(format!("{}-wal", path.display())).exists());
+        // reopened on use
+        assert_eq!(history.observers().unwrap(), vec!["Ithia Tig", "Oro Vell"]);
+        history.close().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use chrono::DateTime;
+use chrono::Local;
+
+use crate::prelude::*;
+
+/// How much of its past an [`ObservationBuffer`] holds on to.
+///
+/// Kind limits always drop entries, the entries past `max_entries` or `max_age` are summarized instead when `summarize` is set.
+/// Dropped entries remain in the [`ObservationHistory`].
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+pub struct ObservationRetention {
+    pub max_entries: Option<usize>,
+    pub max_age: Option<Duration>,
+    /// Only this many of the newest entries of a kind are kept
+    pub kind_limits: HashMap<ObservationKind, usize>,
+    /// Replace expired entries with a [`SomethingObservableHappenedEvent::Summary`] instead of dropping them
+    pub summarize: bool,
+    /// Expired entries wait until there are this many to summarize them together
+    pub min_summary_run: usize,
+    /// Entries past this many are dropped even while a summary is pending, so a slow summary can't grow the buffer without bound
+    pub hard_max_entries: Option<usize>,
+}
+
+impl Default for ObservationRetention {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(200),
+            max_age: None,
+            // only the latest screen matters
+            kind_limits: [(ObservationKind::UISnapshot, 1)].into_iter().collect(),
+            summarize: true,
+            min_summary_run: 20,
+            hard_max_entries: Some(400),
+        }
+    }
+}
+
+/// Positions in a buffer that a retention pass removes, ascending
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPlan {
+    pub evict: Vec<usize>,
+    /// A run of the oldest kept entries to replace with a summary
+    pub summarize: Vec<usize>,
+    /// The oldest kept entries past [`ObservationRetention::hard_max_entries`], to evict while a summary is pending
+    pub overflow: Vec<usize>,
+}
+
+impl RetentionPlan {
+    pub fn is_empty(&self) -> bool {
+        self.evict.is_empty() && self.summarize.is_empty() && self.overflow.is_empty()
+    }
+}
+
+impl ObservationRetention {
+    pub fn plan(&self, entries: &[ObservationBufferEntry], now: DateTime<Local>) -> RetentionPlan {
+        let mut seen = HashMap::<ObservationKind, usize>::default();
+        let mut evict = Vec::new();
+        let mut kept = Vec::new();
+        for (index, entry) in entries.iter().enumerate().rev() {
+            let kind = entry.origin.kind();
+            let count = seen.entry(kind).or_default();
+            *count += 1;
+            match self.kind_limits.get(&kind) {
+                Some(limit) if *count > *limit => evict.push(index),
+                _ => kept.push(index),
+            }
+        }
+        evict.reverse();
+        kept.reverse();
+
+        let overflow = self
+            .max_entries
+            .map(|max_entries| kept.len().saturating_sub(max_entries))
+            .unwrap_or_default();
+        let too_old = match self
+            .max_age
+            .and_then(|max_age| chrono::Duration::from_std(max_age).ok())
+        {
+            Some(max_age) => kept
+                .iter()
+                .take_while(|index| entries[**index].datetime < now - max_age)
+                .count(),
+            None => 0,
+        };
+        let hard_overflow = self
+            .hard_max_entries
+            .map(|hard_max_entries| kept.len().saturating_sub(hard_max_entries))
+            .unwrap_or_default();
+        let expired = kept
+            .iter()
+            .copied()
+            .take(overflow.max(too_old))
+            .collect::<Vec<_>>();
+
+        if !self.summarize {
+            evict.extend(expired);
+            evict.sort();
+            return RetentionPlan { evict, ..default() };
+        }
+        RetentionPlan {
+            evict,
+            summarize: match expired.len() >= self.min_summary_run.max(1) {
+                true => expired,
+                false => Vec::new(),
+            },
+            overflow: kept.into_iter().take(hard_overflow).collect(),
+        }
+    }
+}
+
+/// A summary being generated for a run of entries, lives on the entity used as the inference session
+#[derive(Component, Reflect, Debug, Clone, PartialEq)]
+pub struct ObservationSummarySession {
+    pub observation_buffer_id: Entity,
+    /// The entries being summarized, oldest first
+    #[reflect(ignore)]
+    pub run: Vec<ObservationBufferEntry>,
+}
+
+impl ObservationSummarySession {
+    /// Removes the entries of the run that are still in the buffer, returning the position of the first and the removed entries.
+    ///
+    /// Entries are matched whole and in order, so newer entries sharing a datetime stay
+    pub fn take_run(
+        &self,
+        observations: &mut Vec<ObservationBufferEntry>,
+    ) -> (Option<usize>, Vec<ObservationBufferEntry>) {
+        let mut next = 0;
+        let mut position = None;
+        let mut removed = Vec::new();
+        let mut index = 0;
+        observations.retain(|entry| {
+            // entries of the run may have been evicted meanwhile
+            let found = self.run[next..]
+                .iter()
+                .position(|candidate| candidate == entry);
+            let keep = match found {
+                Some(offset) => {
+                    next += offset + 1;
+                    position.get_or_insert(index);
+                    removed.push(entry.clone());
+                    false
+                }
+                None => true,
+            };
+            index += 1;
+            keep
+        });
+        (position, removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cursor_hero_ui_automation_types::prelude::UiSnapshot;
+
+    fn entry(minutes_ago: i64, origin: SomethingObservableHappenedEvent) -> ObservationBufferEntry {
+        ObservationBufferEntry {
+            datetime: Local::now() - chrono::Duration::minutes(minutes_ago),
+            origin,
+        }
+    }
+
+    fn chat(minutes_ago: i64) -> ObservationBufferEntry {
+        entry(
+            minutes_ago,
+            SomethingObservableHappenedEvent::Chat {
+                environment_id: None,
+                character_id: Entity::from_raw(1),
+                character_name: "Tume Eena".to_string(),
+                message: format!("{} minutes ago", minutes_ago),
+            },
+        )
+    }
+
+    fn snapshot(minutes_ago: i64) -> ObservationBufferEntry {
+        entry(
+            minutes_ago,
+            SomethingObservableHappenedEvent::UISnapshot {
+                environment_id: None,
+                snapshot: UiSnapshot {
+                    app_windows: Vec::new(),
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn retention_plans() {
+        let entries = vec![chat(50), snapshot(40), chat(30), snapshot(20), chat(10)];
+        let retention = ObservationRetention {
+            max_entries: Some(2),
+            summarize: false,
+            ..default()
+        };
+        assert_eq!(
+            retention.plan(&entries, Local::now()),
+            RetentionPlan {
+                evict: vec![0, 1, 2],
+                ..default()
+            }
+        );
+
+        let retention = ObservationRetention {
+            max_entries: None,
+            max_age: Some(Duration::from_secs(60 * 45)),
+            min_summary_run: 2,
+            ..default()
+        };
+        // a single expired entry waits for more
+        assert!(retention.plan(&entries, Local::now()).summarize.is_empty());
+        assert_eq!(
+            retention.plan(&entries, Local::now() + chrono::Duration::minutes(20)),
+            RetentionPlan {
+                evict: vec![1],
+                summarize: vec![0, 2],
+                overflow: vec![],
+            }
+        );
+
+        // a pending summary doesn't hold back the hard limit
+        let retention = ObservationRetention {
+            max_entries: Some(1),
+            hard_max_entries: Some(2),
+            min_summary_run: 1,
+            kind_limits: HashMap::default(),
+            ..default()
+        };
+        assert_eq!(
+            retention.plan(&entries, Local::now()),
+            RetentionPlan {
+                evict: vec![],
+                summarize: vec![0, 1, 2, 3],
+                overflow: vec![0, 1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn summary_runs_are_taken_whole() {
+        let run = vec![chat(30), chat(20), chat(10)];
+        let session = ObservationSummarySession {
+            observation_buffer_id: Entity::from_raw(1),
+            run: run.clone(),
+        };
+        let mut sharing_datetime = run[2].clone();
+        sharing_datetime.origin = snapshot(0).origin;
+        // the first entry of the run was evicted while the summary was pending
+        let mut observations = vec![run[1].clone(), run[2].clone(), sharing_datetime.clone()];
+        let (position, removed) = session.take_run(&mut observations);
+        assert_eq!(position, Some(0));
+        assert_eq!(removed, run[1..]);
+        assert_eq!(observations, vec![sharing_datetime]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+/// Markdown transcripts carry each entry as JSON in a comment like this so they can be imported again
+const MARKDOWN_ENTRY_PREFIX: &str = "<!-- entry: ";
+const MARKDOWN_ENTRY_SUFFIX: &str = " -->";
+
+#[derive(Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    /// One [`ObservationBufferEntry`] per line
+    JsonLines,
+    /// Readable by people, the entries are embedded as comments
+    Markdown,
+}
+
+impl TranscriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::JsonLines => "jsonl",
+            TranscriptFormat::Markdown => "md",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "jsonl" => Some(TranscriptFormat::JsonLines),
+            "md" => Some(TranscriptFormat::Markdown),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Event, Reflect, Debug, Clone)]
+pub enum ObservationTranscriptEvent {
+    /// Writes the observation buffer of the agent with this `Name` to the transcripts dir
+    Export {
+        agent_name: String,
+        format: TranscriptFormat,
+    },
+    /// Seeds the agent with this `Name` with the entries of a transcript.
+    ///
+    /// The entries go into the [`ObservationHistory`], so an agent spawned under the name later starts with them.
+    /// An agent with that name that is already around must not have chatted yet.
+    Import { agent_name: String, path: PathBuf },
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum TranscriptError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// Transcripts end in `.jsonl` or `.md`
+    UnknownFormat(PathBuf),
+    UnknownAgent(String),
+    /// Imports only seed agents that haven't chatted yet
+    AgentNotFresh(String),
+    History(ObservationHistoryError),
+}
+
+/// File name of an agent's transcript, characters Windows doesn't allow in file names are replaced
+pub fn transcript_file_name(agent_name: &str, format: TranscriptFormat) -> String {
+    let stem = agent_name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    format!("{}.{}", stem, format.extension())
+}
+
+/// Transcripts don't hold on to the agent's entity, references to it are written as [`Entity::PLACEHOLDER`]
+pub fn encode_transcript(
+    agent_name: &str,
+    agent_id: Entity,
+    entries: &[ObservationBufferEntry],
+    format: TranscriptFormat,
+) -> Result<String, serde_json::Error> {
+    let mut transcript = String::new();
+    if format == TranscriptFormat::Markdown {
+        transcript.push_str(&format!("# Observations of {}\n\n", agent_name));
+    }
+    for entry in entries {
+        let mut entry = entry.clone();
+        entry.origin.remap_entity(agent_id, Entity::PLACEHOLDER);
+        let json = serde_json::to_string(&entry)?;
+        match format {
+            TranscriptFormat::JsonLines => {
+                transcript.push_str(&json);
+                transcript.push('\n');
+            }
+            TranscriptFormat::Markdown => {
+                transcript.push_str(&format!(
+                    "- {} {}\n  {}{}{}\n",
+                    entry.datetime.format("%Y-%m-%d %H:%M:%S"),
+                    entry.origin.to_string().replace('\n', " "),
+                    MARKDOWN_ENTRY_PREFIX,
+                    json,
+                    MARKDOWN_ENTRY_SUFFIX
+                ));
+            }
+        }
+    }
+    Ok(transcript)
+}
+
+/// The entries of a transcript, oldest first, as observed by the agent with `agent_id`
+pub fn decode_transcript(
+    transcript: &str,
+    agent_id: Entity,
+    format: TranscriptFormat,
+) -> Result<Vec<ObservationBufferEntry>, serde_json::Error> {
+    let mut entries = Vec::new();
+    for line in transcript.lines() {
+        let line = line.trim();
+        let json = match format {
+            TranscriptFormat::JsonLines if !line.is_empty() => line,
+            TranscriptFormat::Markdown => match line
+                .strip_prefix(MARKDOWN_ENTRY_PREFIX)
+                .and_then(|line| line.strip_suffix(MARKDOWN_ENTRY_SUFFIX))
+            {
+                Some(json) => json,
+                None => continue,
+            },
+            _ => continue,
+        };
+        let mut entry: ObservationBufferEntry = serde_json::from_str(json)?;
+        entry.origin.remap_entity(Entity::PLACEHOLDER, agent_id);
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry| entry.datetime);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcripts_round_trip() {
+        let agent_id = Entity::from_raw(1);
+        let entries = vec![
+            ObservationBufferEntry {
+                datetime: chrono::Local::now() - chrono::Duration::minutes(1),
+                origin: SomethingObservableHappenedEvent::Chat {
+                    environment_id: None,
+                    character_id: Entity::from_raw(2),
+                    character_name: "Tume Eena".to_string(),
+                    message: "Hello\nthere".to_string(),
+                },
+            },
+            ObservationBufferEntry {
+                datetime: chrono::Local::now(),
+                origin: SomethingObservableHappenedEvent::Chat {
+                    environment_id: None,
+                    character_id: agent_id,
+                    character_name: "Ithia Tig".to_string(),
+                    message: "Hi!".to_string(),
+                },
+            },
+        ];
+        let new_agent_id = Entity::from_raw(3);
+        for format in [TranscriptFormat::JsonLines, TranscriptFormat::Markdown] {
+            let transcript = encode_transcript("Ithia Tig", agent_id, &entries, format).unwrap();
+            let decoded = decode_transcript(&transcript, new_agent_id, format).unwrap();
+            assert_eq!(decoded.len(), 2);
+            assert_eq!(decoded[0], entries[0]);
+            assert!(matches!(
+                decoded[1].origin,
+                SomethingObservableHappenedEvent::Chat { character_id, .. } if character_id == new_agent_id
+            ));
+        }
+        assert_eq!(
+            transcript_file_name("Character - (Agent) Ithia: Tig", TranscriptFormat::Markdown),
+            "Character - (Agent) Ithia_ Tig.md"
+        );
+    }
+}
@@ -13,13 +13,65 @@ use serde::Serialize;
 pub struct ObservationTool {
     #[reflect(ignore)]
     pub last_inference: Option<DateTime<Local>>,
+    /// Restored into `last_inference` when the latest request fails so its observations are considered again
+    #[reflect(ignore)]
+    pub previous_inference: Option<DateTime<Local>>,
+    #[reflect(ignore)]
+    pub retry_after: Option<DateTime<Local>>,
+    pub failed_attempts: u32,
+    /// A request was sent and neither its response nor its failure has arrived yet
+    pub awaiting_response: bool,
     pub _whats_new: Option<WhatsNew>, // latest value for visual inspection
 }
 
+impl ObservationTool {
+    /// Doubles with every consecutive failure, up to five minutes
+    pub fn retry_delay(&self) -> Duration {
+        let exponent = self.failed_attempts.saturating_sub(1).min(6);
+        Duration::from_secs(5 * 2u64.pow(exponent)).min(Duration::from_secs(60 * 5))
+    }
+}
+
+/// How the latest observation prompt of an agent fit into the model's context window
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ObservationBudget {
+    pub context_tokens: usize,
+    pub used_tokens: usize,
+    /// Observations sent in full
+    pub included: usize,
+    /// Observations cut short to save space
+    pub truncated: usize,
+    /// Observations only mentioned in the summary line
+    pub omitted: usize,
+}
+
+impl ObservationBudget {
+    pub fn headroom(&self) -> usize {
+        self.context_tokens.saturating_sub(self.used_tokens)
+    }
+
+    pub fn headroom_fraction(&self) -> f32 {
+        if self.context_tokens == 0 {
+            return 0.0;
+        }
+        self.headroom() as f32 / self.context_tokens as f32
+    }
+}
+
 #[derive(Debug, Reflect, PartialEq, Eq, Clone, Copy)]
 pub enum WhatsNew {
     Nothing,
+    /// Someone else clicked something
+    Clicked,
+    ToolActivated,
+    WindowPositioned,
+    /// Something entered or left the environment
+    EnvironmentChanged,
+    PressurePlateActivated,
+    CalculatorResult,
     SelfChat,
+    /// A chat between others, neither addressed to the observer nor to nobody in particular
+    ChatOverheard,
     ChatReceived,
     ChatReceivedButTheyProbablyStillThinking,
     MemoryRestored,
@@ -35,6 +87,13 @@ impl WhatsNew {
         match self {
             WhatsNew::SelfChat => Duration::from_secs(60),
             WhatsNew::Nothing => Duration::MAX,
+            WhatsNew::Clicked => Duration::from_secs(90),
+            WhatsNew::ToolActivated => Duration::from_secs(90),
+            WhatsNew::WindowPositioned => Duration::from_secs(60),
+            WhatsNew::EnvironmentChanged => Duration::from_secs(30),
+            WhatsNew::PressurePlateActivated => Duration::from_secs(10),
+            WhatsNew::CalculatorResult => Duration::from_secs(10),
+            WhatsNew::ChatOverheard => Duration::from_secs(45),
             WhatsNew::ChatReceived => Duration::ZERO,
             WhatsNew::ChatReceivedButTheyProbablyStillThinking => Duration::from_secs(25),
             WhatsNew::MemoryRestored => Duration::from_secs(5),
@@ -100,9 +159,55 @@ pub enum SomethingObservableHappenedEvent {
         environment_id: Option<Entity>,
         snapshot: UiSnapshot,
     },
-    // BrickEnteredEnvironment {
-
-    // }
+    /// Stands in for a run of older entries of a buffer, see [`ObservationRetention`]
+    Summary {
+        observation_buffer_id: Entity,
+        summarized: usize,
+        summary: String,
+    },
+    EnvironmentEntered {
+        environment_id: Entity,
+        entity_id: Entity,
+        entity_name: String,
+    },
+    EnvironmentLeft {
+        environment_id: Entity,
+        entity_id: Entity,
+        entity_name: String,
+    },
+    ToolActivated {
+        environment_id: Option<Entity>,
+        character_id: Entity,
+        character_name: String,
+        tool_name: String,
+        /// False when the tool was deactivated
+        active: bool,
+    },
+    Click {
+        environment_id: Option<Entity>,
+        character_id: Entity,
+        character_name: String,
+        target_id: Entity,
+        target_name: String,
+        /// Which button, e.g. `Left`
+        way: String,
+    },
+    PressurePlateActivated {
+        environment_id: Option<Entity>,
+        plate_id: Entity,
+        plate_name: String,
+    },
+    WindowPositioned {
+        window_id: Entity,
+        /// What changed, e.g. `moved to (0, 0), resized to 1920x1080`
+        description: String,
+    },
+    CalculatorResult {
+        environment_id: Option<Entity>,
+        calculator_id: Entity,
+        expression: String,
+        value: String,
+    },
 }
 impl Display for SomethingObservableHappenedEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -123,6 +228,62 @@ impl Display for SomethingObservableHappenedEvent {
             SomethingObservableHappenedEvent::UISnapshot { snapshot, .. } => {
                 write!(f, "Snapshot with {} windows", snapshot.app_windows.len())
             }
+            SomethingObservableHappenedEvent::Summary {
+                summarized,
+                summary,
+                ..
+            } => {
+                write!(
+                    f,
+                    "Summary of {} earlier observations: {}",
+                    summarized, summary
+                )
+            }
+            SomethingObservableHappenedEvent::EnvironmentEntered { entity_name, .. } => {
+                write!(f, "{} entered the environment.", entity_name)
+            }
+            SomethingObservableHappenedEvent::EnvironmentLeft { entity_name, .. } => {
+                write!(f, "{} left the environment.", entity_name)
+            }
+            SomethingObservableHappenedEvent::ToolActivated {
+                character_name,
+                tool_name,
+                active,
+                ..
+            } => match active {
+                true => write!(f, "{} activated the {} tool.", character_name, tool_name),
+                false => write!(f, "{} deactivated the {} tool.", character_name, tool_name),
+            },
+            SomethingObservableHappenedEvent::Click {
+                character_name,
+                target_name,
+                way,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} {} clicked {}.",
+                    character_name,
+                    way.to_lowercase(),
+                    target_name
+                )
+            }
+            SomethingObservableHappenedEvent::PressurePlateActivated { plate_name, .. } => {
+                write!(f, "{} was activated.", plate_name)
+            }
+            SomethingObservableHappenedEvent::WindowPositioned { description, .. } => {
+                write!(f, "The game window was {}.", description)
+            }
+            SomethingObservableHappenedEvent::CalculatorResult {
+                expression, value, ..
+            } => {
+                // the calculator shows the expression with its equals sign
+                let expression = expression.trim_end().trim_end_matches('=').trim_end();
+                match value.trim() {
+                    "" => write!(f, "A calculator was asked to compute {}.", expression),
+                    value => write!(f, "A calculator computed {} = {}.", expression, value),
+                }
+            }
         }
     }
 }
@@ -144,6 +305,343 @@ impl SomethingObservableHappenedEvent {
             SomethingObservableHappenedEvent::Chat { .. } => WhatsNew::ChatReceived,
             SomethingObservableHappenedEvent::MemoryRestored { .. } => WhatsNew::MemoryRestored,
             SomethingObservableHappenedEvent::UISnapshot { .. } => WhatsNew::UISnapshot,
+            // only replaces what was already observed
+            SomethingObservableHappenedEvent::Summary { .. } => WhatsNew::Nothing,
+            // the observer's own doings are nothing new to it
+            SomethingObservableHappenedEvent::EnvironmentEntered { entity_id, .. }
+            | SomethingObservableHappenedEvent::EnvironmentLeft { entity_id, .. }
+                if *entity_id == observation_buffer_id =>
+            {
+                WhatsNew::Nothing
+            }
+            SomethingObservableHappenedEvent::EnvironmentEntered { .. }
+            | SomethingObservableHappenedEvent::EnvironmentLeft { .. } => {
+                WhatsNew::EnvironmentChanged
+            }
+            SomethingObservableHappenedEvent::ToolActivated { character_id, .. }
+            | SomethingObservableHappenedEvent::Click { character_id, .. }
+                if *character_id == observation_buffer_id =>
+            {
+                WhatsNew::Nothing
+            }
+            SomethingObservableHappenedEvent::ToolActivated { .. } => WhatsNew::ToolActivated,
+            SomethingObservableHappenedEvent::Click { .. } => WhatsNew::Clicked,
+            SomethingObservableHappenedEvent::PressurePlateActivated { .. } => {
+                WhatsNew::PressurePlateActivated
+            }
+            SomethingObservableHappenedEvent::WindowPositioned { .. } => WhatsNew::WindowPositioned,
+            SomethingObservableHappenedEvent::CalculatorResult { .. } => WhatsNew::CalculatorResult,
+        }
+    }
+
+    /// The environment the event happened in, `None` when it happened outside of all environments
+    pub fn environment_id(&self) -> Option<Entity> {
+        match self {
+            SomethingObservableHappenedEvent::Chat { environment_id, .. }
+            | SomethingObservableHappenedEvent::UISnapshot { environment_id, .. }
+            | SomethingObservableHappenedEvent::ToolActivated { environment_id, .. }
+            | SomethingObservableHappenedEvent::Click { environment_id, .. }
+            | SomethingObservableHappenedEvent::PressurePlateActivated { environment_id, .. }
+            | SomethingObservableHappenedEvent::CalculatorResult { environment_id, .. } => {
+                *environment_id
+            }
+            SomethingObservableHappenedEvent::EnvironmentEntered { environment_id, .. }
+            | SomethingObservableHappenedEvent::EnvironmentLeft { environment_id, .. } => {
+                Some(*environment_id)
+            }
+            SomethingObservableHappenedEvent::MemoryRestored { .. }
+            | SomethingObservableHappenedEvent::Summary { .. }
+            | SomethingObservableHappenedEvent::WindowPositioned { .. } => None,
+        }
+    }
+
+    /// Whether an observer in `observer_environment_id`, `None` outside of all environments, is in the same place as the event.
+    ///
+    /// The game window has no environment, moving it concerns every environment
+    pub fn shares_environment(&self, observer_environment_id: Option<Entity>) -> bool {
+        if matches!(
+            self,
+            SomethingObservableHappenedEvent::WindowPositioned { .. }
+        ) {
+            return true;
+        }
+        match (observer_environment_id, self.environment_id()) {
+            (None, _) => true,
+            (Some(observer_environment_id), Some(environment_id)) => {
+                observer_environment_id == environment_id
+            }
+            (Some(_), None) => false,
         }
     }
+
+    /// The entity the event came from, for rules that depend on where it happened
+    pub fn source_id(&self) -> Option<Entity> {
+        match self {
+            SomethingObservableHappenedEvent::Chat { character_id, .. }
+            | SomethingObservableHappenedEvent::ToolActivated { character_id, .. }
+            | SomethingObservableHappenedEvent::Click { character_id, .. } => Some(*character_id),
+            SomethingObservableHappenedEvent::EnvironmentEntered { entity_id, .. }
+            | SomethingObservableHappenedEvent::EnvironmentLeft { entity_id, .. } => {
+                Some(*entity_id)
+            }
+            SomethingObservableHappenedEvent::PressurePlateActivated { plate_id, .. } => {
+                Some(*plate_id)
+            }
+            SomethingObservableHappenedEvent::CalculatorResult { calculator_id, .. } => {
+                Some(*calculator_id)
+            }
+            SomethingObservableHappenedEvent::MemoryRestored { .. }
+            | SomethingObservableHappenedEvent::UISnapshot { .. }
+            | SomethingObservableHappenedEvent::Summary { .. }
+            | SomethingObservableHappenedEvent::WindowPositioned { .. } => None,
+        }
+    }
+
+    /// The name of the character that caused the event
+    pub fn character_name(&self) -> Option<&str> {
+        match self {
+            SomethingObservableHappenedEvent::Chat { character_name, .. }
+            | SomethingObservableHappenedEvent::ToolActivated { character_name, .. }
+            | SomethingObservableHappenedEvent::Click { character_name, .. } => {
+                Some(character_name.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// The only buffer that may observe the event, for events that concern a single buffer
+    pub fn observation_buffer_id(&self) -> Option<Entity> {
+        match self {
+            SomethingObservableHappenedEvent::MemoryRestored {
+                observation_buffer_id,
+            }
+            | SomethingObservableHappenedEvent::Summary {
+                observation_buffer_id,
+                ..
+            } => Some(*observation_buffer_id),
+            _ => None,
+        }
+    }
+
+    /// Replaces every reference to `from` with `to`, for moving observations between entities
+    pub fn remap_entity(&mut self, from: Entity, to: Entity) {
+        let remap = |entity: &mut Entity| {
+            if *entity == from {
+                *entity = to;
+            }
+        };
+        match self {
+            SomethingObservableHappenedEvent::Chat {
+                environment_id,
+                character_id,
+                ..
+            }
+            | SomethingObservableHappenedEvent::ToolActivated {
+                environment_id,
+                character_id,
+                ..
+            } => {
+                if let Some(environment_id) = environment_id {
+                    remap(environment_id);
+                }
+                remap(character_id);
+            }
+            SomethingObservableHappenedEvent::MemoryRestored {
+                observation_buffer_id,
+            }
+            | SomethingObservableHappenedEvent::Summary {
+                observation_buffer_id,
+                ..
+            } => remap(observation_buffer_id),
+            SomethingObservableHappenedEvent::UISnapshot { environment_id, .. } => {
+                if let Some(environment_id) = environment_id {
+                    remap(environment_id);
+                }
+            }
+            SomethingObservableHappenedEvent::EnvironmentEntered {
+                environment_id,
+                entity_id,
+                ..
+            }
+            | SomethingObservableHappenedEvent::EnvironmentLeft {
+                environment_id,
+                entity_id,
+                ..
+            } => {
+                remap(environment_id);
+                remap(entity_id);
+            }
+            SomethingObservableHappenedEvent::Click {
+                environment_id,
+                character_id,
+                target_id,
+                ..
+            } => {
+                if let Some(environment_id) = environment_id {
+                    remap(environment_id);
+                }
+                remap(character_id);
+                remap(target_id);
+            }
+            SomethingObservableHappenedEvent::PressurePlateActivated {
+                environment_id,
+                plate_id,
+                ..
+            } => {
+                if let Some(environment_id) = environment_id {
+                    remap(environment_id);
+                }
+                remap(plate_id);
+            }
+            SomethingObservableHappenedEvent::WindowPositioned { window_id, .. } => {
+                remap(window_id)
+            }
+            SomethingObservableHappenedEvent::CalculatorResult {
+                environment_id,
+                calculator_id,
+                ..
+            } => {
+                if let Some(environment_id) = environment_id {
+                    remap(environment_id);
+                }
+                remap(calculator_id);
+            }
+        }
+    }
+
+    /// Like [`SomethingObservableHappenedEvent::into_whats_new`], for an observer that shares its environment with other agents.
+    ///
+    /// Chats count as received when they name the observer, or when a non-agent names none of the agents.
+    pub fn into_whats_new_among(
+        &self,
+        observation_buffer_id: Entity,
+        observer_name: &str,
+        agent_names: &[String],
+    ) -> WhatsNew {
+        let whats_new = self.into_whats_new(observation_buffer_id);
+        let SomethingObservableHappenedEvent::Chat {
+            character_name,
+            message,
+            ..
+        } = self
+        else {
+            return whats_new;
+        };
+        if !matches!(
+            whats_new,
+            WhatsNew::ChatReceived | WhatsNew::ChatReceivedButTheyProbablyStillThinking
+        ) || is_addressed_to(message, observer_name)
+        {
+            return whats_new;
+        }
+        let from_agent = agent_names.iter().any(|name| name == character_name);
+        let names_another_agent = agent_names
+            .iter()
+            .filter(|name| *name != observer_name && *name != character_name)
+            .any(|name| is_addressed_to(message, name));
+        match from_agent || names_another_agent {
+            true => WhatsNew::ChatOverheard,
+            false => whats_new,
+        }
+    }
+}
+
+/// Whether the message mentions any part of the name as a whole word, ignoring case
+pub fn is_addressed_to(message: &str, name: &str) -> bool {
+    let words = message
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>();
+    name.split_whitespace()
+        .map(|part| part.to_lowercase())
+        .any(|part| words.contains(&part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat(
+        character_id: Entity,
+        character_name: &str,
+        message: &str,
+    ) -> SomethingObservableHappenedEvent {
+        SomethingObservableHappenedEvent::Chat {
+            environment_id: None,
+            character_id,
+            character_name: character_name.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn chats_among_agents() {
+        let observer = Entity::from_raw(1);
+        let other = Entity::from_raw(2);
+        let agents = vec!["Ithia Tig".to_string(), "Oro Vell".to_string()];
+        let whats_new = |event: SomethingObservableHappenedEvent| {
+            event.into_whats_new_among(observer, "Ithia Tig", &agents)
+        };
+        assert_eq!(
+            whats_new(chat(other, "Tume Eena", "Anyone around?")),
+            WhatsNew::ChatReceived
+        );
+        assert_eq!(
+            whats_new(chat(other, "Tume Eena", "What do you think, Oro?")),
+            WhatsNew::ChatOverheard
+        );
+        assert_eq!(
+            whats_new(chat(other, "Oro Vell", "I like bricks.")),
+            WhatsNew::ChatOverheard
+        );
+        assert_eq!(
+            whats_new(chat(other, "Oro Vell", "Do you like bricks, ithia?")),
+            WhatsNew::ChatReceived
+        );
+        assert_eq!(
+            whats_new(chat(observer, "Ithia Tig", "I do, Oro.")),
+            WhatsNew::SelfChat
+        );
+    }
+
+    #[test]
+    fn calculator_results_read_as_sentences() {
+        let result = |expression: &str, value: &str| {
+            SomethingObservableHappenedEvent::CalculatorResult {
+                environment_id: None,
+                calculator_id: Entity::from_raw(1),
+                expression: expression.to_string(),
+                value: value.to_string(),
+            }
+            .to_string()
+        };
+        assert_eq!(result("1+2=", "3"), "A calculator computed 1+2 = 3.");
+        assert_eq!(result("1 + 2", "3"), "A calculator computed 1 + 2 = 3.");
+        assert_eq!(result("1+2=", ""), "A calculator was asked to compute 1+2.");
+    }
+
+    #[test]
+    fn window_moves_concern_every_environment() {
+        let inside = Some(Entity::from_raw(10));
+        let elsewhere = Some(Entity::from_raw(11));
+        let window_moved = SomethingObservableHappenedEvent::WindowPositioned {
+            window_id: Entity::from_raw(1),
+            description: "moved to (0, 0)".to_string(),
+        };
+        assert!(window_moved.shares_environment(inside));
+        assert!(window_moved.shares_environment(None));
+
+        let outside_chat = chat(Entity::from_raw(2), "Tume Eena", "Anyone around?");
+        assert!(!outside_chat.shares_environment(inside));
+        let inside_chat = SomethingObservableHappenedEvent::Chat {
+            environment_id: inside,
+            character_id: Entity::from_raw(2),
+            character_name: "Tume Eena".to_string(),
+            message: "Anyone around?".to_string(),
+        };
+        assert!(inside_chat.shares_environment(inside));
+        assert!(!inside_chat.shares_environment(elsewhere));
+        assert!(inside_chat.shares_environment(None));
+    }
 }
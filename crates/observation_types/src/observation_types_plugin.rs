@@ -8,7 +8,15 @@ impl Plugin for ObservationTypesPlugin {
         app.register_type::<ObservationBuffer>();
         app.register_type::<ObservationBufferEntry>();
         app.register_type::<WhatsNew>();
+        app.register_type::<ObservationBudget>();
+        app.register_type::<ObservationKind>();
+        app.register_type::<ObservationRetention>();
+        app.register_type::<ObservationFilter>();
+        app.register_type::<ObservationRule>();
+        app.register_type::<ObservationSummarySession>();
+        app.register_type::<TranscriptFormat>();
         app.add_event::<SomethingObservableHappenedEvent>();
         app.add_event::<ObservationBufferEvent>();
+        app.add_event::<ObservationTranscriptEvent>();
     }
 }
@@ -1,6 +1,9 @@
 pub mod ollama;
 pub mod ollama_button_plugin;
 pub mod ollama_inference_plugin;
+pub mod ollama_model_list_plugin;
+pub mod ollama_model_picker_plugin;
+pub mod ollama_model_worker_plugin;
 pub mod ollama_plugin;
 pub mod ollama_status_plugin;
 pub mod ollama_status_worker_plugin;
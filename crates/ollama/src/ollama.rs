@@ -1,28 +1,135 @@
-use cursor_hero_inference_types::inference_types::TextInferenceOptions;
+use crate::ollama_inference_plugin::OLLAMA_BACKEND;
+use cursor_hero_inference_types::inference_metrics_types::TextInferenceUsage;
+use cursor_hero_inference_types::inference_types::InferenceConfig;
+use cursor_hero_inference_types::prompt_types::ChatMessage;
+use cursor_hero_inference_types::prompt_types::MaterializedTextPrompt;
+use cursor_hero_inference_types::prompt_types::TextPrompt;
+use cursor_hero_inference_types::text_inference_backend_types::TextInferenceBackendError;
+use cursor_hero_inference_types::tool_call_types::TextInferenceOutput;
+use cursor_hero_inference_types::tool_call_types::ToolCall;
+use cursor_hero_inference_types::tool_call_types::ToolDefinition;
+use cursor_hero_ollama_types::ollama_types::OllamaModel;
+use cursor_hero_ollama_types::ollama_types::OllamaModelDetails;
+use cursor_hero_ollama_types::ollama_types::OllamaPullProgress;
 use cursor_hero_ollama_types::ollama_types::OllamaStatus;
 use reqwest::Client;
 use std::error::Error;
 
 use serde::Deserialize;
 
+/// Body of `/api/generate` and `/api/chat` responses, or one line of them when `"stream": true`
 #[derive(Debug, Deserialize)]
 struct ApiResponse {
+    /// Set by `/api/generate`
+    #[serde(default)]
     response: String,
+    /// Set by `/api/chat`
+    message: Option<ApiMessage>,
+    #[serde(default)]
+    done: bool,
+    /// Only set once `done`
+    prompt_eval_count: Option<usize>,
+    eval_count: Option<usize>,
 }
 
-pub async fn generate(
-    prompt: &str,
-    options: Option<TextInferenceOptions>,
-) -> Result<String, Box<dyn Error>> {
-    let mut payload = serde_json::json!({
-        "model": "whatevs",
-        "prompt": prompt,
-        "stream": false
-    });
-    if let Some(options) = options {
-        // create empty object
-        let mut options_json = serde_json::json!({});
+#[derive(Debug, Deserialize)]
+struct ApiMessage {
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ApiToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiToolCall {
+    function: ApiFunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiFunctionCall {
+    name: String,
+    arguments: serde_json::Value,
+}
+
+impl ApiResponse {
+    fn text(&self) -> &str {
+        match &self.message {
+            Some(message) => &message.content,
+            None => &self.response,
+        }
+    }
+
+    fn usage(&self) -> Option<TextInferenceUsage> {
+        if self.prompt_eval_count.is_none() && self.eval_count.is_none() {
+            return None;
+        }
+        Some(TextInferenceUsage {
+            // omitted when the prompt was cached from a previous request
+            prompt_tokens: self.prompt_eval_count.unwrap_or_default(),
+            completion_tokens: self.eval_count.unwrap_or_default(),
+        })
+    }
+}
+
+/// Ollama has no `name` field on chat messages, so the speaker is folded into the content
+fn chat_message_json(message: &ChatMessage) -> serde_json::Value {
+    let content = match &message.name {
+        Some(name) => format!("{}: {}", name, message.content),
+        None => message.content.clone(),
+    };
+    serde_json::json!({
+        "role": message.role.as_str(),
+        "content": content,
+    })
+}
+
+/// Structured prompts go to `/api/chat`, everything else is sent pre-rendered to `/api/generate`
+fn build_request(
+    prompt: &MaterializedTextPrompt,
+    config: &InferenceConfig,
+    stream: bool,
+) -> (String, serde_json::Value) {
+    let (url, mut payload) = match &prompt.prompt {
+        TextPrompt::Messages { messages, .. } => (
+            format!("{}/api/chat", config.base_url(OLLAMA_BACKEND)),
+            serde_json::json!({
+                "model": config.model,
+                "messages": messages.iter().map(chat_message_json).collect::<Vec<_>>(),
+                "stream": stream
+            }),
+        ),
+        _ => (
+            format!("{}/api/generate", config.base_url(OLLAMA_BACKEND)),
+            serde_json::json!({
+                "model": config.model,
+                "prompt": prompt.materialized,
+                "stream": stream
+            }),
+        ),
+    };
+    if let Some(keep_alive) = &config.keep_alive {
+        payload["keep_alive"] = serde_json::json!(keep_alive);
+    }
+
+    // create empty object
+    let mut options_json = serde_json::json!({});
+
+    if let Some(temperature) = config.temperature {
+        options_json["temperature"] = serde_json::json!(temperature);
+    }
+
+    if let Some(top_p) = config.top_p {
+        options_json["top_p"] = serde_json::json!(top_p);
+    }
+
+    if let Some(seed) = config.seed {
+        options_json["seed"] = serde_json::json!(seed);
+    }
+
+    if let Some(context_length) = config.context_length {
+        options_json["num_ctx"] = serde_json::json!(context_length);
+    }
 
+    if let Some(options) = prompt.prompt.options() {
         if let Some(num_predict) = options.num_predict {
             options_json["num_predict"] = serde_json::json!(num_predict);
         }
@@ -30,37 +137,318 @@ pub async fn generate(
         if let Some(stop) = options.stop {
             options_json["stop"] = serde_json::json!(stop);
         }
+    }
 
-        payload["options"] = options_json;
+    payload["options"] = options_json;
+    (url, payload)
+}
+
+fn clean_response(text: &str) -> String {
+    text.trim_end_matches("<dummy32000>").trim().to_string()
+}
+
+pub async fn generate(
+    prompt: &MaterializedTextPrompt,
+    config: &InferenceConfig,
+) -> Result<String, TextInferenceBackendError> {
+    let (url, payload) = build_request(prompt, config, false);
+
+    let client = Client::new();
+
+    let res = client.post(url).json(&payload).send().await?;
+
+    if res.status().is_success() {
+        let api_response = res.json::<ApiResponse>().await?;
+        Ok(clean_response(api_response.text()))
+    } else {
+        let status = res.status();
+        let body = res.text().await?;
+        Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+    }
+}
+
+/// Same as [`generate`], but offers `tools` to the model.
+///
+/// Only `/api/chat` accepts tools, so pre-rendered prompts are sent as a single user message.
+pub async fn generate_with_tools(
+    prompt: &MaterializedTextPrompt,
+    config: &InferenceConfig,
+    tools: &[ToolDefinition],
+) -> Result<TextInferenceOutput, TextInferenceBackendError> {
+    let (_, mut payload) = build_request(prompt, config, false);
+    if let Some(payload) = payload.as_object_mut() {
+        if payload.remove("prompt").is_some() {
+            payload.insert(
+                "messages".to_string(),
+                serde_json::json!([{"role": "user", "content": prompt.materialized}]),
+            );
+        }
     }
+    payload["tools"] = serde_json::json!(tools
+        .iter()
+        .map(|tool| tool.function_json())
+        .collect::<Vec<_>>());
 
     let client = Client::new();
 
     let res = client
-        .post("http://localhost:11434/api/generate")
+        .post(format!("{}/api/chat", config.base_url(OLLAMA_BACKEND)))
         .json(&payload)
         .send()
         .await?;
 
     if res.status().is_success() {
         let api_response = res.json::<ApiResponse>().await?;
-        let mut text = api_response.response.as_str();
-        text = text.trim_end_matches("<dummy32000>");
-        text = text.trim();
-        Ok(text.to_string())
+        let tool_calls = match &api_response.message {
+            Some(message) => message
+                .tool_calls
+                .iter()
+                .map(|call| ToolCall::parse(&call.function.name, &call.function.arguments))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+        Ok(TextInferenceOutput {
+            text: clean_response(api_response.text()),
+            tool_calls,
+            usage: api_response.usage(),
+        })
+    } else {
+        let status = res.status();
+        let body = res.text().await?;
+        Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+    }
+}
+
+/// Same as [`generate`], but consumes the NDJSON stream and reports each token through `on_delta` as it arrives.
+///
+/// Token usage is taken from the final line of the stream.
+pub async fn generate_streaming(
+    prompt: &MaterializedTextPrompt,
+    config: &InferenceConfig,
+    on_delta: &(dyn Fn(&str) + Send + Sync),
+) -> Result<TextInferenceOutput, TextInferenceBackendError> {
+    let (url, payload) = build_request(prompt, config, true);
+
+    let client = Client::new();
+
+    let mut res = client.post(url).json(&payload).send().await?;
+
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await?;
+        return Err(format!("Failed to call API. Status: {} Body: {}", status, body).into());
+    }
+
+    let mut full = String::new();
+    let mut pending = Vec::<u8>::new();
+    while let Some(chunk) = res.chunk().await? {
+        pending.extend_from_slice(&chunk);
+        // chunks are not guaranteed to align with lines
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            let line = pending.drain(..=newline).collect::<Vec<u8>>();
+            if line.iter().all(|b| b.is_ascii_whitespace()) {
+                continue;
+            }
+            let part = serde_json::from_slice::<ApiResponse>(&line)?;
+            let delta = part.text().replace("<dummy32000>", "");
+            if !delta.is_empty() {
+                on_delta(&delta);
+                full.push_str(&delta);
+            }
+            if part.done {
+                return Ok(TextInferenceOutput {
+                    text: clean_response(&full),
+                    tool_calls: Vec::new(),
+                    usage: part.usage(),
+                });
+            }
+        }
+    }
+    Ok(TextInferenceOutput {
+        text: clean_response(&full),
+        tool_calls: Vec::new(),
+        usage: None,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+pub async fn embed(
+    text: &str,
+    config: &InferenceConfig,
+) -> Result<Vec<f32>, TextInferenceBackendError> {
+    let mut payload = serde_json::json!({
+        "model": config.embedding_model,
+        "prompt": text,
+    });
+    if let Some(keep_alive) = &config.keep_alive {
+        payload["keep_alive"] = serde_json::json!(keep_alive);
+    }
+
+    let client = Client::new();
+
+    let res = client
+        .post(format!(
+            "{}/api/embeddings",
+            config.base_url(OLLAMA_BACKEND)
+        ))
+        .json(&payload)
+        .send()
+        .await?;
+
+    if res.status().is_success() {
+        Ok(res.json::<EmbeddingResponse>().await?.embedding)
+    } else {
+        let status = res.status();
+        let body = res.text().await?;
+        Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    models: Vec<TagsModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsModel {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    modified_at: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+struct ApiModelDetails {
+    family: String,
+    parameter_size: String,
+    quantization_level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShowResponse {
+    #[serde(default)]
+    details: ApiModelDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullResponse {
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    completed: u64,
+    #[serde(default)]
+    total: u64,
+    error: Option<String>,
+}
+
+async fn check_status(
+    res: reqwest::Response,
+) -> Result<reqwest::Response, TextInferenceBackendError> {
+    if res.status().is_success() {
+        Ok(res)
     } else {
         let status = res.status();
         let body = res.text().await?;
-        Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to call API. Status: {} Body: {}", status, body),
-        )))
+        Err(format!("Failed to call API. Status: {} Body: {}", status, body).into())
+    }
+}
+
+pub async fn list_models(base_url: &str) -> Result<Vec<OllamaModel>, TextInferenceBackendError> {
+    let client = Client::new();
+    let res = client.get(format!("{}/api/tags", base_url)).send().await?;
+    let tags = check_status(res).await?.json::<TagsResponse>().await?;
+    Ok(tags
+        .models
+        .into_iter()
+        .map(|model| OllamaModel {
+            name: model.name,
+            size: model.size,
+            modified_at: model.modified_at,
+            details: None,
+        })
+        .collect())
+}
+
+pub async fn show_model(
+    base_url: &str,
+    name: &str,
+) -> Result<OllamaModelDetails, TextInferenceBackendError> {
+    let client = Client::new();
+    let res = client
+        .post(format!("{}/api/show", base_url))
+        .json(&serde_json::json!({ "name": name }))
+        .send()
+        .await?;
+    let details = check_status(res)
+        .await?
+        .json::<ShowResponse>()
+        .await?
+        .details;
+    Ok(OllamaModelDetails {
+        family: details.family,
+        parameter_size: details.parameter_size,
+        quantization_level: details.quantization_level,
+    })
+}
+
+/// Downloads `name`, reporting each progress line of the NDJSON stream through `on_progress`
+pub async fn pull_model(
+    base_url: &str,
+    name: &str,
+    on_progress: &(dyn Fn(OllamaPullProgress) + Send + Sync),
+) -> Result<(), TextInferenceBackendError> {
+    let client = Client::new();
+    let res = client
+        .post(format!("{}/api/pull", base_url))
+        .json(&serde_json::json!({ "name": name, "stream": true }))
+        .send()
+        .await?;
+    let mut res = check_status(res).await?;
+
+    let mut pending = Vec::<u8>::new();
+    while let Some(chunk) = res.chunk().await? {
+        pending.extend_from_slice(&chunk);
+        // chunks are not guaranteed to align with lines
+        while let Some(newline) = pending.iter().position(|b| *b == b'\n') {
+            let line = pending.drain(..=newline).collect::<Vec<u8>>();
+            if line.iter().all(|b| b.is_ascii_whitespace()) {
+                continue;
+            }
+            let part = serde_json::from_slice::<PullResponse>(&line)?;
+            if let Some(error) = part.error {
+                return Err(error.into());
+            }
+            on_progress(OllamaPullProgress {
+                status: part.status,
+                completed: part.completed,
+                total: part.total,
+            });
+        }
     }
+    Ok(())
+}
+
+/// Asks the server to evict `name` from memory right away
+pub async fn unload_model(base_url: &str, name: &str) -> Result<(), TextInferenceBackendError> {
+    let client = Client::new();
+    let res = client
+        .post(format!("{}/api/generate", base_url))
+        .json(&serde_json::json!({ "model": name, "keep_alive": 0 }))
+        .send()
+        .await?;
+    check_status(res).await?;
+    Ok(())
 }
 
-pub async fn get_status() -> Result<OllamaStatus, Box<dyn Error>> {
+pub async fn get_status(base_url: &str) -> Result<OllamaStatus, Box<dyn Error>> {
     let client = Client::new();
-    match client.get("http://localhost:11434/").send().await {
+    match client.get(format!("{}/", base_url)).send().await {
         Ok(res) => match res.status().is_success() {
             true => Ok(OllamaStatus::Alive),
             false => Ok(OllamaStatus::Dead),
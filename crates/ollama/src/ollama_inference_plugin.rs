@@ -1,166 +1,75 @@
 use bevy::prelude::*;
-use crossbeam_channel::bounded;
-use crossbeam_channel::Receiver;
-use crossbeam_channel::Sender;
+use bevy::utils::BoxedFuture;
 use cursor_hero_inference_types::prelude::*;
-use cursor_hero_text_asset_types::prelude::*;
-use std::thread;
 
 pub struct OllamaInferencePlugin;
 
 impl Plugin for OllamaInferencePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, create_worker_thread);
-        app.add_systems(Update, bridge_generate_requests);
-        app.add_systems(Update, bridge_generate_responses);
+        app.init_resource::<TextInferenceBackends>();
+        app.world
+            .resource_mut::<TextInferenceBackends>()
+            .register(OllamaTextInferenceBackend);
     }
 }
 
-#[derive(Debug)]
-enum GameboundMessage {
-    Response {
-        session_id: Entity,
-        prompt: MaterializedTextPrompt,
-        response: String,
-    },
-}
+/// Name the Ollama backend is registered under, also the key of its [`InferenceConfig::base_urls`] entry
+pub const OLLAMA_BACKEND: &str = "ollama";
 
-#[derive(Debug)]
-enum ThreadboundMessage {
-    Generate {
-        session_id: Entity,
-        prompt: MaterializedTextPrompt,
-    },
-}
+pub struct OllamaTextInferenceBackend;
 
-#[derive(Resource)]
-struct Bridge {
-    pub sender: Sender<ThreadboundMessage>,
-    pub receiver: Receiver<GameboundMessage>,
-}
+impl TextInferenceBackend for OllamaTextInferenceBackend {
+    fn name(&self) -> &str {
+        OLLAMA_BACKEND
+    }
 
-fn create_worker_thread(mut commands: Commands) {
-    let (game_tx, game_rx) = bounded::<_>(10);
-    let (thread_tx, thread_rx) = bounded::<_>(10);
-    commands.insert_resource(Bridge {
-        sender: thread_tx,
-        receiver: game_rx,
-    });
+    fn generate<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(crate::ollama::generate(prompt, config))
+    }
 
-    let game_tx_clone = game_tx.clone();
-    thread::Builder::new()
-        .name("Ollama thread".to_string())
-        .spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async {
-                let game_tx = game_tx_clone;
-                loop {
-                    let msg = match thread_rx.recv() {
-                        Ok(msg) => msg,
-                        Err(_) => {
-                            error!("Threadbound channel failure, exiting");
-                            break;
-                        }
-                    };
-                    match msg {
-                        ThreadboundMessage::Generate { session_id, prompt } => {
-                            debug!("Worker received generate request for session {:?}, generating response", session_id);
-                            let data = match crate::ollama::generate(&prompt.materialized, prompt.prompt.options()).await {
-                                Ok(data) => data,
-                                Err(e) => {
-                                    error!("Failed to generate: {:?}", e);
-                                    continue;
-                                }
-                            };
-                            if let Err(e) = game_tx.send(GameboundMessage::Response {
-                                session_id,
-                                prompt: prompt.clone(),
-                                response: data,
-                            }) {
-                                error!("Gamebound channel failure, exiting: {:?}", e);
-                                break;
-                            }
-                        }
-                    }
-                    std::thread::sleep(std::time::Duration::from_millis(50));
-                }
-            });
+    fn generate_streaming<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        on_delta: &'a (dyn Fn(&str) + Send + Sync),
+    ) -> BoxedFuture<'a, Result<String, TextInferenceBackendError>> {
+        Box::pin(async move {
+            let output = crate::ollama::generate_streaming(prompt, config, on_delta).await?;
+            Ok(output.text)
         })
-        .expect("Failed to spawn thread");
-}
-
-fn bridge_generate_requests(
-    bridge: ResMut<Bridge>,
-    mut events: EventReader<TextInferenceEvent>,
-    prompts: Res<TextPromptHandles>,
-    text_assets: Res<Assets<TextAsset>>,
-) {
-    for event in events.read() {
-        if let TextInferenceEvent::Request { session_id, prompt } = event {
-            debug!(
-                "Received generate request for session {:?}, sending over bridge to worker thread",
-                session_id
-            );
+    }
 
-            // we gotta load the prompt from the asset server to materialize it before we can send it
-            let handle = match prompt {
-                TextPrompt::Raw { .. } => &prompts.raw,
-                TextPrompt::Chat { .. } => &prompts.chat,
-            };
-            let prompt_asset = match text_assets.get(handle) {
-                Some(asset) => asset,
-                None => {
-                    error!(
-                        "Failed to load prompt asset {:?} for prompt type {:?}",
-                        handle,
-                        std::any::type_name_of_val(&prompt)
-                    );
-                    continue;
-                }
-            };
-            let materialized_prompt = MaterializedTextPrompt {
-                prompt: prompt.clone(),
-                materialized: match prompt {
-                    TextPrompt::Raw { content, .. } => {
-                        prompt_asset.value.replace("{{content}}", content)
-                    }
-                    TextPrompt::Chat { chat_history, .. } => {
-                        prompt_asset.value.replace("{{chat_history}}", chat_history)
-                    }
-                },
-            };
+    fn generate_streaming_output<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        on_delta: &'a (dyn Fn(&str) + Send + Sync),
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(crate::ollama::generate_streaming(prompt, config, on_delta))
+    }
 
-            info!(
-                "Sending prompt to text generation worker:\n{}",
-                materialized_prompt.materialized
-            );
+    fn embed<'a>(
+        &'a self,
+        text: &'a str,
+        config: &'a InferenceConfig,
+    ) -> BoxedFuture<'a, Result<Vec<f32>, TextInferenceBackendError>> {
+        Box::pin(crate::ollama::embed(text, config))
+    }
 
-            if let Err(e) = bridge.sender.send(ThreadboundMessage::Generate {
-                session_id: *session_id,
-                prompt: materialized_prompt,
-            }) {
-                error!("Threadbound channel failure: {}", e);
-            }
-        }
+    fn supports_tools(&self) -> bool {
+        true
     }
-}
 
-fn bridge_generate_responses(bridge: ResMut<Bridge>, mut events: EventWriter<TextInferenceEvent>) {
-    for msg in bridge.receiver.try_iter() {
-        match msg {
-            GameboundMessage::Response {
-                session_id,
-                prompt,
-                response,
-            } => {
-                let event = TextInferenceEvent::Response {
-                    session_id,
-                    response,
-                    prompt,
-                };
-                debug!("Received bridge response, sending game event {:?}", event);
-                events.send(event);
-            }
-        }
+    fn generate_with_tools<'a>(
+        &'a self,
+        prompt: &'a MaterializedTextPrompt,
+        config: &'a InferenceConfig,
+        tools: &'a [ToolDefinition],
+    ) -> BoxedFuture<'a, Result<TextInferenceOutput, TextInferenceBackendError>> {
+        Box::pin(crate::ollama::generate_with_tools(prompt, config, tools))
     }
 }
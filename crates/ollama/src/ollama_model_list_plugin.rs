@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use cursor_hero_ollama_types::prelude::*;
+
+/// Keeps [`OllamaModelList`] in sync with the server.
+pub struct OllamaModelListPlugin;
+
+impl Plugin for OllamaModelListPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OllamaModelList>();
+        app.add_systems(Update, list_when_alive);
+        app.add_systems(Update, handle_model_events);
+    }
+}
+
+fn list_when_alive(
+    mut status_events: EventReader<OllamaStatusEvent>,
+    mut model_events: EventWriter<OllamaModelEvent>,
+) {
+    for event in status_events.read() {
+        if let OllamaStatusEvent::Changed {
+            new_value: OllamaStatus::Alive,
+        } = event
+        {
+            debug!("Ollama is alive, listing models");
+            model_events.send(OllamaModelEvent::ListRequest);
+        }
+    }
+}
+
+fn handle_model_events(
+    mut model_events: ParamSet<(EventReader<OllamaModelEvent>, EventWriter<OllamaModelEvent>)>,
+    mut model_list: ResMut<OllamaModelList>,
+) {
+    let mut requests = Vec::new();
+    for event in model_events.p0().read() {
+        match event {
+            OllamaModelEvent::Listed { models } => {
+                let mut models = models.clone();
+                for model in models.iter_mut() {
+                    // details do not change unless the model is pulled again
+                    model.details = model_list
+                        .get(&model.name)
+                        .filter(|known| known.modified_at == model.modified_at)
+                        .and_then(|known| known.details.clone());
+                    if model.details.is_none() {
+                        requests.push(OllamaModelEvent::ShowRequest {
+                            name: model.name.clone(),
+                        });
+                    }
+                }
+                info!("Ollama has {} models installed", models.len());
+                model_list.models = models;
+                model_list.last_error = None;
+            }
+            OllamaModelEvent::PullProgress { name, progress } => {
+                model_list.pulls.insert(name.clone(), progress.clone());
+            }
+            OllamaModelEvent::Pulled { name } => {
+                info!("Pulled Ollama model {}", name);
+                model_list.pulls.remove(name);
+                requests.push(OllamaModelEvent::ListRequest);
+            }
+            OllamaModelEvent::Shown { name, details } => {
+                if let Some(model) = model_list
+                    .models
+                    .iter_mut()
+                    .find(|model| &model.name == name)
+                {
+                    model.details = Some(details.clone());
+                }
+            }
+            OllamaModelEvent::Unloaded { name } => {
+                info!("Unloaded Ollama model {}", name);
+            }
+            OllamaModelEvent::Failed { name, reason } => {
+                if let Some(name) = name {
+                    model_list.pulls.remove(name);
+                }
+                model_list.last_error = Some(reason.clone());
+            }
+            _ => {}
+        }
+    }
+    for event in requests {
+        debug!("Sending event {:?}", event);
+        model_events.p1().send(event);
+    }
+}
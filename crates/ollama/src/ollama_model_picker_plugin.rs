@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use cursor_hero_cursor_types::prelude::*;
+use cursor_hero_environment_types::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_ollama_types::prelude::*;
+
+/// Lists installed models next to the Ollama Server Control so the active one can be picked in-world.
+pub struct OllamaModelPickerPlugin;
+
+impl Plugin for OllamaModelPickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, populate_new_host_environments);
+        app.add_systems(Update, rebuild_entries);
+        app.add_systems(Update, entry_click_listener);
+    }
+}
+
+const ENTRY_SIZE: Vec2 = Vec2::new(400.0, 40.0);
+
+fn populate_new_host_environments(
+    mut commands: Commands,
+    mut environment_events: EventReader<PopulateEnvironmentEvent>,
+    environment_query: Query<(), With<HostEnvironment>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in environment_events.read() {
+        if !environment_query.contains(event.environment_id) {
+            continue;
+        };
+        let environment_id = event.environment_id;
+        info!(
+            "Adding Ollama model picker to new host environment {:?}",
+            environment_id
+        );
+        commands.entity(environment_id).with_children(|parent| {
+            parent
+                .spawn((
+                    OllamaModelPicker,
+                    Name::new("Ollama Model Picker"),
+                    SpatialBundle::from_transform(Transform::from_translation(Vec3::new(
+                        1920.0 / 2.0 + 400.0,
+                        -1080.0 - 200.0,
+                        0.0,
+                    ))),
+                ))
+                .with_children(|parent| {
+                    parent.spawn((Text2dBundle {
+                        text: Text::from_section(
+                            "Ollama Models".to_string(),
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                font_size: 32.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        transform: Transform::from_xyz(0.0, 70.0, 1.0),
+                        ..default()
+                    },));
+                });
+        });
+    }
+}
+
+fn describe(model: &OllamaModel) -> String {
+    match &model.details {
+        Some(details) => format!(
+            "{} ({:.1} GB, {} {})",
+            model.name,
+            model.size_gb(),
+            details.parameter_size,
+            details.quantization_level
+        ),
+        None => format!("{} ({:.1} GB)", model.name, model.size_gb()),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn rebuild_entries(
+    mut commands: Commands,
+    picker_query: Query<(Entity, Option<&Children>), With<OllamaModelPicker>>,
+    added_query: Query<(), Added<OllamaModelPicker>>,
+    entry_query: Query<(), With<OllamaModelPickerEntry>>,
+    model_list: Res<OllamaModelList>,
+    config: Res<InferenceConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    if !model_list.is_changed() && !config.is_changed() && added_query.is_empty() {
+        return;
+    }
+    let mut entries = model_list
+        .models
+        .iter()
+        .map(|model| {
+            let selected = model.name == config.model;
+            (
+                OllamaModelPickerEntry::Installed {
+                    name: model.name.clone(),
+                },
+                describe(model),
+                match selected {
+                    true => Color::DARK_GREEN,
+                    false => Color::DARK_GRAY,
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+    if model_list.get(&config.model).is_none() {
+        let text = match model_list.pulls.get(&config.model) {
+            Some(progress) => format!(
+                "pulling {} ({} {:.0}%)",
+                config.model,
+                progress.status,
+                progress.fraction() * 100.0
+            ),
+            None => format!("pull {}", config.model),
+        };
+        entries.push((
+            OllamaModelPickerEntry::Missing {
+                name: config.model.clone(),
+            },
+            text,
+            Color::ORANGE,
+        ));
+    }
+
+    for (picker_id, picker_children) in picker_query.iter() {
+        for child in picker_children.into_iter().flatten() {
+            if entry_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        commands.entity(picker_id).with_children(|parent| {
+            for (i, (entry, text, color)) in entries.iter().enumerate() {
+                parent
+                    .spawn((
+                        entry.clone(),
+                        Name::new(format!(
+                            "Ollama Model Picker Entry - {}",
+                            entry.model_name()
+                        )),
+                        SpriteBundle {
+                            sprite: Sprite {
+                                custom_size: Some(ENTRY_SIZE),
+                                color: *color,
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(
+                                0.0,
+                                -(i as f32) * (ENTRY_SIZE.y + 10.0),
+                                0.0,
+                            ),
+                            ..default()
+                        },
+                        Clickable,
+                        Hoverable,
+                        RigidBody::Static,
+                        Sensor,
+                        Collider::cuboid(ENTRY_SIZE.x, ENTRY_SIZE.y),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((Text2dBundle {
+                            text: Text::from_section(
+                                text.clone(),
+                                TextStyle {
+                                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                                    font_size: 20.0,
+                                    color: Color::WHITE,
+                                },
+                            )
+                            .with_alignment(TextAlignment::Center),
+                            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                            ..default()
+                        },));
+                    });
+            }
+        });
+    }
+}
+
+fn entry_click_listener(
+    mut click_events: EventReader<ClickEvent>,
+    entry_query: Query<&OllamaModelPickerEntry>,
+    mut model_events: EventWriter<OllamaModelEvent>,
+    mut config: ResMut<InferenceConfig>,
+    model_list: Res<OllamaModelList>,
+) {
+    for event in click_events.read() {
+        let ClickEvent::Clicked { target_id, way, .. } = event else {
+            continue;
+        };
+        let Ok(entry) = entry_query.get(*target_id) else {
+            continue;
+        };
+        let event = match (entry, way) {
+            (OllamaModelPickerEntry::Installed { name }, Way::Left) => {
+                info!("Selecting Ollama model {}", name);
+                config.model = name.clone();
+                continue;
+            }
+            (OllamaModelPickerEntry::Installed { name }, Way::Right) => {
+                OllamaModelEvent::UnloadRequest { name: name.clone() }
+            }
+            (OllamaModelPickerEntry::Missing { name }, Way::Left) => {
+                if model_list.pulls.contains_key(name) {
+                    warn!("Already pulling Ollama model {}", name);
+                    continue;
+                }
+                OllamaModelEvent::PullRequest { name: name.clone() }
+            }
+            _ => continue,
+        };
+        debug!("Sending event {:?}", event);
+        model_events.send(event);
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_ollama_types::prelude::*;
+use cursor_hero_worker::prelude::anyhow::Error;
+use cursor_hero_worker::prelude::crossbeam_channel::TrySendError;
+use cursor_hero_worker::prelude::send_gamebound;
+use cursor_hero_worker::prelude::Sender;
+use cursor_hero_worker::prelude::WorkerConfig;
+use cursor_hero_worker::prelude::WorkerFuture;
+use cursor_hero_worker::prelude::WorkerPlugin;
+
+use crate::ollama_inference_plugin::OLLAMA_BACKEND;
+
+/// Answers [`OllamaModelEvent`] requests on a thread of its own, pulls can take a long time so they run side by side.
+pub struct OllamaModelWorkerPlugin;
+
+impl Plugin for OllamaModelWorkerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(WorkerPlugin {
+            config: WorkerConfig::<ThreadboundMessage, GameboundMessage, (), _, _, _> {
+                name: "ollama_model".to_string(),
+                handle_threadbound_message_async: Some(handle_threadbound_message),
+                // pulls report progress many times per second
+                gamebound_channel_capacity: 100,
+                ..default()
+            },
+        });
+        app.add_systems(Update, events_to_bridge);
+        app.add_systems(Update, bridge_to_events);
+    }
+}
+
+#[derive(Debug, Clone, Event)]
+enum GameboundMessage {
+    Listed {
+        models: Vec<OllamaModel>,
+    },
+    PullProgress {
+        name: String,
+        progress: OllamaPullProgress,
+    },
+    Pulled {
+        name: String,
+    },
+    Shown {
+        name: String,
+        details: OllamaModelDetails,
+    },
+    Unloaded {
+        name: String,
+    },
+    Failed {
+        name: Option<String>,
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, Event)]
+enum ThreadboundMessage {
+    List { base_url: String },
+    Pull { base_url: String, name: String },
+    Show { base_url: String, name: String },
+    Unload { base_url: String, name: String },
+}
+
+fn handle_threadbound_message<'a>(
+    msg: &'a ThreadboundMessage,
+    reply_tx: &'a Sender<GameboundMessage>,
+    _state: &'a mut (),
+) -> WorkerFuture<'a, Error> {
+    Box::pin(async move {
+        debug!("Worker received {:?}", msg);
+        let reply = match msg {
+            ThreadboundMessage::List { base_url } => {
+                match crate::ollama::list_models(base_url).await {
+                    Ok(models) => GameboundMessage::Listed { models },
+                    Err(e) => GameboundMessage::Failed {
+                        name: None,
+                        reason: e.to_string(),
+                    },
+                }
+            }
+            ThreadboundMessage::Pull { base_url, name } => {
+                let on_progress = |progress: OllamaPullProgress| {
+                    // progress is best effort, a full channel drops it rather than stalling the other messages
+                    let update = GameboundMessage::PullProgress {
+                        name: name.clone(),
+                        progress,
+                    };
+                    match reply_tx.try_send(update) {
+                        Ok(()) | Err(TrySendError::Full(_)) => {}
+                        Err(e) => error!("Gamebound channel failure: {:?}", e),
+                    }
+                };
+                match crate::ollama::pull_model(base_url, name, &on_progress).await {
+                    Ok(()) => GameboundMessage::Pulled { name: name.clone() },
+                    Err(e) => GameboundMessage::Failed {
+                        name: Some(name.clone()),
+                        reason: e.to_string(),
+                    },
+                }
+            }
+            ThreadboundMessage::Show { base_url, name } => {
+                match crate::ollama::show_model(base_url, name).await {
+                    Ok(details) => GameboundMessage::Shown {
+                        name: name.clone(),
+                        details,
+                    },
+                    Err(e) => GameboundMessage::Failed {
+                        name: Some(name.clone()),
+                        reason: e.to_string(),
+                    },
+                }
+            }
+            ThreadboundMessage::Unload { base_url, name } => {
+                match crate::ollama::unload_model(base_url, name).await {
+                    Ok(()) => GameboundMessage::Unloaded { name: name.clone() },
+                    Err(e) => GameboundMessage::Failed {
+                        name: Some(name.clone()),
+                        reason: e.to_string(),
+                    },
+                }
+            }
+        };
+        send_gamebound(reply_tx, reply).await?;
+        Ok(())
+    })
+}
+
+fn events_to_bridge(
+    mut bridge: EventWriter<ThreadboundMessage>,
+    mut model_events: EventReader<OllamaModelEvent>,
+    config: Res<InferenceConfig>,
+) {
+    for event in model_events.read() {
+        let base_url = config.base_url(OLLAMA_BACKEND).to_string();
+        let msg = match event {
+            OllamaModelEvent::ListRequest => ThreadboundMessage::List { base_url },
+            OllamaModelEvent::PullRequest { name } => ThreadboundMessage::Pull {
+                base_url,
+                name: name.clone(),
+            },
+            OllamaModelEvent::ShowRequest { name } => ThreadboundMessage::Show {
+                base_url,
+                name: name.clone(),
+            },
+            OllamaModelEvent::UnloadRequest { name } => ThreadboundMessage::Unload {
+                base_url,
+                name: name.clone(),
+            },
+            _ => continue,
+        };
+        debug!("Sending bridge message: {:?}", msg);
+        bridge.send(msg);
+    }
+}
+
+fn bridge_to_events(
+    mut bridge: EventReader<GameboundMessage>,
+    mut events: EventWriter<OllamaModelEvent>,
+) {
+    for msg in bridge.read() {
+        let event = match msg.clone() {
+            GameboundMessage::Listed { models } => OllamaModelEvent::Listed { models },
+            GameboundMessage::PullProgress { name, progress } => {
+                OllamaModelEvent::PullProgress { name, progress }
+            }
+            GameboundMessage::Pulled { name } => OllamaModelEvent::Pulled { name },
+            GameboundMessage::Shown { name, details } => OllamaModelEvent::Shown { name, details },
+            GameboundMessage::Unloaded { name } => OllamaModelEvent::Unloaded { name },
+            GameboundMessage::Failed { name, reason } => {
+                warn!("Ollama model request failed for {:?}: {}", name, reason);
+                OllamaModelEvent::Failed { name, reason }
+            }
+        };
+        trace!("Received bridge response, sending game event {:?}", event);
+        events.send(event);
+    }
+}
@@ -2,6 +2,9 @@ use bevy::prelude::*;
 
 use crate::ollama_button_plugin::OllamaButtonPlugin;
 use crate::ollama_inference_plugin::OllamaInferencePlugin;
+use crate::ollama_model_list_plugin::OllamaModelListPlugin;
+use crate::ollama_model_picker_plugin::OllamaModelPickerPlugin;
+use crate::ollama_model_worker_plugin::OllamaModelWorkerPlugin;
 use crate::ollama_status_plugin::OllamaStatusPlugin;
 use crate::ollama_status_worker_plugin::OllamaStatusWorkerPlugin;
 
@@ -13,5 +16,8 @@ impl Plugin for OllamaPlugin {
         app.add_plugins(OllamaButtonPlugin);
         app.add_plugins(OllamaStatusPlugin);
         app.add_plugins(OllamaStatusWorkerPlugin);
+        app.add_plugins(OllamaModelListPlugin);
+        app.add_plugins(OllamaModelWorkerPlugin);
+        app.add_plugins(OllamaModelPickerPlugin);
     }
 }
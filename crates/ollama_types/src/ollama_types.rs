@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 use bevy::utils::Instant;
 
 #[derive(Reflect, Resource, Default, Debug, Eq, PartialEq, Clone, Copy)]
@@ -60,3 +61,114 @@ pub enum OllamaTerminalButtonVisualState {
 pub enum OllamaTerminalEvent {
     Startup,
 }
+
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Default)]
+pub struct OllamaModel {
+    pub name: String,
+    /// Bytes on disk
+    pub size: u64,
+    pub modified_at: String,
+    /// Filled in by [`OllamaModelEvent::Shown`]
+    pub details: Option<OllamaModelDetails>,
+}
+
+impl OllamaModel {
+    pub fn size_gb(&self) -> f32 {
+        self.size as f32 / 1_000_000_000.0
+    }
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Default)]
+pub struct OllamaModelDetails {
+    pub family: String,
+    pub parameter_size: String,
+    pub quantization_level: String,
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq, Eq, Default)]
+pub struct OllamaPullProgress {
+    pub status: String,
+    pub completed: u64,
+    pub total: u64,
+}
+
+impl OllamaPullProgress {
+    pub fn fraction(&self) -> f32 {
+        match self.total {
+            0 => 0.0,
+            total => self.completed as f32 / total as f32,
+        }
+    }
+}
+
+/// Models installed on the Ollama server, kept up to date by [`OllamaModelEvent`]s
+#[derive(Reflect, Resource, Default, Debug, Clone)]
+#[reflect(Resource)]
+pub struct OllamaModelList {
+    pub models: Vec<OllamaModel>,
+    /// Pulls in progress by model name
+    pub pulls: HashMap<String, OllamaPullProgress>,
+    pub last_error: Option<String>,
+}
+
+impl OllamaModelList {
+    pub fn get(&self, name: &str) -> Option<&OllamaModel> {
+        self.models.iter().find(|model| model.name == name)
+    }
+}
+
+#[derive(Event, Debug, Reflect, Clone)]
+pub enum OllamaModelEvent {
+    ListRequest,
+    Listed {
+        models: Vec<OllamaModel>,
+    },
+    PullRequest {
+        name: String,
+    },
+    PullProgress {
+        name: String,
+        progress: OllamaPullProgress,
+    },
+    Pulled {
+        name: String,
+    },
+    ShowRequest {
+        name: String,
+    },
+    Shown {
+        name: String,
+        details: OllamaModelDetails,
+    },
+    /// Evicts the model from memory, it stays installed
+    UnloadRequest {
+        name: String,
+    },
+    Unloaded {
+        name: String,
+    },
+    Failed {
+        name: Option<String>,
+        reason: String,
+    },
+}
+
+/// In-world list of installed models, next to the [`OllamaStatusButton`]
+#[derive(Component, Debug, Reflect, Default)]
+pub struct OllamaModelPicker;
+
+#[derive(Component, Debug, Reflect, Clone)]
+pub enum OllamaModelPickerEntry {
+    /// Left click selects the model, right click unloads it
+    Installed { name: String },
+    /// The configured model is not installed, clicking pulls it
+    Missing { name: String },
+}
+impl OllamaModelPickerEntry {
+    pub fn model_name(&self) -> &str {
+        match self {
+            OllamaModelPickerEntry::Installed { name }
+            | OllamaModelPickerEntry::Missing { name } => name,
+        }
+    }
+}
@@ -18,5 +18,11 @@ impl Plugin for OllamaTypesPlugin {
         app.register_type::<OllamaTerminalButtonVisualState>();
         app.register_type::<OllamaTerminalEvent>();
         app.add_event::<OllamaTerminalEvent>();
+
+        app.register_type::<OllamaModelList>();
+        app.register_type::<OllamaModelEvent>();
+        app.add_event::<OllamaModelEvent>();
+        app.register_type::<OllamaModelPicker>();
+        app.register_type::<OllamaModelPickerEntry>();
     }
 }
@@ -63,7 +63,9 @@ use cursor_hero_host_event::prelude::*;
 use cursor_hero_host_event_types::prelude::*;
 use cursor_hero_host_fs::prelude::*;
 use cursor_hero_host_fs_types::prelude::*;
+use cursor_hero_inference::fixture_inference_backend::InferenceFixturePlugin;
 use cursor_hero_inference::inference_plugin::InferencePlugin;
+use cursor_hero_inference_types::inference_types::InferenceFixtureMode;
 use cursor_hero_inference_types::inference_types_plugin::InferenceTypesPlugin;
 use cursor_hero_memory::prelude::*;
 use cursor_hero_memory_types::prelude::*;
@@ -95,6 +97,7 @@ use cursor_hero_window_swap_tool_types::prelude::*;
 use cursor_hero_zoom_tool::prelude::*;
 use cursor_hero_zoom_tool_types::prelude::*;
 use itertools::Itertools;
+use std::path::PathBuf;
 pub struct DefaultLaunchModePlugin;
 
 impl Plugin for DefaultLaunchModePlugin {
@@ -110,9 +113,7 @@ impl Plugin for DefaultLaunchModePlugin {
         app.add_plugins(UiHoverTypesPlugin);
         app.add_plugins(UiHoverPlugin);
         app.add_plugins(MemoryTypesPlugin);
-        let memory_config = MemoryConfig {
-            save_dir: "Cursor Hero Memory".to_string(),
-        };
+        let memory_config = MemoryConfig::new("Cursor Hero Memory");
         app.add_plugins(MemoryPlugin {
             config: memory_config.clone(),
             build_config: MemoryPluginBuildConfig::all_enabled(),
@@ -155,6 +156,12 @@ impl Plugin for DefaultLaunchModePlugin {
         app.add_plugins(ObservationPlugin);
         app.add_plugins(InferenceTypesPlugin);
         app.add_plugins(InferencePlugin);
+        if let Some(mode) = InferenceFixtureMode::from_env() {
+            app.add_plugins(InferenceFixturePlugin {
+                mode,
+                file: PathBuf::from(&memory_config.save_dir).join("inference_fixtures.json"),
+            });
+        }
         app.add_plugins(MovementToolTypesPlugin);
         app.add_plugins(MovementToolPlugin);
         app.add_plugins(CharacterTypesPlugin);
@@ -20,9 +20,7 @@ impl Plugin for HeadlessLaunchModePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(MemoryTypesPlugin);
 
-        let memory_config = MemoryConfig {
-            save_dir: "Cursor Hero Memory (inspect mode)".to_string(),
-        };
+        let memory_config = MemoryConfig::new("Cursor Hero Memory (inspect mode)");
         app.add_plugins(MemoryPlugin {
             config: memory_config.clone(),
             build_config: MemoryPluginBuildConfig {
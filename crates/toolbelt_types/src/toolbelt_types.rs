@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy::utils::HashMap;
@@ -163,6 +164,12 @@ impl Default for Wheel {
     }
 }
 
+/// Names of the actions a tool performs for [`ToolCallEvent`]s.
+///
+/// Only tools whose action type has a `ToolCallPlugin` get one, other tools can't be called.
+#[derive(Component, Reflect, Clone, Debug, Default)]
+pub struct ToolCallActions(pub Vec<String>);
+
 #[derive(Component, Reflect, Clone, Debug)]
 pub struct Tool {
     pub name: String,
@@ -203,6 +210,18 @@ pub enum ToolActivationEvent {
     Deactivate(Entity),
 }
 
+/// Asks a tool to perform one of its actions as if its inputs were used, see `ToolCallPlugin` in the tools crate
+#[derive(Event, Debug, Reflect, Clone)]
+pub struct ToolCallEvent {
+    pub tool_id: Entity,
+    /// Debug name of the action, matched case-insensitively
+    pub action: String,
+    /// Axis pair for actions that take a direction
+    pub direction: Option<Vec2>,
+    /// How long the action stays pressed, zero presses it for a single frame
+    pub hold: Duration,
+}
+
 #[derive(Component, Reflect, Debug)]
 pub struct ToolHelp {
     pub timer: Timer,
@@ -9,8 +9,10 @@ impl Plugin for ToolbeltTypesPlugin {
         app.register_type::<Wheel>();
         app.register_type::<Tool>();
         app.register_type::<ActiveTool>();
+        app.register_type::<ToolCallActions>();
         app.add_event::<ToolbeltPopulateEvent>();
         app.add_event::<ToolbeltOpeningEvent>();
         app.add_event::<ToolActivationEvent>();
+        app.add_event::<ToolCallEvent>();
     }
 }
@@ -17,6 +17,7 @@ impl Plugin for CubeToolPlugin {
         app.register_type::<CubeTool>();
         app.register_type::<CubeToolInteractable>();
         app.add_plugins(InputManagerPlugin::<CubeToolAction>::default());
+        app.add_plugins(ToolCallPlugin::<CubeToolAction>::default());
         app.add_systems(Update, (toolbelt_events, handle_input));
     }
 }
@@ -30,11 +31,14 @@ fn toolbelt_events(
     mut reader: EventReader<ToolbeltPopulateEvent>,
 ) {
     for event in reader.read() {
-        let ToolbeltLoadout::Default = event.loadout else {
-            continue;
+        let input_map = match event.loadout {
+            ToolbeltLoadout::Default => CubeToolAction::default_input_map(event),
+            ToolbeltLoadout::Agent => None,
+            _ => continue,
         };
         ToolSpawnConfig::<CubeTool, CubeToolAction>::new(CubeTool, event.id, event)
             .with_src_path(file!().into())
+            .with_input_map(input_map)
             .guess_name(file!())
             .guess_image(file!(), &asset_server, "png")
             .with_description("Spawn and attract cubes")
@@ -10,6 +10,7 @@ pub mod placeholder_tool;
 pub mod restart_tool;
 pub mod scroll_tool;
 pub mod talk_tool;
+pub mod tool_call_plugin;
 pub mod tool_plugin;
 pub mod tool_spawning;
 pub mod window_drag_tool;
@@ -17,6 +18,8 @@ pub mod window_drag_tool;
 pub use crate::tool_plugin::ToolPlugin;
 
 pub mod prelude {
+    pub use crate::tool_call_plugin::HeldToolActions;
+    pub use crate::tool_call_plugin::ToolCallPlugin;
     pub use crate::tool_spawning::NoInputs;
     pub use crate::tool_spawning::StartingState;
     pub use crate::tool_spawning::ToolSpawnConfig;
@@ -0,0 +1,126 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use cursor_hero_toolbelt_types::prelude::*;
+use leafwing_input_manager::axislike::DualAxisData;
+use leafwing_input_manager::plugin::InputManagerSystem;
+use leafwing_input_manager::prelude::*;
+
+/// Presses actions of tools using `A` when a [`ToolCallEvent`] targets them.
+///
+/// Runs after the input manager has updated, so the presses are not overwritten by the tool's own input map.
+/// Tools using `A` are given [`ToolCallActions`] listing every variant, whether or not the tool has inputs bound to it.
+pub struct ToolCallPlugin<A: Actionlike>(PhantomData<A>);
+
+impl<A: Actionlike> Default for ToolCallPlugin<A> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<A: Actionlike + Debug> Plugin for ToolCallPlugin<A> {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (start_tool_calls::<A>, hold_tool_calls::<A>)
+                .chain()
+                .in_set(InputManagerSystem::ManualControl),
+        );
+        app.add_systems(Update, describe_tool_call_actions::<A>);
+    }
+}
+
+fn describe_tool_call_actions<A: Actionlike + Debug>(
+    mut commands: Commands,
+    tool_query: Query<Entity, (With<Tool>, Added<ActionState<A>>)>,
+) {
+    for tool_id in tool_query.iter() {
+        let actions = A::variants()
+            .map(|variant| format!("{:?}", variant))
+            .collect();
+        commands.entity(tool_id).insert(ToolCallActions(actions));
+    }
+}
+
+/// Actions pressed on behalf of [`ToolCallEvent`]s that have not been released yet
+#[derive(Component, Debug)]
+pub struct HeldToolActions<A: Actionlike> {
+    held: Vec<HeldToolAction<A>>,
+}
+
+#[derive(Debug)]
+struct HeldToolAction<A> {
+    action: A,
+    direction: Option<Vec2>,
+    timer: Timer,
+}
+
+fn press<A: Actionlike>(action_state: &mut ActionState<A>, action: &A, direction: Option<Vec2>) {
+    if let Some(direction) = direction {
+        action_state.action_data_mut(action.clone()).axis_pair =
+            Some(DualAxisData::from_xy(direction.clamp_length_max(1.0)));
+    }
+    action_state.press(action.clone());
+}
+
+fn start_tool_calls<A: Actionlike + Debug>(
+    mut commands: Commands,
+    mut events: EventReader<ToolCallEvent>,
+    mut tool_query: Query<(&mut ActionState<A>, Option<&mut HeldToolActions<A>>)>,
+) {
+    for event in events.read() {
+        // tools using other action types are handled by their own plugin
+        let Ok((mut action_state, held)) = tool_query.get_mut(event.tool_id) else {
+            continue;
+        };
+        let Some(action) = A::variants()
+            .find(|variant| format!("{:?}", variant).eq_ignore_ascii_case(&event.action))
+        else {
+            warn!(
+                "Tool {:?} has no action named {:?}, ignoring tool call",
+                event.tool_id, event.action
+            );
+            continue;
+        };
+        debug!(
+            "Tool call pressing {:?} on {:?} for {:?}",
+            action, event.tool_id, event.hold
+        );
+        press(&mut action_state, &action, event.direction);
+        let entry = HeldToolAction {
+            action,
+            direction: event.direction,
+            timer: Timer::new(event.hold, TimerMode::Once),
+        };
+        match held {
+            Some(mut held) => held.held.push(entry),
+            None => {
+                commands
+                    .entity(event.tool_id)
+                    .insert(HeldToolActions { held: vec![entry] });
+            }
+        }
+    }
+}
+
+fn hold_tool_calls<A: Actionlike + Debug>(
+    mut commands: Commands,
+    mut tool_query: Query<(Entity, &mut ActionState<A>, &mut HeldToolActions<A>)>,
+    time: Res<Time>,
+) {
+    for (tool_id, mut action_state, mut held) in tool_query.iter_mut() {
+        held.held.retain_mut(|entry| {
+            entry.timer.tick(time.delta());
+            if entry.timer.finished() {
+                return false;
+            }
+            press(&mut action_state, &entry.action, entry.direction);
+            true
+        });
+        // the input manager releases the actions on its next update
+        if held.held.is_empty() {
+            commands.entity(tool_id).remove::<HeldToolActions<A>>();
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_egui::egui::collapsing_header::CollapsingState;
+use bevy_egui::EguiContexts;
+use cursor_hero_inference_types::prelude::*;
+use cursor_hero_ui_inspector_types::prelude::UIData;
+
+pub struct InferenceMetricsEguiPlugin;
+
+impl Plugin for InferenceMetricsEguiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InferenceMetrics>();
+        app.add_systems(
+            Update,
+            gui.run_if(|ui_data: Res<UIData>| {
+                ui_data.windows.global_toggle && ui_data.windows.inference_metrics.open
+            }),
+        );
+    }
+}
+
+const HISTOGRAM_BUCKETS: usize = 20;
+const HISTOGRAM_SIZE: egui::Vec2 = egui::vec2(240.0, 40.0);
+
+fn gui(
+    mut contexts: EguiContexts,
+    mut ui_data: ResMut<UIData>,
+    metrics: Res<InferenceMetrics>,
+    queue_status: Res<InferenceQueueStatus>,
+) {
+    // Get context
+    let ctx = contexts.ctx_mut();
+
+    // Do window
+    let window_id = egui::Id::new("Inference Metrics");
+    egui::Window::new("Inference Metrics")
+        .id(window_id)
+        .default_open(ui_data.windows.inference_metrics.header_open)
+        .show(ctx, |ui| {
+            ui.label(format!(
+                "{} requests, {} failed, {} queued, {} running",
+                metrics.requests, metrics.failures, queue_status.pending, queue_status.running
+            ));
+            ui.label(format!(
+                "{} prompt tokens, {} completion tokens",
+                metrics.prompt_tokens_total, metrics.completion_tokens_total
+            ));
+            ui.separator();
+
+            egui::Grid::new(window_id.with("histograms"))
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("");
+                    ui.strong("last");
+                    ui.strong("p50");
+                    ui.strong("p95");
+                    ui.strong("max");
+                    ui.strong("distribution");
+                    ui.end_row();
+                    for (name, histogram) in [
+                        ("queue wait (ms)", &metrics.queue_wait_ms),
+                        ("first token (ms)", &metrics.first_token_ms),
+                        ("total (ms)", &metrics.total_ms),
+                        ("prompt tokens", &metrics.prompt_tokens),
+                        ("completion tokens", &metrics.completion_tokens),
+                        ("tokens/s", &metrics.tokens_per_second),
+                    ] {
+                        ui.label(name);
+                        ui.label(format_value(histogram.last()));
+                        ui.label(format_value(histogram.percentile(50.0)));
+                        ui.label(format_value(histogram.percentile(95.0)));
+                        ui.label(format_value(histogram.max()));
+                        draw_histogram(ui, histogram);
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+
+            egui::CollapsingHeader::new("Recent requests").show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for sample in metrics.recent.iter().rev() {
+                            let tokens = match sample.usage {
+                                Some(usage) => format!(
+                                    "{} + {} tokens",
+                                    usage.prompt_tokens, usage.completion_tokens
+                                ),
+                                None => "? tokens".to_string(),
+                            };
+                            ui.label(format!(
+                                "{:?} {} {} - waited {:.0?}, first token {}, total {:.0?}, {}",
+                                sample.priority,
+                                sample.backend,
+                                sample.model,
+                                sample.timing.queue_wait,
+                                sample
+                                    .timing
+                                    .first_token
+                                    .map(|first_token| format!("{:.0?}", first_token))
+                                    .unwrap_or_else(|| "-".to_string()),
+                                sample.timing.total,
+                                tokens
+                            ));
+                        }
+                    });
+            });
+        });
+
+    // Track window collapsed state
+    ui_data.windows.inference_metrics.header_open =
+        CollapsingState::load(ctx, window_id.with("collapsing"))
+            .map(|x| x.is_open())
+            .unwrap_or(ui_data.windows.inference_metrics.header_open);
+}
+
+fn format_value(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{:.1}", value),
+        None => "-".to_string(),
+    }
+}
+
+fn draw_histogram(ui: &mut egui::Ui, histogram: &RollingHistogram) {
+    let (rect, response) = ui.allocate_exact_size(HISTOGRAM_SIZE, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+
+    let buckets = histogram.buckets(HISTOGRAM_BUCKETS);
+    let tallest = buckets.iter().copied().max().unwrap_or_default();
+    if tallest == 0 {
+        return;
+    }
+    let bar_width = rect.width() / HISTOGRAM_BUCKETS as f32;
+    for (i, count) in buckets.iter().enumerate() {
+        let height = rect.height() * *count as f32 / tallest as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + i as f32 * bar_width, rect.bottom() - height),
+            egui::pos2(
+                rect.left() + (i + 1) as f32 * bar_width - 1.0,
+                rect.bottom(),
+            ),
+        );
+        painter.rect_filled(bar, 0.0, ui.visuals().selection.bg_fill);
+    }
+    response.on_hover_text(format!(
+        "{} samples from 0 to {}",
+        histogram.len(),
+        format_value(histogram.max())
+    ));
+}
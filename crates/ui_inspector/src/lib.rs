@@ -1,4 +1,7 @@
 #![feature(let_chains, trivial_bounds, if_let_guard)]
+mod inference_metrics_egui_plugin;
+mod memory_egui_plugin;
+mod observation_history_egui_plugin;
 mod ui_inspector_children_fetcher_plugin;
 mod ui_inspector_tree_egui_plugin;
 mod ui_inspector_egui_properties_panel;
@@ -13,6 +16,7 @@ mod ui_inspector_tree_update_plugin;
 mod ui_inspector_worker_plugin;
 mod ui_inspector_paused_egui_plugin;
 mod ui_inspector_properties_egui_plugin;
+mod worker_status_egui_plugin;
 
 pub mod prelude {
     pub use crate::ui_inspector_plugin::*;
@@ -0,0 +1,263 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_egui::egui::collapsing_header::CollapsingState;
+use bevy_egui::EguiContexts;
+use cursor_hero_character_types::prelude::*;
+use cursor_hero_memory_types::prelude::*;
+use cursor_hero_observation_types::prelude::*;
+use cursor_hero_ui_inspector_types::prelude::UIData;
+
+pub struct MemoryEguiPlugin;
+
+impl Plugin for MemoryEguiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            gui.run_if(
+                |ui_data: Res<UIData>, memory_config: Option<Res<MemoryConfig>>| {
+                    memory_config.is_some()
+                        && ui_data.windows.global_toggle
+                        && ui_data.windows.memory.open
+                },
+            ),
+        );
+    }
+}
+
+/// What was on disk when last listed, listing it every frame would keep hitting the disk
+#[derive(Default)]
+struct MemoryListing {
+    files: Vec<String>,
+    /// The save file whose backups are shown
+    file: Option<String>,
+    /// Newest first
+    backups: Vec<String>,
+    error: Option<String>,
+    listed: bool,
+    /// Name to save a slot under
+    slot_name: String,
+    /// File names in [`MemoryConfig::transcripts_dir`]
+    transcripts: Vec<String>,
+    transcript: Option<String>,
+    /// Agent to export from or import into
+    agent_name: String,
+}
+
+impl MemoryListing {
+    fn refresh(&mut self, memory_config: &MemoryConfig) {
+        self.listed = true;
+        self.error = None;
+        match list_save_file_names(memory_config) {
+            Ok(files) => self.files = files,
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+        match list_transcripts(memory_config) {
+            Ok(transcripts) => self.transcripts = transcripts,
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+        self.backups.clear();
+        let Some(file) = &self.file else {
+            return;
+        };
+        match list_backups(memory_config, file) {
+            Ok(backups) => {
+                self.backups = backups
+                    .iter()
+                    .rev()
+                    .filter_map(|backup| backup.file_name())
+                    .map(|backup| backup.to_string_lossy().to_string())
+                    .collect()
+            }
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+    }
+}
+
+fn list_transcripts(memory_config: &MemoryConfig) -> Result<Vec<String>, std::io::Error> {
+    let dir = memory_config.transcripts_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut transcripts = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if TranscriptFormat::from_path(&path).is_some() {
+            if let Some(file_name) = path.file_name() {
+                transcripts.push(file_name.to_string_lossy().to_string());
+            }
+        }
+    }
+    transcripts.sort();
+    Ok(transcripts)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn gui(
+    mut contexts: EguiContexts,
+    mut ui_data: ResMut<UIData>,
+    memory_config: Res<MemoryConfig>,
+    slots: Res<MemorySlots>,
+    mut command_events: EventWriter<MemoryCommandEvent>,
+    mut transcript_events: EventWriter<ObservationTranscriptEvent>,
+    agent_query: Query<&Name, With<AgentCharacter>>,
+    mut listing: Local<MemoryListing>,
+) {
+    if !listing.listed {
+        listing.refresh(&memory_config);
+    }
+
+    // Get context
+    let ctx = contexts.ctx_mut();
+
+    // Do window
+    let window_id = egui::Id::new("Memory");
+    egui::Window::new("Memory")
+        .id(window_id)
+        .default_open(ui_data.windows.memory.header_open)
+        .show(ctx, |ui| {
+            let mut stale = false;
+            ui.horizontal(|ui| {
+                ui.label(format!("Saving to {}", memory_config.save_dir));
+                stale |= ui.button("Refresh").clicked();
+            });
+            if let Some(error) = &listing.error {
+                ui.colored_label(ui.visuals().error_fg_color, error.as_str());
+            }
+            ui.separator();
+
+            ui.strong("Backups");
+            ui.horizontal(|ui| {
+                ui.label("File");
+                egui::ComboBox::from_id_source(window_id.with("file"))
+                    .selected_text(listing.file.as_deref().unwrap_or("Pick a save file"))
+                    .show_ui(ui, |ui| {
+                        for file in listing.files.clone() {
+                            let label = file.clone();
+                            stale |= ui
+                                .selectable_value(&mut listing.file, Some(file), label)
+                                .changed();
+                        }
+                    });
+            });
+            if let Some(file_name) = listing.file.clone() {
+                if listing.backups.is_empty() {
+                    ui.label("No backups yet");
+                }
+                egui::ScrollArea::vertical()
+                    .id_source(window_id.with("backups"))
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for backup_name in listing.backups.iter() {
+                            ui.horizontal(|ui| {
+                                ui.label(backup_name);
+                                if ui.button("Restore").clicked() {
+                                    command_events.send(MemoryCommandEvent::RestoreBackup {
+                                        file_name: file_name.clone(),
+                                        backup_name: backup_name.clone(),
+                                    });
+                                    // restoring backs up the replaced file
+                                    stale = true;
+                                }
+                            });
+                        }
+                    });
+            }
+
+            ui.separator();
+
+            ui.strong("Slots");
+            for slot in slots.available.iter() {
+                ui.horizontal(|ui| {
+                    match slots.active.as_ref() == Some(slot) {
+                        true => ui.strong(slot),
+                        false => ui.label(slot),
+                    };
+                    if ui.button("Load").clicked() {
+                        command_events.send(MemoryCommandEvent::LoadSlot { name: slot.clone() });
+                        // loading backs up the replaced files
+                        stale = true;
+                    }
+                    if ui.button("Overwrite").clicked() {
+                        command_events.send(MemoryCommandEvent::SaveSlot { name: slot.clone() });
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut listing.slot_name);
+                let name = listing.slot_name.trim().to_string();
+                if ui
+                    .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                    .clicked()
+                {
+                    command_events.send(MemoryCommandEvent::SaveSlot { name });
+                    listing.slot_name.clear();
+                }
+            });
+
+            ui.separator();
+
+            ui.strong("Transcripts");
+            ui.horizontal(|ui| {
+                ui.label("Agent");
+                ui.text_edit_singleline(&mut listing.agent_name);
+                egui::ComboBox::from_id_source(window_id.with("agent"))
+                    .selected_text("Pick")
+                    .show_ui(ui, |ui| {
+                        for name in agent_query.iter() {
+                            if ui.selectable_label(false, name.as_str()).clicked() {
+                                listing.agent_name = name.to_string();
+                            }
+                        }
+                    });
+            });
+            let agent_name = listing.agent_name.trim().to_string();
+            ui.horizontal(|ui| {
+                for format in [TranscriptFormat::JsonLines, TranscriptFormat::Markdown] {
+                    let export = ui.add_enabled(
+                        !agent_name.is_empty(),
+                        egui::Button::new(format!("Export .{}", format.extension())),
+                    );
+                    if export.clicked() {
+                        transcript_events.send(ObservationTranscriptEvent::Export {
+                            agent_name: agent_name.clone(),
+                            format,
+                        });
+                        stale = true;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(window_id.with("transcript"))
+                    .selected_text(listing.transcript.as_deref().unwrap_or("Pick a transcript"))
+                    .show_ui(ui, |ui| {
+                        for transcript in listing.transcripts.clone() {
+                            let label = transcript.clone();
+                            ui.selectable_value(&mut listing.transcript, Some(transcript), label);
+                        }
+                    });
+                let import = ui
+                    .add_enabled(
+                        !agent_name.is_empty() && listing.transcript.is_some(),
+                        egui::Button::new("Import"),
+                    )
+                    .on_hover_text("Seeds an agent that hasn't chatted yet");
+                if import.clicked() {
+                    if let Some(transcript) = &listing.transcript {
+                        transcript_events.send(ObservationTranscriptEvent::Import {
+                            agent_name: agent_name.clone(),
+                            path: transcript.into(),
+                        });
+                    }
+                }
+            });
+
+            if stale {
+                listing.listed = false;
+            }
+        });
+
+    // Track window collapsed state
+    ui_data.windows.memory.header_open = CollapsingState::load(ctx, window_id.with("collapsing"))
+        .map(|x| x.is_open())
+        .unwrap_or(ui_data.windows.memory.header_open);
+}
@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_egui::egui::collapsing_header::CollapsingState;
+use bevy_egui::EguiContexts;
+use cursor_hero_observation_types::prelude::*;
+use cursor_hero_ui_inspector_types::prelude::UIData;
+
+pub struct ObservationHistoryEguiPlugin;
+
+impl Plugin for ObservationHistoryEguiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            gui.run_if(
+                |ui_data: Res<UIData>, history: Option<Res<ObservationHistory>>| {
+                    history.is_some()
+                        && ui_data.windows.global_toggle
+                        && ui_data.windows.observation_history.open
+                },
+            ),
+        );
+    }
+}
+
+/// The filters being edited and the results of the last search
+struct HistorySearch {
+    observer: Option<String>,
+    kinds: Vec<ObservationKind>,
+    text: String,
+    /// 0 searches all time
+    hours: u32,
+    limit: usize,
+    observers: Vec<String>,
+    results: Vec<ObservationHistoryRow>,
+    error: Option<String>,
+    stale: bool,
+}
+
+impl Default for HistorySearch {
+    fn default() -> Self {
+        Self {
+            observer: None,
+            kinds: ObservationKind::ALL.to_vec(),
+            text: String::new(),
+            hours: 24,
+            limit: 100,
+            observers: Vec::new(),
+            results: Vec::new(),
+            error: None,
+            stale: true,
+        }
+    }
+}
+
+impl HistorySearch {
+    fn query(&self) -> ObservationHistoryQuery {
+        ObservationHistoryQuery {
+            observer: self.observer.clone(),
+            since: match self.hours {
+                0 => None,
+                hours => Some(chrono::Local::now() - chrono::Duration::hours(hours as i64)),
+            },
+            kinds: self.kinds.clone(),
+            text: Some(self.text.clone()).filter(|text| !text.is_empty()),
+            limit: self.limit,
+            ..default()
+        }
+    }
+
+    fn refresh(&mut self, history: &ObservationHistory) {
+        self.stale = false;
+        self.error = None;
+        match history.observers() {
+            Ok(observers) => self.observers = observers,
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+        // nothing would match, skip the query
+        if self.kinds.is_empty() {
+            self.results.clear();
+            return;
+        }
+        match history.query(&self.query()) {
+            Ok(results) => self.results = results,
+            Err(e) => self.error = Some(format!("{:?}", e)),
+        }
+    }
+}
+
+fn gui(
+    mut contexts: EguiContexts,
+    mut ui_data: ResMut<UIData>,
+    history: Res<ObservationHistory>,
+    mut search: Local<HistorySearch>,
+) {
+    if search.stale {
+        search.refresh(&history);
+    }
+
+    // Get context
+    let ctx = contexts.ctx_mut();
+
+    // Do window
+    let window_id = egui::Id::new("Observation History");
+    egui::Window::new("Observation History")
+        .id(window_id)
+        .default_open(ui_data.windows.observation_history.header_open)
+        .show(ctx, |ui| {
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.label("Observer");
+                egui::ComboBox::from_id_source(window_id.with("observer"))
+                    .selected_text(search.observer.as_deref().unwrap_or("Everyone"))
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut search.observer, None, "Everyone")
+                            .changed();
+                        for observer in search.observers.clone() {
+                            let label = observer.clone();
+                            changed |= ui
+                                .selectable_value(&mut search.observer, Some(observer), label)
+                                .changed();
+                        }
+                    });
+            });
+            ui.horizontal_wrapped(|ui| {
+                for kind in ObservationKind::ALL {
+                    let mut included = search.kinds.contains(&kind);
+                    if ui.checkbox(&mut included, kind.as_str()).changed() {
+                        search.kinds.retain(|k| *k != kind);
+                        if included {
+                            search.kinds.push(kind);
+                        }
+                        changed = true;
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Text");
+                changed |= ui.text_edit_singleline(&mut search.text).changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label("Last");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut search.hours).suffix(" hours"))
+                    .on_hover_text("0 searches all time")
+                    .changed();
+                ui.label("Limit");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut search.limit).clamp_range(1..=1000))
+                    .changed();
+                changed |= ui.button("Refresh").clicked();
+            });
+            if changed {
+                search.stale = true;
+            }
+            if let Some(error) = &search.error {
+                ui.colored_label(ui.visuals().error_fg_color, error.as_str());
+            }
+            ui.separator();
+
+            ui.label(format!("{} observations", search.results.len()));
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for row in search.results.iter().rev() {
+                        ui.label(format!(
+                            "{} {} [{}] {}",
+                            row.entry.datetime.format("%Y-%m-%d %H:%M:%S"),
+                            row.observer,
+                            row.entry.origin.kind().as_str(),
+                            row.entry.origin
+                        ));
+                    }
+                });
+        });
+
+    // Track window collapsed state
+    ui_data.windows.observation_history.header_open =
+        CollapsingState::load(ctx, window_id.with("collapsing"))
+            .map(|x| x.is_open())
+            .unwrap_or(ui_data.windows.observation_history.header_open);
+}
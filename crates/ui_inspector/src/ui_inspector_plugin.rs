@@ -1,3 +1,6 @@
+use crate::inference_metrics_egui_plugin::InferenceMetricsEguiPlugin;
+use crate::memory_egui_plugin::MemoryEguiPlugin;
+use crate::observation_history_egui_plugin::ObservationHistoryEguiPlugin;
 use crate::ui_inspector_children_fetcher_plugin::UiInspectorChildrenFetcherPlugin;
 use crate::ui_inspector_hover_indicator_click_plugin::UiInspectorHoverIndicatorClickPlugin;
 use crate::ui_inspector_paused_egui_plugin::UiInspectorPausedEguiPlugin;
@@ -9,6 +12,7 @@ use crate::ui_inspector_toggle_plugin::UiInspectorTogglePlugin;
 use crate::ui_inspector_tree_egui_plugin::UiInspectorTreeEguiPlugin;
 use crate::ui_inspector_tree_update_plugin::UiInspectorTreeUpdatePlugin;
 use crate::ui_inspector_worker_plugin::UiInspectorWorkerPlugin;
+use crate::worker_status_egui_plugin::WorkerStatusEguiPlugin;
 use cursor_hero_input::active_input_state_plugin::InputMethod;
 
 use bevy::prelude::*;
@@ -31,6 +35,10 @@ impl Plugin for UiInspectorPlugin {
         app.add_plugins(UiInspectorPausedEguiPlugin);
         app.add_plugins(UiInspectorPropertiesEguiPlugin);
         app.add_plugins(UiInspectorScratchPadEguiPlugin);
+        app.add_plugins(InferenceMetricsEguiPlugin);
+        app.add_plugins(ObservationHistoryEguiPlugin);
+        app.add_plugins(WorkerStatusEguiPlugin);
+        app.add_plugins(MemoryEguiPlugin);
 
         // must be after the default plugins
         app.add_plugins(
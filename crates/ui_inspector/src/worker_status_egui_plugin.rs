@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use bevy_egui::egui;
+use bevy_egui::egui::collapsing_header::CollapsingState;
+use bevy_egui::EguiContexts;
+use cursor_hero_ui_inspector_types::prelude::UIData;
+use cursor_hero_worker::prelude::*;
+
+pub struct WorkerStatusEguiPlugin;
+
+impl Plugin for WorkerStatusEguiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorkerStatuses>();
+        app.add_systems(
+            Update,
+            gui.run_if(|ui_data: Res<UIData>| {
+                ui_data.windows.global_toggle && ui_data.windows.worker_status.open
+            }),
+        );
+    }
+}
+
+fn gui(mut contexts: EguiContexts, mut ui_data: ResMut<UIData>, statuses: Res<WorkerStatuses>) {
+    // Get context
+    let ctx = contexts.ctx_mut();
+
+    // Do window
+    let window_id = egui::Id::new("Workers");
+    egui::Window::new("Workers")
+        .id(window_id)
+        .default_open(ui_data.windows.worker_status.header_open)
+        .show(ctx, |ui| {
+            let mut workers = statuses.workers.iter().collect::<Vec<_>>();
+            workers.sort_by_key(|(name, _)| name.as_str());
+            egui::Grid::new(window_id.with("workers"))
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, status) in workers {
+                        ui.label(name);
+                        match status {
+                            WorkerStatus::Running => {
+                                ui.colored_label(egui::Color32::GREEN, "Running");
+                            }
+                            WorkerStatus::Stopped => {
+                                ui.label("Stopped");
+                            }
+                            WorkerStatus::Crashed {
+                                last_error,
+                                crashes,
+                            } => {
+                                ui.colored_label(
+                                    egui::Color32::RED,
+                                    format!("Crashed ({}x)", crashes),
+                                )
+                                .on_hover_text(last_error);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+    // Track window collapsed state
+    ui_data.windows.worker_status.header_open =
+        CollapsingState::load(ctx, window_id.with("collapsing"))
+            .map(|x| x.is_open())
+            .unwrap_or(ui_data.windows.worker_status.header_open);
+}
@@ -78,6 +78,14 @@ pub struct InspectorWindows {
     pub tree: EguiWindow,
     pub properties: EguiWindow,
     pub scratch_pad: EguiWindow,
+    #[serde(default)]
+    pub inference_metrics: EguiWindow,
+    #[serde(default)]
+    pub observation_history: EguiWindow,
+    #[serde(default)]
+    pub worker_status: EguiWindow,
+    #[serde(default)]
+    pub memory: EguiWindow,
 }
 
 pub struct InspectorWindowsIter<'a> {
@@ -104,7 +112,6 @@ impl InspectorWindows {
             _marker: PhantomData,
         }
     }
-
 }
 
 impl<'a> Iterator for InspectorWindowsIter<'a> {
@@ -117,6 +124,10 @@ impl<'a> Iterator for InspectorWindowsIter<'a> {
             2 => Some(&self.windows.tree),
             3 => Some(&self.windows.properties),
             4 => Some(&self.windows.scratch_pad),
+            5 => Some(&self.windows.inference_metrics),
+            6 => Some(&self.windows.observation_history),
+            7 => Some(&self.windows.worker_status),
+            8 => Some(&self.windows.memory),
             _ => None,
         };
         self.index += 1;
@@ -135,6 +146,10 @@ impl<'a> Iterator for InspectorWindowsIterMut<'a> {
                 2 => Some(&mut (*self.windows).tree),
                 3 => Some(&mut (*self.windows).properties),
                 4 => Some(&mut (*self.windows).scratch_pad),
+                5 => Some(&mut (*self.windows).inference_metrics),
+                6 => Some(&mut (*self.windows).observation_history),
+                7 => Some(&mut (*self.windows).worker_status),
+                8 => Some(&mut (*self.windows).memory),
                 _ => None,
             };
             self.index += 1;
@@ -152,6 +167,10 @@ impl Default for InspectorWindows {
             tree: EguiWindow::default(),
             properties: EguiWindow::default(),
             scratch_pad: EguiWindow::default(),
+            inference_metrics: EguiWindow::default(),
+            observation_history: EguiWindow::default(),
+            worker_status: EguiWindow::default(),
+            memory: EguiWindow::default(),
         }
     }
 }
@@ -4,6 +4,7 @@ use bevy::log::info;
 use crossbeam_channel::Sender;
 use cursor_hero_secret_types::secrets_types::SecretString;
 use cursor_hero_voice_to_text_types::prelude::*;
+use cursor_hero_worker::prelude::send_gamebound;
 use futures_util::stream::StreamExt;
 use futures_util::SinkExt;
 use rand::distributions::Alphanumeric;
@@ -188,7 +189,7 @@ pub(crate) async fn connect_receiver(
                                     transcription: concatenated_text,
                                 };
                                 debug!("Sending transcription to game: {:?}", msg);
-                                if let Err(e) = game_tx.send(msg) {
+                                if let Err(e) = send_gamebound(&game_tx, msg).await {
                                     error!("Failed to send transcription to game: {:?}", e);
                                 }
                             }
@@ -1,10 +1,39 @@
+use bevy::app::AppExit;
 use bevy::prelude::*;
 use crossbeam_channel::bounded;
+use crossbeam_channel::SendError;
+use crossbeam_channel::TryRecvError;
+use crossbeam_channel::TrySendError;
 use cursor_hero_worker_types::prelude::*;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::task::JoinSet;
 
 use windows::Win32::System::Com::CoInitializeEx;
 use windows::Win32::System::Com::COINIT_MULTITHREADED;
+
+/// How often a thread with an async handler checks for new messages while others are in flight
+const ASYNC_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Like [`Sender::send`], but async handlers wait for room in a full channel without blocking their other in-flight messages
+pub async fn send_gamebound<G>(reply_tx: &Sender<G>, mut msg: G) -> Result<(), SendError<G>> {
+    loop {
+        match reply_tx.try_send(msg) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Full(returned)) => {
+                msg = returned;
+                tokio::time::sleep(ASYNC_POLL_INTERVAL).await;
+            }
+            Err(TrySendError::Disconnected(returned)) => return Err(SendError(returned)),
+        }
+    }
+}
+
 pub struct WorkerPlugin<T, G, S, E, EE, EEE>
 where
     T: WorkerMessage,
@@ -27,17 +56,73 @@ where
         // TODO: conditionally register if T or G support it
         // app.register_type::<T>();
         // app.register_type::<G>();
+        if !app.is_plugin_added::<WorkerTypesPlugin>() {
+            app.add_plugins(WorkerTypesPlugin);
+        }
         app.add_event::<T>();
         app.add_event::<G>();
         app.insert_resource(self.config.clone());
         app.add_systems(Startup, create_worker_thread::<T, G, S, E, EE, EEE>);
         app.add_systems(Update, bridge_requests::<T, G, S, E, EE, EEE>);
         app.add_systems(Update, bridge_responses::<T, G, S, E, EE, EEE>);
+        app.add_systems(Update, monitor_worker_thread::<T, G, S, E, EE, EEE>);
+        app.add_systems(
+            Last,
+            shutdown_worker_thread::<T, G, S, E, EE, EEE>.in_set(WorkerShutdownSet::Signal),
+        );
+        // shared by every worker
+        if !app.world.contains_resource::<StoppingWorkers>() {
+            app.init_resource::<StoppingWorkers>();
+            app.configure_sets(
+                Last,
+                WorkerShutdownSet::Signal.before(WorkerShutdownSet::Join),
+            );
+            app.add_systems(Last, join_worker_threads.in_set(WorkerShutdownSet::Join));
+        }
     }
 }
 
+/// Every worker is signalled to stop before any of them is waited on
+#[derive(SystemSet, Clone, Hash, Debug, PartialEq, Eq)]
+enum WorkerShutdownSet {
+    Signal,
+    Join,
+}
+
+struct StoppingWorker {
+    name: String,
+    handle: Option<JoinHandle<Result<(), String>>>,
+}
+
+/// Workers signalled to stop, joined together against the longest [`WorkerConfig::shutdown_timeout`]
+#[derive(Resource, Default)]
+struct StoppingWorkers {
+    workers: Vec<StoppingWorker>,
+    timeout: Duration,
+}
+
+/// The thread of a worker and what it takes to restart it
+#[derive(Resource)]
+struct WorkerLifecycle<T, G, S, E, EE, EEE>
+where
+    T: WorkerMessage,
+    G: WorkerMessage,
+{
+    // the thread's ends of the bridge, restarted threads pick up the messages queued while it was down
+    thread_rx: Receiver<T>,
+    game_tx: Sender<G>,
+    handle: Option<JoinHandle<Result<(), String>>>,
+    started: Instant,
+    shutdown: Arc<AtomicBool>,
+    /// Consecutive crashes, for the restart backoff
+    crashes: u32,
+    restart_at: Option<Instant>,
+    _type_holder: PhantomHolder<T, G, S, E, EE, EEE>,
+}
+
 fn create_worker_thread<T, G, S, E, EE, EEE>(
     config: Res<WorkerConfig<T, G, S, E, EE, EEE>>,
+    mut statuses: ResMut<WorkerStatuses>,
     mut commands: Commands,
 ) where
     T: WorkerMessage,
@@ -55,13 +140,46 @@ fn create_worker_thread<T, G, S, E, EE, EEE>(
         receiver: game_rx,
     });
 
+    let mut lifecycle = WorkerLifecycle {
+        thread_rx,
+        game_tx,
+        handle: None,
+        started: Instant::now(),
+        shutdown: Arc::new(AtomicBool::new(false)),
+        crashes: 0,
+        restart_at: None,
+        _type_holder: PhantomHolder::<T, G, S, E, EE, EEE>::default(),
+    };
+    start_worker_thread(&config, &mut lifecycle, &mut statuses);
+    commands.insert_resource(lifecycle);
+}
+
+fn start_worker_thread<T, G, S, E, EE, EEE>(
+    config: &WorkerConfig<T, G, S, E, EE, EEE>,
+    lifecycle: &mut WorkerLifecycle<T, G, S, E, EE, EEE>,
+    statuses: &mut WorkerStatuses,
+) where
+    T: WorkerMessage,
+    G: WorkerMessage,
+    S: WorkerState,
+    E: WorkerError,
+    EE: WorkerError,
+    EEE: WorkerError,
+{
     let name = config.name.clone();
     let handler = config.handle_threadbound_message;
+    let async_handler = config.handle_threadbound_message_async;
+    let max_in_flight = config.max_in_flight.max(1);
     let handler_error_handler = config.handle_threadbound_message_error_handler;
     let sleep_duration = config.sleep_duration;
     let is_ui_automation_thread = config.is_ui_automation_thread;
     let receiver = config.threadbound_message_receiver;
-    if let Err(e) = thread::Builder::new().name(name.clone()).spawn(move || {
+    let thread_rx = lifecycle.thread_rx.clone();
+    let game_tx = lifecycle.game_tx.clone();
+    let shutdown = lifecycle.shutdown.clone();
+    lifecycle.started = Instant::now();
+    lifecycle.restart_at = None;
+    match thread::Builder::new().name(name.clone()).spawn(move || {
         if is_ui_automation_thread {
             unsafe {
                 // Initialize COM in MTA mode
@@ -74,19 +192,40 @@ fn create_worker_thread<T, G, S, E, EE, EEE>(
             }
         }
 
+        let rt = match tokio::runtime::Runtime::new() {
+            Ok(rt) => rt,
+            Err(e) => return Err(format!("Failed to create runtime: {:?}", e)),
+        };
+        if let Some(async_handler) = async_handler {
+            let local = tokio::task::LocalSet::new();
+            return local.block_on(
+                &rt,
+                handle_messages_concurrently(
+                    &name,
+                    async_handler,
+                    handler_error_handler,
+                    max_in_flight,
+                    &thread_rx,
+                    &game_tx,
+                    &shutdown,
+                ),
+            );
+        }
+
         let Ok(mut state) = S::try_default() else {
             error!("[{}] Failed to initialize state", name);
-            return;
+            return Err("Failed to initialize state".to_string());
         };
 
-        let rt = tokio::runtime::Runtime::new().unwrap();
         rt.block_on(async {
-            loop {
+            while !shutdown.load(Ordering::Relaxed) {
                 let msg = match (receiver)(&thread_rx, &mut state) {
                     Ok(msg) => msg,
+                    // the bridge is dropped on shutdown, which disconnects the channel
+                    Err(_) if shutdown.load(Ordering::Relaxed) => break,
                     Err(e) => {
                         error!("[{}] Threadbound channel receiver failure: {:?}, quitting loop", name, e);
-                        break;
+                        return Err(format!("Threadbound channel receiver failure: {:?}", e));
                     }
                 };
                 if let Err(e) = (handler)(&msg, &game_tx, &mut state) {
@@ -104,17 +243,244 @@ fn create_worker_thread<T, G, S, E, EE, EEE>(
                 }
                 std::thread::sleep(sleep_duration);
             }
-        });
+            debug!("[{}] Thread shut down", name);
+            Ok(())
+        })
     }) {
-        error!("[{}] Failed to spawn thread: {:?}", config.name, e);
-    } else {
-        info!("[{}] Thread created", config.name);
+        Ok(handle) => {
+            info!("[{}] Thread created", config.name);
+            lifecycle.handle = Some(handle);
+            statuses
+                .workers
+                .insert(config.name.clone(), WorkerStatus::Running);
+        }
+        Err(e) => {
+            error!("[{}] Failed to spawn thread: {:?}", config.name, e);
+            record_crash(config, lifecycle, statuses, e.to_string());
+        }
+    }
+}
+
+/// Works on up to `max_in_flight` messages at once, each with a state of its own
+async fn handle_messages_concurrently<T, G, S, E, EE>(
+    name: &str,
+    handler: AsyncThreadboundMessageHandler<T, G, S, E>,
+    error_handler: ThreadboundMessageErrorHandler<T, G, S, E, EE>,
+    max_in_flight: usize,
+    thread_rx: &Receiver<T>,
+    game_tx: &Sender<G>,
+    shutdown: &AtomicBool,
+) -> Result<(), String>
+where
+    T: WorkerMessage,
+    G: WorkerMessage,
+    S: WorkerState,
+    E: WorkerError,
+    EE: WorkerError,
+{
+    let mut idle_states = Vec::new();
+    let mut in_flight = JoinSet::new();
+    while !shutdown.load(Ordering::Relaxed) {
+        while in_flight.len() < max_in_flight {
+            let msg = match thread_rx.try_recv() {
+                Ok(msg) => msg,
+                Err(TryRecvError::Empty) => break,
+                // the bridge is dropped on shutdown, which disconnects the channel
+                Err(TryRecvError::Disconnected) if shutdown.load(Ordering::Relaxed) => {
+                    return Ok(());
+                }
+                Err(e) => {
+                    error!(
+                        "[{}] Threadbound channel receiver failure: {:?}, quitting loop",
+                        name, e
+                    );
+                    return Err(format!("Threadbound channel receiver failure: {:?}", e));
+                }
+            };
+            let mut state = match idle_states.pop() {
+                Some(state) => state,
+                None => S::try_default().map_err(|_| {
+                    error!("[{}] Failed to initialize state", name);
+                    "Failed to initialize state".to_string()
+                })?,
+            };
+            let name = name.to_string();
+            let game_tx = game_tx.clone();
+            in_flight.spawn_local(async move {
+                if let Err(e) = (handler)(&msg, &game_tx, &mut state).await {
+                    error!(
+                        "[{}] Failed to process thread message {:?}, got error {:?}",
+                        name, msg, e
+                    );
+                    if let Err(ee) = (error_handler)(&msg, &game_tx, &mut state, &e) {
+                        error!(
+                            "[{}] BAD NEWS! Failed while processing error handler for message {:?} that produced error {:?}, got new error {:?}",
+                            name, msg, e, ee
+                        );
+                    }
+                }
+                state
+            });
+        }
+        // new messages are picked up between completions
+        match tokio::time::timeout(ASYNC_POLL_INTERVAL, in_flight.join_next()).await {
+            Ok(Some(Ok(state))) => idle_states.push(state),
+            Ok(Some(Err(e))) => return Err(format!("Message handler panicked: {:?}", e)),
+            Ok(None) => tokio::time::sleep(ASYNC_POLL_INTERVAL).await,
+            Err(_) => {}
+        }
+    }
+    debug!("[{}] Thread shut down", name);
+    Ok(())
+}
+
+fn record_crash<T, G, S, E, EE, EEE>(
+    config: &WorkerConfig<T, G, S, E, EE, EEE>,
+    lifecycle: &mut WorkerLifecycle<T, G, S, E, EE, EEE>,
+    statuses: &mut WorkerStatuses,
+    last_error: String,
+) where
+    T: WorkerMessage,
+    G: WorkerMessage,
+{
+    // a thread that ran for a while before dying starts the backoff over
+    if lifecycle.started.elapsed() > config.max_restart_backoff {
+        lifecycle.crashes = 0;
+    }
+    lifecycle.crashes += 1;
+    let delay = config.restart_delay(lifecycle.crashes);
+    warn!(
+        "[{}] Thread died with {:?}, restarting in {:?}",
+        config.name, last_error, delay
+    );
+    lifecycle.restart_at = Some(Instant::now() + delay);
+    statuses.workers.insert(
+        config.name.clone(),
+        WorkerStatus::Crashed {
+            last_error,
+            crashes: lifecycle.crashes,
+        },
+    );
+}
+
+/// Restarts the thread with a backoff when it dies while the app is running
+fn monitor_worker_thread<T, G, S, E, EE, EEE>(
+    config: Res<WorkerConfig<T, G, S, E, EE, EEE>>,
+    lifecycle: Option<ResMut<WorkerLifecycle<T, G, S, E, EE, EEE>>>,
+    mut statuses: ResMut<WorkerStatuses>,
+) where
+    T: WorkerMessage,
+    G: WorkerMessage,
+    S: WorkerState,
+    E: WorkerError,
+    EE: WorkerError,
+    EEE: WorkerError,
+{
+    let Some(mut lifecycle) = lifecycle else {
+        return;
+    };
+    if lifecycle.shutdown.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(handle) = lifecycle.handle.take() {
+        if !handle.is_finished() {
+            lifecycle.handle = Some(handle);
+            return;
+        }
+        let last_error = match handle.join() {
+            Ok(Ok(())) => "Thread stopped unexpectedly".to_string(),
+            Ok(Err(e)) => e,
+            Err(panic) => match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+                (Some(message), _) => format!("Thread panicked: {}", message),
+                (_, Some(message)) => format!("Thread panicked: {}", message),
+                _ => "Thread panicked".to_string(),
+            },
+        };
+        record_crash(&config, &mut lifecycle, &mut statuses, last_error);
+    }
+    if lifecycle
+        .restart_at
+        .is_some_and(|restart_at| Instant::now() >= restart_at)
+    {
+        info!("[{}] Restarting thread", config.name);
+        start_worker_thread(&config, &mut lifecycle, &mut statuses);
+    }
+}
+
+/// Signals the thread to stop when the app exits, [`join_worker_threads`] waits for it
+fn shutdown_worker_thread<T, G, S, E, EE, EEE>(world: &mut World)
+where
+    T: WorkerMessage,
+    G: WorkerMessage,
+    S: WorkerState,
+    E: WorkerError,
+    EE: WorkerError,
+    EEE: WorkerError,
+{
+    if world.resource::<Events<AppExit>>().is_empty() {
+        return;
+    }
+    let Some(mut lifecycle) = world.remove_resource::<WorkerLifecycle<T, G, S, E, EE, EEE>>()
+    else {
+        return;
+    };
+    let config = world
+        .resource::<WorkerConfig<T, G, S, E, EE, EEE>>()
+        .clone();
+    lifecycle.shutdown.store(true, Ordering::Relaxed);
+    // disconnects the channel to wake up receivers waiting on it
+    world.remove_resource::<Bridge<T, G>>();
+
+    let mut stopping = world.resource_mut::<StoppingWorkers>();
+    stopping.timeout = stopping.timeout.max(config.shutdown_timeout);
+    stopping.workers.push(StoppingWorker {
+        name: config.name,
+        handle: lifecycle.handle.take(),
+    });
+}
+
+/// Waits for every signalled thread at once, up to the longest [`WorkerConfig::shutdown_timeout`] among them
+fn join_worker_threads(
+    mut stopping: ResMut<StoppingWorkers>,
+    mut statuses: ResMut<WorkerStatuses>,
+) {
+    if stopping.workers.is_empty() {
+        return;
+    }
+    let timeout = std::mem::take(&mut stopping.timeout);
+    let deadline = Instant::now() + timeout;
+    let running = |worker: &StoppingWorker| {
+        worker
+            .handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    };
+    while stopping.workers.iter().any(running) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    for worker in stopping.workers.drain(..) {
+        match worker.handle {
+            Some(handle) if !handle.is_finished() => {
+                warn!(
+                    "[{}] Thread did not stop within {:?}, leaving it behind",
+                    worker.name, timeout
+                );
+            }
+            Some(handle) => {
+                if let Ok(Err(e)) = handle.join() {
+                    warn!("[{}] Thread failed while shutting down: {}", worker.name, e);
+                }
+                info!("[{}] Thread stopped", worker.name);
+            }
+            None => {}
+        }
+        statuses.workers.insert(worker.name, WorkerStatus::Stopped);
     }
 }
 
 fn bridge_requests<T, G, S, E, EE, EEE>(
     config: Res<WorkerConfig<T, G, S, E, EE, EEE>>,
-    bridge: ResMut<Bridge<T, G>>,
+    bridge: Option<Res<Bridge<T, G>>>,
     mut events: EventReader<T>,
 ) where
     T: WorkerMessage,
@@ -124,6 +490,10 @@ fn bridge_requests<T, G, S, E, EE, EEE>(
     EE: WorkerError,
     EEE: WorkerError,
 {
+    let Some(bridge) = bridge else {
+        events.clear();
+        return;
+    };
     for event in events.read() {
         trace!("[{}] Bevy => Thread: {:?}", config.name, event);
         if let Err(e) = bridge.sender.try_send(event.clone()) {
@@ -147,7 +517,7 @@ fn bridge_requests<T, G, S, E, EE, EEE>(
 
 fn bridge_responses<T, G, S, E, EE, EEE>(
     config: Res<WorkerConfig<T, G, S, E, EE, EEE>>,
-    bridge: ResMut<Bridge<T, G>>,
+    bridge: Option<Res<Bridge<T, G>>>,
     mut events: EventWriter<G>,
 ) where
     T: WorkerMessage,
@@ -157,6 +527,9 @@ fn bridge_responses<T, G, S, E, EE, EEE>(
     EE: WorkerError,
     EEE: WorkerError,
 {
+    let Some(bridge) = bridge else {
+        return;
+    };
     for msg in bridge.receiver.try_iter() {
         trace!("[{}] Thread => Bevy: {:?}", config.name, msg);
         events.send(msg);
@@ -1,9 +1,12 @@
 // use anyhow::Error;
 // use anyhow::Result;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
 pub use crossbeam_channel::Receiver;
 pub use crossbeam_channel::Sender;
+use std::future::Future;
 use std::marker::PhantomData;
+use std::pin::Pin;
 
 #[derive(Resource)]
 pub struct Bridge<T, G>
@@ -44,6 +47,12 @@ pub type ThreadboundMessageErrorHandler<T, G, S, ErrorFromMsgHandling, ErrorFrom
         error: &ErrorFromMsgHandling,
     ) -> Result<(), ErrorFromErrorHandling>;
 
+/// A future of an async handler, it stays on the worker thread so it doesn't need to be `Send`
+pub type WorkerFuture<'a, E> = Pin<Box<dyn Future<Output = Result<(), E>> + 'a>>;
+
+pub type AsyncThreadboundMessageHandler<T, G, S, E> =
+    for<'a> fn(msg: &'a T, reply_tx: &'a Sender<G>, state: &'a mut S) -> WorkerFuture<'a, E>;
+
 pub type ThreadboundMessageReceiver<T, S, E> =
     fn(thread_rx: &Receiver<T>, state: &mut S) -> Result<T, E>;
 
@@ -96,10 +105,22 @@ pub struct WorkerConfig<
     pub is_ui_automation_thread: bool,
     pub threadbound_message_receiver: ThreadboundMessageReceiver<T, S, ErrorFromMessageReceiving>,
     pub handle_threadbound_message: ThreadboundMessageHandler<T, G, S, ErrorFromMessageHandling>,
+    /// Used instead of `handle_threadbound_message` when set.
+    ///
+    /// Messages are taken straight from the channel and handled concurrently, each in-flight message gets a state of its own.
+    pub handle_threadbound_message_async:
+        Option<AsyncThreadboundMessageHandler<T, G, S, ErrorFromMessageHandling>>,
+    /// How many messages an async handler works on at once
+    pub max_in_flight: usize,
     pub handle_threadbound_message_error_handler:
         ThreadboundMessageErrorHandler<T, G, S, ErrorFromMessageHandling, ErrorFromErrorHandling>,
     pub gamebound_channel_capacity: usize,
     pub threadbound_channel_capacity: usize,
+    /// How long the app waits for the thread to finish when exiting
+    pub shutdown_timeout: std::time::Duration,
+    /// Delay before restarting a crashed thread, doubled for every consecutive crash
+    pub restart_backoff: std::time::Duration,
+    pub max_restart_backoff: std::time::Duration,
     pub type_holder: PhantomHolder<
         T,
         G,
@@ -121,6 +142,8 @@ where
             is_ui_automation_thread: false,
             sleep_duration: std::time::Duration::ZERO,
             handle_threadbound_message: |_, _, _| Ok(()),
+            handle_threadbound_message_async: None,
+            max_in_flight: 8,
             handle_threadbound_message_error_handler: |_, _, _, _| Ok(()),
             threadbound_message_receiver: |thread_rx, _state| {
                 thread_rx
@@ -129,6 +152,9 @@ where
             },
             gamebound_channel_capacity: 10,
             threadbound_channel_capacity: 10,
+            shutdown_timeout: std::time::Duration::from_secs(2),
+            restart_backoff: std::time::Duration::from_secs(1),
+            max_restart_backoff: std::time::Duration::from_secs(60),
             type_holder: PhantomHolder::<T, G, S, _, _, _>::default(),
         }
     }
@@ -141,10 +167,63 @@ impl<T, G, S, E, EE, EEE> Clone for WorkerConfig<T, G, S, E, EE, EEE> {
             is_ui_automation_thread: self.is_ui_automation_thread,
             threadbound_message_receiver: self.threadbound_message_receiver,
             handle_threadbound_message: self.handle_threadbound_message,
+            handle_threadbound_message_async: self.handle_threadbound_message_async,
+            max_in_flight: self.max_in_flight,
             handle_threadbound_message_error_handler: self.handle_threadbound_message_error_handler,
             gamebound_channel_capacity: self.gamebound_channel_capacity,
             threadbound_channel_capacity: self.threadbound_channel_capacity,
+            shutdown_timeout: self.shutdown_timeout,
+            restart_backoff: self.restart_backoff,
+            max_restart_backoff: self.max_restart_backoff,
             type_holder: self.type_holder.clone(),
         }
     }
 }
+
+impl<T, G, S, E, EE, EEE> WorkerConfig<T, G, S, E, EE, EEE> {
+    /// Delay before the next restart after `crashes` consecutive crashes
+    pub fn restart_delay(&self, crashes: u32) -> std::time::Duration {
+        let exponent = crashes.saturating_sub(1).min(16);
+        self.restart_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_restart_backoff)
+    }
+}
+
+#[derive(Debug, Reflect, Clone, PartialEq, Eq)]
+pub enum WorkerStatus {
+    Running,
+    /// Shut down with the app
+    Stopped,
+    /// The thread died on its own, it is restarted after a delay
+    Crashed {
+        last_error: String,
+        crashes: u32,
+    },
+}
+
+/// The [`WorkerStatus`] of every worker thread by [`WorkerConfig::name`]
+#[derive(Resource, Reflect, Debug, Default, Clone)]
+#[reflect(Resource)]
+pub struct WorkerStatuses {
+    pub workers: HashMap<String, WorkerStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Event, Debug, Clone)]
+    struct Message;
+
+    #[test]
+    fn restart_delays() {
+        let config = WorkerConfig::<Message, Message, (), _, _, _>::default();
+        assert_eq!(config.restart_delay(1), std::time::Duration::from_secs(1));
+        assert_eq!(config.restart_delay(3), std::time::Duration::from_secs(4));
+        assert_eq!(
+            config.restart_delay(100),
+            std::time::Duration::from_secs(60)
+        );
+    }
+}
@@ -1,11 +1,12 @@
+use crate::prelude::*;
 use bevy::prelude::*;
-// use crate::prelude::*;
 
 pub struct WorkerTypesPlugin;
 
 impl Plugin for WorkerTypesPlugin {
-    fn build(&self, _app: &mut App) {
-        // app.register_type::<MyComponent>();
-        // app.add_event::<MyEvent>();
+    fn build(&self, app: &mut App) {
+        app.register_type::<WorkerStatus>();
+        app.register_type::<WorkerStatuses>();
+        app.init_resource::<WorkerStatuses>();
     }
 }